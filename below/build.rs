@@ -13,24 +13,33 @@
 // limitations under the License.
 
 use std::env;
+use std::path::Path;
 use std::path::PathBuf;
 
 use libbpf_cargo::SkeletonBuilder;
 
-const SRC: &str = "./src/bpf/exitstat.bpf.c";
+const SRCS: [&str; 2] = ["./src/bpf/exitstat.bpf.c", "./src/bpf/run_delay.bpf.c"];
 
 fn main() {
-    let mut out =
+    let out_dir =
         PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR must be set in build script"));
-    out.push("exitstat.skel.rs");
 
-    let mut builder = SkeletonBuilder::new();
-    builder.source(SRC);
-    if let Some(clang) = option_env!("CLANG") {
-        builder.clang(clang);
+    for src in SRCS {
+        let name = Path::new(src)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".bpf.c"))
+            .expect("BPF source must be named <name>.bpf.c");
+        let out = out_dir.join(format!("{}.skel.rs", name));
+
+        let mut builder = SkeletonBuilder::new();
+        builder.source(src);
+        if let Some(clang) = option_env!("CLANG") {
+            builder.clang(clang);
+        }
+        builder.build_and_generate(out).unwrap();
+        println!("cargo:rerun-if-changed={}", src);
     }
-    builder.build_and_generate(out).unwrap();
-    println!("cargo:rerun-if-changed={}", SRC);
 
     #[cfg(all(feature = "no-vendor", feature = "default"))]
     compile_error!(
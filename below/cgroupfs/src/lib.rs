@@ -35,6 +35,8 @@ use thiserror::Error;
 
 mod types;
 pub use types::*;
+mod v1;
+pub use v1::CgroupV1Reader;
 
 #[cfg(test)]
 mod test;
@@ -53,6 +55,8 @@ pub enum Error {
     UnexpectedLine(PathBuf, String),
     #[error("Not cgroup2 filesystem: {0:?}")]
     NotCgroup2(PathBuf),
+    #[error("No cgroup v1 controllers mounted: {0:?}")]
+    NoCgroupV1Controllers(PathBuf),
     #[error("Pressure metrics not supported: {0:?}")]
     PressureNotSupported(PathBuf),
 }
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::ffi::OsStr;
 use std::fs::File;
@@ -24,12 +25,18 @@ use std::str::FromStr;
 use paste::paste;
 use tempfile::TempDir;
 
+use crate::v1::parse_mountinfo;
+use crate::v1::usec_per_tick;
 use crate::CgroupReader;
+use crate::CgroupV1Reader;
 use crate::CpuMax;
+use crate::CpuStat;
 use crate::Cpuset;
 use crate::Error;
+use crate::IoStat;
 use crate::MemNodes;
 use crate::MemoryNumaStat;
+use crate::MemoryStat;
 
 struct TestCgroup {
     tempdir: TempDir,
@@ -951,3 +958,250 @@ fn test_memory_numa_stat_invalid_format() {
         _ => panic!("Got unexpected error type: {}", err),
     }
 }
+
+/// Fake v1 hierarchy with cpu and cpuacct co-mounted, plus memory, blkio and
+/// pids, described by a mountinfo file in the same tempdir.
+struct TestCgroupV1 {
+    tempdir: TempDir,
+}
+
+impl TestCgroupV1 {
+    fn new() -> TestCgroupV1 {
+        let tempdir = TempDir::new().expect("Failed to create tempdir");
+        let mut mountinfo = String::from(
+            "22 1 0:21 / /sys rw,nosuid,nodev,noexec,relatime shared:7 - sysfs sysfs rw\n",
+        );
+        for (id, controllers) in ["cpu,cpuacct", "memory", "blkio", "pids"]
+            .iter()
+            .enumerate()
+        {
+            let mount_point = tempdir.path().join(controllers);
+            std::fs::create_dir(&mount_point).expect("Failed to create controller mount");
+            mountinfo.push_str(&format!(
+                "{} 25 0:{} / {} rw,nosuid,nodev,noexec,relatime shared:{} - cgroup cgroup rw,{}\n",
+                30 + id,
+                30 + id,
+                mount_point.display(),
+                10 + id,
+                controllers,
+            ));
+        }
+        std::fs::write(tempdir.path().join("mountinfo"), mountinfo)
+            .expect("Failed to write mountinfo");
+        TestCgroupV1 { tempdir }
+    }
+
+    fn get_reader(&self) -> CgroupV1Reader {
+        CgroupV1Reader::from_mountinfo(&self.tempdir.path().join("mountinfo"))
+            .expect("Failed to construct v1 reader")
+    }
+
+    fn create_file_with_content<P: AsRef<Path>>(&self, controllers: &str, p: P, content: &[u8]) {
+        let path = self.tempdir.path().join(controllers).join(p);
+        std::fs::write(&path, content)
+            .unwrap_or_else(|_| panic!("Failed to write to {}", path.display()));
+    }
+}
+
+#[test]
+fn test_v1_parse_mountinfo() {
+    let mounts = parse_mountinfo(
+        "25 22 0:23 / /sys/fs/cgroup ro,nosuid,nodev,noexec shared:8 - tmpfs tmpfs ro,mode=755\n\
+         26 25 0:24 / /sys/fs/cgroup/unified rw,nosuid,nodev,noexec,relatime shared:9 - cgroup2 cgroup2 rw\n\
+         27 25 0:25 / /sys/fs/cgroup/systemd rw,nosuid,nodev,noexec,relatime shared:10 - cgroup cgroup rw,xattr,name=systemd\n\
+         30 25 0:28 / /sys/fs/cgroup/cpu,cpuacct rw,nosuid,nodev,noexec,relatime shared:13 - cgroup cgroup rw,cpu,cpuacct\n\
+         31 25 0:29 / /sys/fs/cgroup/memory rw,nosuid,nodev,noexec,relatime shared:14 - cgroup cgroup rw,memory\n",
+    );
+    assert_eq!(
+        mounts,
+        BTreeMap::from([
+            (
+                "cpu".to_owned(),
+                PathBuf::from("/sys/fs/cgroup/cpu,cpuacct")
+            ),
+            (
+                "cpuacct".to_owned(),
+                PathBuf::from("/sys/fs/cgroup/cpu,cpuacct")
+            ),
+            ("memory".to_owned(), PathBuf::from("/sys/fs/cgroup/memory")),
+        ])
+    );
+}
+
+#[test]
+fn test_v1_no_controllers() {
+    let tempdir = TempDir::new().expect("Failed to create tempdir");
+    let mountinfo = tempdir.path().join("mountinfo");
+    std::fs::write(
+        &mountinfo,
+        "26 25 0:24 / /sys/fs/cgroup rw,nosuid,nodev,noexec,relatime shared:9 - cgroup2 cgroup2 rw\n",
+    )
+    .expect("Failed to write mountinfo");
+    match CgroupV1Reader::from_mountinfo(&mountinfo) {
+        Err(Error::NoCgroupV1Controllers(_)) => {}
+        Err(e) => panic!("Got unexpected error type {}", e),
+        Ok(_) => panic!("Did not fail without v1 controllers"),
+    }
+}
+
+#[test]
+fn test_v1_new_from_root() {
+    let cgroup = TestCgroupV1::new();
+    // Symlinks to co-mounted controllers and other files are fine
+    std::os::unix::fs::symlink(
+        cgroup.tempdir.path().join("cpu,cpuacct"),
+        cgroup.tempdir.path().join("cpu"),
+    )
+    .expect("Failed to create symlink");
+    cgroup.create_file_with_content("memory", "memory.usage_in_bytes", b"1048576\n");
+    cgroup.create_file_with_content("pids", "pids.current", b"3\n");
+    let reader = CgroupV1Reader::new(cgroup.tempdir.path().to_path_buf())
+        .expect("Failed to construct v1 reader");
+    assert_eq!(reader.read_memory_current().unwrap(), 1048576);
+    assert_eq!(reader.read_pids_current().unwrap(), 3);
+
+    let tempdir = TempDir::new().expect("Failed to create tempdir");
+    match CgroupV1Reader::new(tempdir.path().to_path_buf()) {
+        Err(Error::NoCgroupV1Controllers(_)) => {}
+        Err(e) => panic!("Got unexpected error type {}", e),
+        Ok(_) => panic!("Did not fail without v1 controllers"),
+    }
+}
+
+#[test]
+fn test_v1_cpu_stat() {
+    let cgroup = TestCgroupV1::new();
+    let reader = cgroup.get_reader();
+    // cpuacct.usage is required
+    assert!(reader.read_cpu_stat().is_err());
+
+    cgroup.create_file_with_content("cpu,cpuacct", "cpuacct.usage", b"123456789\n");
+    let val = reader.read_cpu_stat().expect("Failed to read cpu stat");
+    assert_eq!(
+        val,
+        CpuStat {
+            usage_usec: Some(123456),
+            ..Default::default()
+        }
+    );
+
+    cgroup.create_file_with_content("cpu,cpuacct", "cpuacct.stat", b"user 100\nsystem 20\n");
+    cgroup.create_file_with_content(
+        "cpu,cpuacct",
+        "cpu.stat",
+        b"nr_periods 50\nnr_throttled 5\nthrottled_time 2000000\n",
+    );
+    let val = reader.read_cpu_stat().expect("Failed to read cpu stat");
+    assert_eq!(
+        val,
+        CpuStat {
+            usage_usec: Some(123456),
+            user_usec: Some(100 * usec_per_tick()),
+            system_usec: Some(20 * usec_per_tick()),
+            nr_periods: Some(50),
+            nr_throttled: Some(5),
+            throttled_usec: Some(2000),
+        }
+    );
+}
+
+#[test]
+fn test_v1_memory() {
+    let cgroup = TestCgroupV1::new();
+    cgroup.create_file_with_content("memory", "memory.usage_in_bytes", b"1048576\n");
+    cgroup.create_file_with_content(
+        "memory",
+        "memory.stat",
+        b"cache 1\nrss 2\ntotal_cache 4096\ntotal_rss 8192\ntotal_shmem 1024\ntotal_pgfault 7\n",
+    );
+    let reader = cgroup.get_reader();
+    assert_eq!(reader.read_memory_current().unwrap(), 1048576);
+    let val = reader
+        .read_memory_stat()
+        .expect("Failed to read memory.stat");
+    assert_eq!(
+        val,
+        MemoryStat {
+            anon: Some(8192),
+            file: Some(4096),
+            shmem: Some(1024),
+            pgfault: Some(7),
+            ..Default::default()
+        }
+    );
+
+    cgroup.create_file_with_content("memory", "memory.stat", b"total_rss garbage\n");
+    match reader.read_memory_stat() {
+        Err(Error::UnexpectedLine(_, _)) => {}
+        other => panic!("Got unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_v1_io_stat() {
+    let cgroup = TestCgroupV1::new();
+    cgroup.create_file_with_content(
+        "blkio",
+        "blkio.throttle.io_service_bytes",
+        b"8:0 Read 4096\n8:0 Write 8192\n8:0 Sync 0\n8:0 Async 12288\n8:0 Discard 0\n8:0 Total 12288\n\
+          8:16 Read 1\n8:16 Write 2\nTotal 12291\n",
+    );
+    let val = cgroup
+        .get_reader()
+        .read_io_stat()
+        .expect("Failed to read blkio.throttle.io_service_bytes");
+    assert_eq!(
+        val,
+        BTreeMap::from([
+            (
+                "8:0".to_owned(),
+                IoStat {
+                    rbytes: Some(4096),
+                    wbytes: Some(8192),
+                    dbytes: Some(0),
+                    ..Default::default()
+                }
+            ),
+            (
+                "8:16".to_owned(),
+                IoStat {
+                    rbytes: Some(1),
+                    wbytes: Some(2),
+                    ..Default::default()
+                }
+            ),
+        ])
+    );
+}
+
+#[test]
+fn test_v1_pids_and_children() {
+    let cgroup = TestCgroupV1::new();
+    cgroup.create_file_with_content("pids", "pids.current", b"12\n");
+    cgroup.create_file_with_content("pids", "pids.max", b"max\n");
+    std::fs::create_dir(cgroup.tempdir.path().join("memory/system.slice")).unwrap();
+    std::fs::create_dir(cgroup.tempdir.path().join("pids/system.slice")).unwrap();
+    cgroup.create_file_with_content("pids", "system.slice/pids.current", b"3\n");
+    cgroup.create_file_with_content("memory", "system.slice/memory.stat", b"total_rss 1\n");
+
+    let reader = cgroup.get_reader();
+    assert_eq!(reader.name(), Path::new(""));
+    assert_eq!(reader.read_pids_current().unwrap(), 12);
+    assert_eq!(reader.read_pids_max().unwrap(), -1);
+
+    // Children come from the memory hierarchy
+    let children = reader
+        .child_cgroup_iter()
+        .expect("Failed to get child cgroup iter")
+        .collect::<Vec<_>>();
+    assert_eq!(children.len(), 1);
+    let child = &children[0];
+    assert_eq!(child.name(), Path::new("system.slice"));
+    assert_eq!(child.read_pids_current().unwrap(), 3);
+    assert_eq!(child.read_memory_stat().unwrap().anon, Some(1));
+    // Missing files in other hierarchies show up as NotFound
+    match child.read_io_stat() {
+        Err(Error::IoError(_, e)) if e.kind() == std::io::ErrorKind::NotFound => {}
+        other => panic!("Got unexpected result {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,358 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reader for hosts that still run the cgroup v1 (legacy or hybrid)
+//! hierarchy. Each v1 controller is mounted separately, in a directory of
+//! the cgroup root named after its controllers, and the files of every
+//! controller are read from the same relative path. Stats are mapped onto the cgroup2 types so
+//! that the rest of below does not need to care which version it reads.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use nix::unistd::sysconf;
+use nix::unistd::SysconfVar;
+
+use crate::CpuStat;
+use crate::Error;
+use crate::IoStat;
+use crate::MemoryStat;
+use crate::Result;
+use crate::DEFAULT_CG_ROOT;
+
+/// Controllers that can be mapped onto cgroup2 stats.
+const SUPPORTED_CONTROLLERS: &[&str] = &["cpu", "cpuacct", "memory", "blkio", "pids"];
+
+/// Controllers whose hierarchy is walked to find child cgroups, in order of
+/// preference.
+const TREE_CONTROLLERS: &[&str] = &["memory", "cpuacct", "pids", "blkio"];
+
+/// Returns the mount point of each supported v1 controller found in the
+/// content of a mountinfo file.
+pub(crate) fn parse_mountinfo(content: &str) -> BTreeMap<String, PathBuf> {
+    let mut mounts = BTreeMap::new();
+    for line in content.lines() {
+        // e.g. 35 25 0:30 / /sys/fs/cgroup/cpu,cpuacct rw,relatime shared:15 - cgroup cgroup rw,cpu,cpuacct
+        let (mount, fs) = match line.split_once(" - ") {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let mut fs_items = fs.split_ascii_whitespace();
+        if fs_items.next() != Some("cgroup") {
+            continue;
+        }
+        let super_options = match fs_items.nth(1) {
+            Some(options) => options,
+            None => continue,
+        };
+        let mount_point = match mount.split_ascii_whitespace().nth(4) {
+            Some(mount_point) => mount_point,
+            None => continue,
+        };
+        for option in super_options.split(',') {
+            if SUPPORTED_CONTROLLERS.contains(&option) {
+                mounts
+                    .entry(option.to_owned())
+                    .or_insert_with(|| PathBuf::from(mount_point));
+            }
+        }
+    }
+    mounts
+}
+
+/// Returns the directory of each supported v1 controller mounted under
+/// root, e.g. root/cpu,cpuacct for both cpu and cpuacct.
+fn read_controller_dirs(root: &Path) -> Result<BTreeMap<String, PathBuf>> {
+    let mut mounts = BTreeMap::new();
+    for entry in fs::read_dir(root).map_err(|e| Error::IoError(root.to_owned(), e))? {
+        let entry = entry.map_err(|e| Error::IoError(root.to_owned(), e))?;
+        let path = entry.path();
+        // Follows symlinks such as cpu -> cpu,cpuacct
+        if !path.is_dir() {
+            continue;
+        }
+        let name = entry.file_name();
+        for controller in name.to_string_lossy().split(',') {
+            if SUPPORTED_CONTROLLERS.contains(&controller) {
+                mounts
+                    .entry(controller.to_owned())
+                    .or_insert_with(|| path.clone());
+            }
+        }
+    }
+    Ok(mounts)
+}
+
+/// Microseconds per USER_HZ tick, the unit of cpuacct.stat.
+pub(crate) fn usec_per_tick() -> u64 {
+    match sysconf(SysconfVar::CLK_TCK) {
+        Ok(Some(ticks)) if ticks > 0 => 1_000_000 / ticks as u64,
+        _ => 10_000,
+    }
+}
+
+/// Reads a single cgroup of the v1 hierarchies. Fields without a v1
+/// equivalent are not provided, and stats of a controller that is not
+/// mounted are reported as NotFound IoErrors, the same as a missing file in
+/// cgroup2.
+pub struct CgroupV1Reader {
+    relative_path: PathBuf,
+    /// Controller name to mount point
+    mounts: Arc<BTreeMap<String, PathBuf>>,
+    /// Controller whose hierarchy defines the cgroup tree
+    tree_controller: &'static str,
+}
+
+impl CgroupV1Reader {
+    /// Creates a reader of the root cgroup from the v1 controllers mounted
+    /// in the directories of root.
+    pub fn new(root: PathBuf) -> Result<CgroupV1Reader> {
+        let mounts = read_controller_dirs(&root)?;
+        CgroupV1Reader::from_mounts(mounts, &root)
+    }
+
+    pub fn root() -> Result<CgroupV1Reader> {
+        CgroupV1Reader::new(Path::new(DEFAULT_CG_ROOT).to_path_buf())
+    }
+
+    /// Creates a reader of the root cgroup from the v1 controllers mounted
+    /// according to the given mountinfo file.
+    pub fn from_mountinfo(mountinfo: &Path) -> Result<CgroupV1Reader> {
+        let content =
+            fs::read_to_string(mountinfo).map_err(|e| Error::IoError(mountinfo.to_owned(), e))?;
+        CgroupV1Reader::from_mounts(parse_mountinfo(&content), mountinfo)
+    }
+
+    /// source is where the mounts were found, for errors
+    fn from_mounts(mounts: BTreeMap<String, PathBuf>, source: &Path) -> Result<CgroupV1Reader> {
+        let tree_controller = TREE_CONTROLLERS
+            .iter()
+            .find(|controller| mounts.contains_key(**controller))
+            .ok_or_else(|| Error::NoCgroupV1Controllers(source.to_owned()))?;
+        Ok(CgroupV1Reader {
+            relative_path: PathBuf::from(""),
+            mounts: Arc::new(mounts),
+            tree_controller,
+        })
+    }
+
+    /// Returns the cgroup name (e.g. the path relative to the cgroup root)
+    /// Invoking this on the root cgroup will return an empty path
+    pub fn name(&self) -> &Path {
+        &self.relative_path
+    }
+
+    fn controller_path(&self, controller: &str) -> Option<PathBuf> {
+        self.mounts
+            .get(controller)
+            .map(|mount_point| mount_point.join(&self.relative_path))
+    }
+
+    fn read_file(&self, controller: &str, file_name: &str) -> Result<String> {
+        let path = match self.controller_path(controller) {
+            Some(path) => path.join(file_name),
+            None => {
+                return Err(self.io_error(
+                    file_name,
+                    std::io::Error::new(
+                        ErrorKind::NotFound,
+                        format!("{} controller is not mounted", controller),
+                    ),
+                ));
+            }
+        };
+        fs::read_to_string(path).map_err(|e| self.io_error(file_name, e))
+    }
+
+    fn read_singleline_file<T: FromStr>(&self, controller: &str, file_name: &str) -> Result<T> {
+        let content = self.read_file(controller, file_name)?;
+        match content.lines().next() {
+            Some(line) => line
+                .parse::<T>()
+                .map_err(|_| self.unexpected_line(file_name, line.to_owned())),
+            None => Err(self.invalid_file_format(file_name)),
+        }
+    }
+
+    /// Read a `key value` formatted file. Fails if the file has no lines.
+    fn read_key_values(&self, controller: &str, file_name: &str) -> Result<BTreeMap<String, u64>> {
+        let content = self.read_file(controller, file_name)?;
+        let mut map = BTreeMap::new();
+        for line in content.lines() {
+            let mut items = line.split_ascii_whitespace();
+            match (items.next(), items.next(), items.next()) {
+                (Some(key), Some(value), None) => {
+                    let value = value
+                        .parse()
+                        .map_err(|_| self.unexpected_line(file_name, line.to_owned()))?;
+                    map.insert(key.to_owned(), value);
+                }
+                _ => return Err(self.unexpected_line(file_name, line.to_owned())),
+            }
+        }
+        if map.is_empty() {
+            Err(self.invalid_file_format(file_name))
+        } else {
+            Ok(map)
+        }
+    }
+
+    /// Like read_key_values, but a file that does not exist is treated as
+    /// empty.
+    fn read_optional_key_values(
+        &self,
+        controller: &str,
+        file_name: &str,
+    ) -> Result<BTreeMap<String, u64>> {
+        match self.read_key_values(controller, file_name) {
+            Err(Error::IoError(_, e)) if e.kind() == ErrorKind::NotFound => Ok(BTreeMap::new()),
+            v => v,
+        }
+    }
+
+    /// Read pids.current - returning current cgroup number of processes
+    pub fn read_pids_current(&self) -> Result<u64> {
+        self.read_singleline_file("pids", "pids.current")
+    }
+
+    /// Read pids.max - returning max cgroup number of processes
+    /// Will return -1 if the content is max
+    pub fn read_pids_max(&self) -> Result<i64> {
+        match self.read_singleline_file::<u64>("pids", "pids.max") {
+            Ok(v) => Ok(v as i64),
+            Err(Error::UnexpectedLine(_, line)) if line.starts_with("max") => Ok(-1),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Read memory.usage_in_bytes - the equivalent of memory.current
+    pub fn read_memory_current(&self) -> Result<u64> {
+        self.read_singleline_file("memory", "memory.usage_in_bytes")
+    }
+
+    /// Read cpuacct.usage, cpuacct.stat and cpu.stat as a cgroup2 cpu.stat.
+    /// Only cpuacct.usage is required.
+    pub fn read_cpu_stat(&self) -> Result<CpuStat> {
+        let usage_nsec: u64 = self.read_singleline_file("cpuacct", "cpuacct.usage")?;
+        let acct_stat = self.read_optional_key_values("cpuacct", "cpuacct.stat")?;
+        let cpu_stat = self.read_optional_key_values("cpu", "cpu.stat")?;
+        let usec_per_tick = usec_per_tick();
+        Ok(CpuStat {
+            usage_usec: Some(usage_nsec / 1000),
+            user_usec: acct_stat.get("user").map(|ticks| ticks * usec_per_tick),
+            system_usec: acct_stat.get("system").map(|ticks| ticks * usec_per_tick),
+            nr_periods: cpu_stat.get("nr_periods").copied(),
+            nr_throttled: cpu_stat.get("nr_throttled").copied(),
+            throttled_usec: cpu_stat.get("throttled_time").map(|nsec| nsec / 1000),
+        })
+    }
+
+    /// Read memory.stat as a cgroup2 memory.stat. The hierarchical total_*
+    /// counters are used as cgroup2 memory.stat includes descendants.
+    pub fn read_memory_stat(&self) -> Result<MemoryStat> {
+        let stat = self.read_key_values("memory", "memory.stat")?;
+        let total = |key: &str| stat.get(&format!("total_{}", key)).copied();
+        Ok(MemoryStat {
+            anon: total("rss"),
+            file: total("cache"),
+            shmem: total("shmem"),
+            file_mapped: total("mapped_file"),
+            file_dirty: total("dirty"),
+            file_writeback: total("writeback"),
+            anon_thp: total("rss_huge"),
+            inactive_anon: total("inactive_anon"),
+            active_anon: total("active_anon"),
+            inactive_file: total("inactive_file"),
+            active_file: total("active_file"),
+            unevictable: total("unevictable"),
+            pgfault: total("pgfault"),
+            pgmajfault: total("pgmajfault"),
+            ..Default::default()
+        })
+    }
+
+    /// Read blkio.throttle.io_service_bytes as a cgroup2 io.stat, keyed by
+    /// device number.
+    pub fn read_io_stat(&self) -> Result<BTreeMap<String, IoStat>> {
+        let file_name = "blkio.throttle.io_service_bytes";
+        let content = self.read_file("blkio", file_name)?;
+        let mut map: BTreeMap<String, IoStat> = BTreeMap::new();
+        for line in content.lines() {
+            // e.g. 8:0 Read 4096, followed by a final "Total 4096" line
+            let items = line.split_ascii_whitespace().collect::<Vec<_>>();
+            let (device, op, value) = match items[..] {
+                [device, op, value] => (device, op, value),
+                [_total, _value] => continue,
+                _ => return Err(self.unexpected_line(file_name, line.to_owned())),
+            };
+            let value = value
+                .parse()
+                .map_err(|_| self.unexpected_line(file_name, line.to_owned()))?;
+            let stat = map.entry(device.to_owned()).or_default();
+            match op {
+                "Read" => stat.rbytes = Some(value),
+                "Write" => stat.wbytes = Some(value),
+                "Discard" => stat.dbytes = Some(value),
+                _ => {}
+            }
+        }
+        Ok(map)
+    }
+
+    pub fn read_inode_number(&self) -> Result<u64> {
+        use std::os::linux::fs::MetadataExt;
+        let path = self
+            .controller_path(self.tree_controller)
+            .expect("tree controller is mounted");
+        let meta = fs::metadata(&path).map_err(|e| Error::IoError(path, e))?;
+        Ok(meta.st_ino())
+    }
+
+    /// Return an iterator over child cgroups of the tree controller's
+    /// hierarchy
+    pub fn child_cgroup_iter(&self) -> Result<impl Iterator<Item = CgroupV1Reader> + '_> {
+        let path = self
+            .controller_path(self.tree_controller)
+            .expect("tree controller is mounted");
+        Ok(fs::read_dir(&path)
+            .map_err(|e| self.io_error("", e))?
+            .filter_map(move |entry| match entry {
+                Ok(entry) if entry.file_type().map_or(false, |t| t.is_dir()) => {
+                    Some(CgroupV1Reader {
+                        relative_path: self.relative_path.join(entry.file_name()),
+                        mounts: self.mounts.clone(),
+                        tree_controller: self.tree_controller,
+                    })
+                }
+                _ => None,
+            }))
+    }
+
+    fn invalid_file_format<P: AsRef<Path>>(&self, file_name: P) -> Error {
+        Error::InvalidFileFormat(self.relative_path.join(file_name))
+    }
+
+    fn io_error<P: AsRef<Path>>(&self, file_name: P, e: std::io::Error) -> Error {
+        Error::IoError(self.relative_path.join(file_name), e)
+    }
+
+    fn unexpected_line<P: AsRef<Path>>(&self, file_name: P, line: String) -> Error {
+        Error::UnexpectedLine(self.relative_path.join(file_name), line)
+    }
+}
@@ -172,6 +172,36 @@ pub fn get_belowrc_view_section_key() -> &'static str {
     "view"
 }
 
+/// A problem found in below.conf or belowrc by `below config check`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigIssue {
+    /// Errors make below fail or ignore part of the file, warnings are for
+    /// settings that have no effect
+    pub is_error: bool,
+    /// Dotted path of the key at fault, e.g. dump.system.my_pattern, used to
+    /// find its line. Empty if the message already has the line.
+    pub key: String,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    pub fn error(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            is_error: true,
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            is_error: false,
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+}
+
 pub fn read_kern_file_to_internal_buffer<R: Read>(
     buffer: &RefCell<Vec<u8>>,
     mut reader: R,
@@ -14,6 +14,7 @@
 
 #![deny(clippy::all)]
 
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
@@ -34,7 +35,7 @@ const BELOW_DEFAULT_STORE: &str = "/var/log/below/store";
 /// Global below config
 pub static BELOW_CONFIG: OnceLock<BelowConfig> = OnceLock::new();
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 // If value is missing during deserialization, use the Default::default()
 #[serde(default)]
 pub struct BelowConfig {
@@ -44,6 +45,10 @@ pub struct BelowConfig {
     pub cgroup_filter_out: String,
     pub enable_gpu_stats: bool,
     pub use_rgpu_for_gpu_stats: bool,
+    /// Root of the sysfs tree the open source GPU collector reads cards from
+    pub gpu_sysfs_root: PathBuf,
+    /// Root of the procfs tree the open source GPU collector reads DRM clients from
+    pub gpu_proc_root: PathBuf,
     pub enable_btrfs_stats: bool,
     pub btrfs_samples: u64,
     pub btrfs_min_pct: f64,
@@ -51,6 +56,164 @@ pub struct BelowConfig {
     pub enable_ksm_stats: bool,
     pub enable_resctrl_stats: bool,
     pub enable_tc_stats: bool,
+    /// Track execs with eBPF, in addition to exits
+    pub enable_exec_tracking: bool,
+    /// Collect run queue latency histograms per cgroup with eBPF
+    pub enable_run_delay_stats: bool,
+    /// File mapping container ids and pod uids to friendly names
+    pub container_names_file: Option<PathBuf>,
+    /// Sample faster for a while when pressure or other fields cross a
+    /// threshold. Disabled if absent.
+    pub adaptive_sampling: Option<AdaptiveSamplingConfig>,
+    /// Store retention of `below record` in seconds, overridden by
+    /// --retain-for-s
+    pub retain_for_s: Option<u64>,
+    /// Store size limit of `below record` in bytes, overridden by
+    /// --store-size-limit
+    pub store_size_limit: Option<u64>,
+    /// Per collector settings, which take precedence over the enable_*
+    /// flags above
+    pub collectors: CollectorsConfig,
+    /// Redaction of process cmdlines before samples are stored
+    pub redaction: RedactionConfig,
+}
+
+/// The [adaptive_sampling] section of below.conf.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct AdaptiveSamplingConfig {
+    /// Interval while sampling fast, at least 1s
+    pub fast_interval_s: u64,
+    /// How long to sample fast once a threshold is crossed
+    pub window_s: u64,
+    /// Threshold on the avg10 of cpu some, io full and memory full pressure
+    /// of the root and top-level cgroups
+    pub pressure_threshold_pct: f64,
+    /// Thresholds on other fields, keyed by field path as in `below query`,
+    /// e.g. "system.cpu.usage_pct" = 95.0
+    pub triggers: BTreeMap<String, f64>,
+}
+
+impl Default for AdaptiveSamplingConfig {
+    fn default() -> Self {
+        AdaptiveSamplingConfig {
+            fast_interval_s: 1,
+            window_s: 60,
+            pressure_threshold_pct: 40.0,
+            triggers: BTreeMap::new(),
+        }
+    }
+}
+
+/// The [redaction] section of below.conf.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionConfig {
+    /// Applied to each cmdline argument in order
+    pub cmdline_rules: Vec<RedactionRule>,
+    /// comm or exe file names of processes whose cmdline is dropped entirely
+    pub drop_cmdline: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RedactionRule {
+    /// Regex matched against each argument
+    pub pattern: String,
+    /// Replacement of each match, which may refer to capture groups, e.g.
+    /// "${1}<redacted>"
+    pub replacement: String,
+}
+
+impl RedactionConfig {
+    pub fn is_empty(&self) -> bool {
+        self.cmdline_rules.is_empty() && self.drop_cmdline.is_empty()
+    }
+}
+
+/// The [collectors.<name>] sections of below.conf. Unknown collectors and
+/// keys are rejected.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CollectorsConfig {
+    /// cgroup io.stat, off by default as it may be expensive
+    pub io_stat: CollectorConfig,
+    pub disk_stat: CollectorConfig,
+    pub slabinfo: CollectorConfig,
+    pub netstat: CollectorConfig,
+    pub btrfs: CollectorConfig,
+    pub ethtool: CollectorConfig,
+    pub ksm: CollectorConfig,
+    pub resctrl: CollectorConfig,
+    /// The following run in their own threads or are event driven, so they
+    /// do not support interval_multiple
+    pub tc: CollectorConfig,
+    pub gpu: CollectorConfig,
+    pub exitstats: CollectorConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CollectorConfig {
+    /// Unset keeps the default of the collector
+    pub enabled: Option<bool>,
+    /// Run every Nth sample only. Other samples carry the last value
+    /// forward and mark it stale.
+    pub interval_multiple: u64,
+}
+
+impl Default for CollectorConfig {
+    fn default() -> Self {
+        CollectorConfig {
+            enabled: None,
+            interval_multiple: 1,
+        }
+    }
+}
+
+impl CollectorConfig {
+    pub fn is_enabled(&self, default: bool) -> bool {
+        self.enabled.unwrap_or(default)
+    }
+}
+
+impl CollectorsConfig {
+    fn validate(&self) -> Result<()> {
+        for (name, collector) in [
+            ("io_stat", &self.io_stat),
+            ("disk_stat", &self.disk_stat),
+            ("slabinfo", &self.slabinfo),
+            ("netstat", &self.netstat),
+            ("btrfs", &self.btrfs),
+            ("ethtool", &self.ethtool),
+            ("ksm", &self.ksm),
+            ("resctrl", &self.resctrl),
+        ] {
+            if collector.interval_multiple == 0 {
+                bail!("collectors.{}.interval_multiple must be at least 1", name);
+            }
+        }
+        for (name, collector) in [
+            ("tc", &self.tc),
+            ("gpu", &self.gpu),
+            ("exitstats", &self.exitstats),
+        ] {
+            if collector.interval_multiple != 1 {
+                bail!("collectors.{} does not support interval_multiple", name);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A below.conf along with its top level keys that are not attributes of
+/// BelowConfig
+#[derive(Deserialize)]
+struct CheckedBelowConfig {
+    #[serde(flatten)]
+    below_config: BelowConfig,
+    #[serde(flatten)]
+    unknown_keys: BTreeMap<String, toml::Value>,
 }
 
 impl Default for BelowConfig {
@@ -62,6 +225,8 @@ impl Default for BelowConfig {
             cgroup_filter_out: String::new(),
             enable_gpu_stats: false,
             use_rgpu_for_gpu_stats: true,
+            gpu_sysfs_root: "/sys".into(),
+            gpu_proc_root: "/proc".into(),
             enable_btrfs_stats: false,
             btrfs_samples: btrfs::DEFAULT_SAMPLES,
             btrfs_min_pct: btrfs::DEFAULT_MIN_PCT,
@@ -69,6 +234,14 @@ impl Default for BelowConfig {
             enable_ksm_stats: false,
             enable_resctrl_stats: false,
             enable_tc_stats: false,
+            enable_exec_tracking: false,
+            enable_run_delay_stats: false,
+            container_names_file: None,
+            adaptive_sampling: None,
+            retain_for_s: None,
+            store_size_limit: None,
+            collectors: Default::default(),
+            redaction: Default::default(),
         }
     }
 }
@@ -95,8 +268,8 @@ impl BelowConfig {
             }
         };
 
-        match toml::from_str(string_config.as_str()) {
-            Ok(bc) => Ok(bc),
+        let mut below_config: BelowConfig = match toml::from_str(string_config.as_str()) {
+            Ok(bc) => bc,
             Err(e) => {
                 bail!(
                     "Failed to parse config file {}: {}\n{}",
@@ -105,6 +278,69 @@ impl BelowConfig {
                     string_config
                 );
             }
+        };
+        if let Err(e) = below_config.collectors.validate() {
+            bail!("Invalid config file {}: {}", path.to_string_lossy(), e);
+        }
+        below_config.apply_collectors();
+        Ok(below_config)
+    }
+
+    /// Parse a below.conf like load does, also returning the top level keys
+    /// that load ignores because they are not attributes of BelowConfig.
+    pub fn parse_checked(config_str: &str) -> Result<(Self, Vec<String>)> {
+        let checked: CheckedBelowConfig = toml::from_str(config_str)?;
+        let mut below_config = checked.below_config;
+        below_config.collectors.validate()?;
+        below_config.apply_collectors();
+        Ok((below_config, checked.unknown_keys.into_keys().collect()))
+    }
+
+    /// Sorted dotted keys whose values differ between self and other. Tables are
+    /// compared key by key, so a change in [collectors.btrfs] is reported as
+    /// e.g. collectors.btrfs.enabled.
+    pub fn changed_keys(&self, other: &BelowConfig) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        diff_values(
+            "",
+            &toml::Value::try_from(self)?,
+            &toml::Value::try_from(other)?,
+            &mut keys,
+        );
+        keys.sort();
+        Ok(keys)
+    }
+
+    /// Resolve the collectors that also have an enable_* flag, so that the
+    /// flags are the single source of truth for them.
+    fn apply_collectors(&mut self) {
+        let collectors = &self.collectors;
+        self.enable_btrfs_stats = collectors.btrfs.is_enabled(self.enable_btrfs_stats);
+        self.enable_ethtool_stats = collectors.ethtool.is_enabled(self.enable_ethtool_stats);
+        self.enable_ksm_stats = collectors.ksm.is_enabled(self.enable_ksm_stats);
+        self.enable_resctrl_stats = collectors.resctrl.is_enabled(self.enable_resctrl_stats);
+        self.enable_tc_stats = collectors.tc.is_enabled(self.enable_tc_stats);
+        self.enable_gpu_stats = collectors.gpu.is_enabled(self.enable_gpu_stats);
+    }
+}
+
+fn diff_values(path: &str, a: &toml::Value, b: &toml::Value, keys: &mut Vec<String>) {
+    match (a, b) {
+        (toml::Value::Table(a), toml::Value::Table(b)) => {
+            let only_in_b = b.keys().filter(|key| !a.contains_key(*key));
+            for key in a.keys().chain(only_in_b) {
+                let key_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => diff_values(&key_path, a, b, keys),
+                    _ => keys.push(key_path),
+                }
+            }
         }
+        _ if a != b => keys.push(path.to_owned()),
+        _ => {}
     }
 }
@@ -151,3 +151,169 @@ fn test_config_partial_load() {
         "/var/log/below/store"
     );
 }
+
+#[test]
+fn test_config_adaptive_sampling() {
+    let tempdir = TempDir::with_prefix("below_config_load.").expect("Failed to create temp dir");
+    let path = tempdir.path().join("below.config");
+    let config_str = r#"
+        log_dir = '/var/log/below'
+
+        [adaptive_sampling]
+        window_s = 30
+        triggers = { "system.cpu.usage_pct" = 95.0 }
+    "#;
+    std::fs::write(&path, config_str).expect("Faild to write temp conf file during testing ignore");
+
+    let below_config = match BelowConfig::load(&path) {
+        Ok(b) => b,
+        Err(e) => panic!("{:#}", e),
+    };
+    let adaptive_sampling = below_config
+        .adaptive_sampling
+        .expect("Missing adaptive_sampling");
+    assert_eq!(adaptive_sampling.window_s, 30);
+    assert_eq!(adaptive_sampling.fast_interval_s, 1);
+    assert_eq!(
+        adaptive_sampling.triggers.get("system.cpu.usage_pct"),
+        Some(&95.0)
+    );
+    assert!(BelowConfig::default().adaptive_sampling.is_none());
+}
+
+#[test]
+fn test_config_collectors() {
+    let tempdir = TempDir::with_prefix("below_config_load.").expect("Failed to create temp dir");
+    let path = tempdir.path().join("below.config");
+    let config_str = r#"
+        enable_ksm_stats = true
+
+        [collectors.ksm]
+        enabled = false
+
+        [collectors.btrfs]
+        enabled = true
+        interval_multiple = 10
+    "#;
+    std::fs::write(&path, config_str).expect("Faild to write temp conf file during testing ignore");
+
+    let below_config = match BelowConfig::load(&path) {
+        Ok(b) => b,
+        Err(e) => panic!("{:#}", e),
+    };
+    assert!(!below_config.enable_ksm_stats);
+    assert!(below_config.enable_btrfs_stats);
+    assert_eq!(below_config.collectors.btrfs.interval_multiple, 10);
+    assert_eq!(below_config.collectors.io_stat.interval_multiple, 1);
+    assert!(!below_config.collectors.io_stat.is_enabled(false));
+}
+
+#[test]
+fn test_config_collectors_invalid() {
+    let tempdir = TempDir::with_prefix("below_config_load.").expect("Failed to create temp dir");
+    let path = tempdir.path().join("below.config");
+    for (config_str, expected) in [
+        (
+            "[collectors.smaps]\nenabled = true",
+            "Failed to parse config file",
+        ),
+        (
+            "[collectors.btrfs]\ninterval = 2",
+            "Failed to parse config file",
+        ),
+        (
+            "[collectors.btrfs]\ninterval_multiple = 0",
+            "Invalid config file",
+        ),
+        (
+            "[collectors.gpu]\ninterval_multiple = 2",
+            "Invalid config file",
+        ),
+    ] {
+        std::fs::write(&path, config_str)
+            .expect("Faild to write temp conf file during testing ignore");
+        match BelowConfig::load(&path) {
+            Ok(_) => panic!("Below should not load {}", config_str),
+            Err(e) => assert!(
+                format!("{}", e).starts_with(expected),
+                "{}: {}",
+                config_str,
+                e
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_config_parse_checked() {
+    let config_str = r#"
+        log_dir = '/var/log/below'
+        enable_btrfs_stat = true
+        something_else = "demacia"
+
+        [collectors.ksm]
+        enabled = true
+    "#;
+    let (below_config, unknown_keys) =
+        BelowConfig::parse_checked(config_str).expect("Failed to parse config");
+    assert_eq!(below_config.log_dir.to_string_lossy(), "/var/log/below");
+    assert!(below_config.enable_ksm_stats);
+    assert_eq!(unknown_keys, vec!["enable_btrfs_stat", "something_else"]);
+
+    let err = BelowConfig::parse_checked("log_dir = 1").expect_err("Parsed invalid config");
+    assert!(format!("{}", err).contains("line 1"), "{}", err);
+    assert!(BelowConfig::parse_checked("[collectors.tc]\ninterval_multiple = 5").is_err());
+}
+
+#[test]
+fn test_config_changed_keys() {
+    let (old, _) = BelowConfig::parse_checked("cgroup_filter_out = 'user.slice.*'")
+        .expect("Failed to parse config");
+    assert!(old.changed_keys(&old.clone()).unwrap().is_empty());
+
+    let (new, _) = BelowConfig::parse_checked(
+        r#"
+        store_dir = '/data/below'
+        retain_for_s = 86400
+
+        [collectors.btrfs]
+        interval_multiple = 10
+    "#,
+    )
+    .expect("Failed to parse config");
+    assert_eq!(
+        old.changed_keys(&new).unwrap(),
+        vec![
+            "cgroup_filter_out",
+            "collectors.btrfs.interval_multiple",
+            "retain_for_s",
+            "store_dir",
+        ]
+    );
+}
+
+#[test]
+fn test_config_redaction() {
+    let config_str = r#"
+        [redaction]
+        drop_cmdline = ["vault"]
+
+        [[redaction.cmdline_rules]]
+        pattern = '^(--password=)\S+'
+        replacement = '${1}<redacted>'
+    "#;
+    let (below_config, _) = BelowConfig::parse_checked(config_str).expect("Failed to parse config");
+    let redaction = &below_config.redaction;
+    assert!(!redaction.is_empty());
+    assert_eq!(redaction.drop_cmdline, vec!["vault"]);
+    assert_eq!(
+        redaction.cmdline_rules,
+        vec![RedactionRule {
+            pattern: r"^(--password=)\S+".to_owned(),
+            replacement: "${1}<redacted>".to_owned(),
+        }]
+    );
+    assert!(BelowConfig::default().redaction.is_empty());
+    // A rule without a replacement is rejected
+    assert!(BelowConfig::parse_checked("[[redaction.cmdline_rules]]\npattern = 'x'").is_err());
+}
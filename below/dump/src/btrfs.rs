@@ -20,6 +20,7 @@ pub struct Btrfs {
     opts: GeneralOpt,
     select: Option<BtrfsModelFieldId>,
     fields: Vec<BtrfsField>,
+    where_expr: Option<FilterExpr<BtrfsModelFieldId>>,
 }
 
 impl Btrfs {
@@ -27,12 +28,13 @@ impl Btrfs {
         opts: &GeneralOpt,
         select: Option<BtrfsModelFieldId>,
         fields: Vec<BtrfsField>,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        Ok(Self {
             opts: opts.to_owned(),
             select,
             fields,
-        }
+            where_expr: bind_where_expr(opts)?,
+        })
     }
 }
 
@@ -63,6 +65,11 @@ impl Dumper for Btrfs {
                             _ => Some(model),
                         }
                     })
+                    .filter(|model| {
+                        self.where_expr
+                            .as_ref()
+                            .map_or(true, |expr| expr.eval(model))
+                    })
                     .collect();
 
                 if let Some(field_id) = &self.select {
@@ -21,6 +21,7 @@ pub struct Cgroup {
     opts: GeneralOpt,
     select: Option<SingleCgroupModelFieldId>,
     fields: Vec<CgroupField>,
+    where_expr: Option<FilterExpr<SingleCgroupModelFieldId>>,
 }
 
 impl Cgroup {
@@ -28,12 +29,13 @@ impl Cgroup {
         opts: &GeneralOpt,
         select: Option<SingleCgroupModelFieldId>,
         fields: Vec<CgroupField>,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        Ok(Self {
             opts: opts.to_owned(),
             select,
             fields,
-        }
+            where_expr: bind_where_expr(opts)?,
+        })
     }
 }
 
@@ -48,6 +50,7 @@ impl Dumper for Cgroup {
     ) -> Result<IterExecResult> {
         fn output_cgroup(
             handle: &Cgroup,
+            where_expr: Option<&FilterExpr<SingleCgroupModelFieldId>>,
             ctx: &CommonFieldContext,
             model: &model::CgroupModel,
             output: &mut dyn Write,
@@ -64,7 +67,7 @@ impl Dumper for Cgroup {
                         .map_or("?".to_owned(), |v| v.to_string()),
                 ),
                 _ => true,
-            };
+            } && where_expr.map_or(true, |expr| expr.eval(cgroup));
 
             if should_print {
                 match handle.opts.output_format {
@@ -146,7 +149,16 @@ impl Dumper for Cgroup {
 
             for child_cgroup in &children {
                 let mut child = json!({});
-                output_cgroup(handle, ctx, child_cgroup, output, round, json, &mut child)?;
+                output_cgroup(
+                    handle,
+                    where_expr,
+                    ctx,
+                    child_cgroup,
+                    output,
+                    round,
+                    json,
+                    &mut child,
+                )?;
                 if json && child["children"].is_array() {
                     // Parent does not match, but child does, we should also render parent.
                     if !jval["children"].is_array() {
@@ -161,7 +173,16 @@ impl Dumper for Cgroup {
         }
         let json = self.opts.output_format == Some(OutputFormat::Json);
         let mut jval = json!({});
-        output_cgroup(self, ctx, &model.cgroup, output, round, json, &mut jval)?;
+        output_cgroup(
+            self,
+            self.where_expr.as_ref(),
+            ctx,
+            &model.cgroup,
+            output,
+            round,
+            json,
+            &mut jval,
+        )?;
         match (json, comma_flag) {
             (true, true) => write!(output, ",{}", jval)?,
             (true, false) => write!(output, "{}", jval)?,
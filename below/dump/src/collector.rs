@@ -0,0 +1,117 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+pub struct Collector {
+    opts: GeneralOpt,
+    fields: Vec<CollectorField>,
+    where_expr: Option<FilterExpr<model::CollectorHealthModelFieldId>>,
+}
+
+impl Collector {
+    pub fn new(opts: &GeneralOpt, fields: Vec<CollectorField>) -> Result<Self> {
+        Ok(Self {
+            opts: opts.to_owned(),
+            fields,
+            where_expr: bind_where_expr(opts)?,
+        })
+    }
+}
+
+impl Dumper for Collector {
+    fn dump_model(
+        &self,
+        ctx: &CommonFieldContext,
+        model: &model::Model,
+        output: &mut dyn Write,
+        round: &mut usize,
+        comma_flag: bool,
+    ) -> Result<IterExecResult> {
+        // Samples recorded before collector health was added have none
+        let collector = match model.collector.as_ref() {
+            Some(collector) => collector,
+            None => return Ok(IterExecResult::Skip),
+        };
+        if !self
+            .where_expr
+            .as_ref()
+            .map_or(true, |expr| expr.eval(collector))
+        {
+            return Ok(IterExecResult::Skip);
+        }
+
+        match self.opts.output_format {
+            Some(OutputFormat::Raw) | None => write!(
+                output,
+                "{}",
+                print::dump_raw(
+                    &self.fields,
+                    ctx,
+                    collector,
+                    *round,
+                    self.opts.repeat_title,
+                    self.opts.disable_title,
+                    self.opts.raw
+                )
+            )?,
+            Some(OutputFormat::Csv) => write!(
+                output,
+                "{}",
+                print::dump_csv(
+                    &self.fields,
+                    ctx,
+                    collector,
+                    *round,
+                    self.opts.disable_title,
+                    self.opts.raw
+                )
+            )?,
+            Some(OutputFormat::Tsv) => write!(
+                output,
+                "{}",
+                print::dump_tsv(
+                    &self.fields,
+                    ctx,
+                    collector,
+                    *round,
+                    self.opts.disable_title,
+                    self.opts.raw
+                )
+            )?,
+            Some(OutputFormat::KeyVal) => write!(
+                output,
+                "{}",
+                print::dump_kv(&self.fields, ctx, collector, self.opts.raw)
+            )?,
+            Some(OutputFormat::Json) => {
+                let par = print::dump_json(&self.fields, ctx, collector, self.opts.raw);
+                if comma_flag {
+                    write!(output, ",{}", par)?;
+                } else {
+                    write!(output, "{}", par)?;
+                }
+            }
+            Some(OutputFormat::OpenMetrics) => write!(
+                output,
+                "{}",
+                print::dump_openmetrics(&self.fields, ctx, collector)
+            )?,
+        };
+
+        *round += 1;
+
+        Ok(IterExecResult::Success)
+    }
+}
@@ -19,7 +19,9 @@ use anyhow::bail;
 use anyhow::Error;
 use anyhow::Result;
 use clap::Parser;
+use model::filter_expr::FilterExpr;
 use model::BtrfsModelFieldId;
+use model::CollectorHealthModelFieldId;
 use model::FieldId;
 use model::NetworkModelFieldId;
 use model::SingleCgroupModelFieldId;
@@ -528,6 +530,10 @@ Output stats for top 5 CPU intense processes for each time slice from 08:30:00 t
 
 $ below dump process -b "08:30:00" -e "08:30:30" -s cpu.usage_pct --rsort --top 5
 
+Output stats for processes in workload.slice using more than 50% CPU from 08:30:00 to 08:30:30:
+
+$ below dump process -b "08:30:00" -e "08:30:30" --where "cpu.usage_pct > 50 && cgroup =~ 'workload.slice'"
+
 "#,
         about = PROCESS_ABOUT,
         common_fields = join(enum_iterator::all::<CommonField>()),
@@ -647,6 +653,11 @@ from 08:30:00 to 08:30:30 recursively:
 
 $ below dump cgroup -b "08:30:00" -e "08:30:30" -s cpu.usage_pct --rsort --top 5
 
+Output stats for cgroups under memory pressure or close to their memory limit
+from 08:30:00 to 08:30:30:
+
+$ below dump cgroup -b "08:30:00" -e "08:30:30" --where "pressure.memory_full_pct > 1 || mem.total > 10000000000"
+
 "#,
         about = CGROUP_ABOUT,
         common_fields = join(enum_iterator::all::<CommonField>()),
@@ -1113,6 +1124,101 @@ $ below dump tc -b "08:30:00" -e "08:30:30" -O json
     )
 });
 
+/// Represents the fields of the collector health model.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    below_derive::EnumFromStr,
+    below_derive::EnumToString
+)]
+pub enum CollectorAggField {
+    Latency,
+    Failures,
+}
+
+impl AggField<CollectorHealthModelFieldId> for CollectorAggField {
+    fn expand(&self, _detail: bool) -> Vec<CollectorHealthModelFieldId> {
+        use model::CollectorHealthModelFieldId::*;
+
+        match self {
+            Self::Latency => vec![
+                CollectLatencyUsec,
+                CgroupLatencyUsec,
+                ProcfsLatencyUsec,
+                NetstatLatencyUsec,
+                EthtoolLatencyUsec,
+                BtrfsLatencyUsec,
+                TcLatencyUsec,
+                GpuLatencyUsec,
+                StoreWriteLatencyUsec,
+            ],
+            Self::Failures => vec![Errors, ExitstatLostEvents],
+        }
+    }
+}
+
+pub type CollectorOptionField = DumpOptionField<CollectorHealthModelFieldId, CollectorAggField>;
+
+pub static DEFAULT_COLLECTOR_FIELDS: &[CollectorOptionField] = &[
+    DumpOptionField::Unit(DumpField::Common(CommonField::Datetime)),
+    DumpOptionField::Agg(CollectorAggField::Latency),
+    DumpOptionField::Agg(CollectorAggField::Failures),
+    DumpOptionField::Unit(DumpField::FieldId(
+        CollectorHealthModelFieldId::StaleCollectors,
+    )),
+    DumpOptionField::Unit(DumpField::Common(CommonField::Timestamp)),
+];
+
+const COLLECTOR_ABOUT: &str = "Dump the health of below's own collection";
+
+/// Generated about message for collector dump so supported fields are up-to-date.
+static COLLECTOR_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
+    format!(
+        r#"{about}
+
+Latencies are of the collection of each sample, by subsystem, and of writing
+the previous sample to the store. Subsystems that are disabled have no
+latency. Subsystems that were not due because of their interval_multiple in
+below.conf carry an earlier value and are listed in stale_collectors. Samples
+recorded by older versions of below are skipped.
+
+********************** Available fields **********************
+
+{common_fields}, {collector_fields}
+
+********************** Aggregated fields **********************
+
+* latency: includes [{agg_latency_fields}].
+
+* failures: includes [{agg_failures_fields}].
+
+* --detail: no effect.
+
+* --default: includes [{default_fields}].
+
+* --everything: includes everything (equivalent to --default --detail).
+
+********************** Example Commands **********************
+
+Simple example:
+
+$ below dump collector -b "08:30:00" -e "08:30:30" -O csv
+
+Output the collection and store write latencies only:
+
+$ below dump collector -b "08:30:00" -e "08:30:30" -f collect_latency_usec store_write_latency_usec
+
+"#,
+        about = COLLECTOR_ABOUT,
+        common_fields = join(enum_iterator::all::<CommonField>()),
+        collector_fields = join(enum_iterator::all::<CollectorHealthModelFieldId>()),
+        agg_latency_fields = join(CollectorAggField::Latency.expand(false)),
+        agg_failures_fields = join(CollectorAggField::Failures.expand(false)),
+        default_fields = join(DEFAULT_COLLECTOR_FIELDS.to_owned()),
+    )
+});
+
 make_option! (OutputFormat {
     "raw": Raw,
     "csv": Csv,
@@ -1145,9 +1251,20 @@ pub struct GeneralOpt {
     /// _
     #[clap(long, group = "time")]
     pub duration: Option<String>,
+    /// Downsample the output to one row per step, e.g. "1m". Rates are
+    /// averaged over the whole step rather than taken from a single sample.
+    #[clap(long)]
+    pub step: Option<humantime::Duration>,
     /// Take a regex and apply to --select selected field. See command level doc for example.
     #[clap(long, short = 'F')]
     pub filter: Option<Regex>,
+    /// Take a filter expression over any of the dumped fields, e.g.{n}
+    /// "cpu.usage_pct > 50 && cgroup =~ 'workload.slice'".{n}
+    /// Supports ==, !=, <, <=, >, >=, =~ (regex), !~ combined with &&, || and !.{n}
+    /// Dumpers with a single row per sample (system, network, transport{n}
+    /// and collector) skip the samples that do not match.
+    #[clap(long = "where", short = 'W')]
+    pub where_expr: Option<FilterExpr<String>>,
     /// Sort (lower to higher) by --select selected field. See command level doc for example.
     #[clap(long)]
     pub sort: bool,
@@ -1307,4 +1424,15 @@ pub enum DumpCommand {
         #[clap(long, short, conflicts_with("fields"))]
         pattern: Option<String>,
     },
+    #[clap(about = COLLECTOR_ABOUT, long_about = COLLECTOR_LONG_ABOUT.as_str())]
+    Collector {
+        /// Select which fields to display and in what order.
+        #[clap(short, long, num_args = 1..)]
+        fields: Option<Vec<CollectorOptionField>>,
+        #[clap(flatten)]
+        opts: GeneralOpt,
+        /// Saved pattern in the dumprc file under [collector] section.
+        #[clap(long, short, conflicts_with("fields"))]
+        pattern: Option<String>,
+    },
 }
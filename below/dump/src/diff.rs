@@ -0,0 +1,333 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use clap::Parser;
+use common::util::get_unix_timestamp;
+use model::diff::cgroup_entities;
+use model::diff::diff_queriables;
+use model::diff::process_entities;
+use model::diff::sort_diffs;
+use model::diff::DiffOrder;
+use model::Model;
+use once_cell::sync::Lazy;
+
+use super::*;
+use crate::command::AggField;
+use crate::command::CgroupOptionField;
+use crate::command::DumpOptionField;
+use crate::command::ProcessOptionField;
+use crate::command::SystemOptionField;
+
+pub const DIFF_ABOUT: &str = "Compare two points in time";
+
+pub static DIFF_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
+    format!(
+        r#"{about}
+
+Loads the samples at --a and --b and prints how each field of each entity
+changed between them. Entities that only exist in one of the samples are
+reported as appeared or disappeared. Only numeric fields are compared, and
+unchanged fields are omitted.
+
+Fields are selected the same way as in `below dump`, see
+`below dump <cgroup|process|system> --help` for the available ones.
+
+********************** Example Commands **********************
+
+Top 10 cgroup changes between 08:00 and 09:00:
+
+$ below diff --a "08:00" --b "09:00" --top 10 cgroup
+
+Processes whose cpu usage changed the most relative to the earlier sample:
+
+$ below diff --a "08:00" --b "09:00" --sort rel process -f cpu.usage_pct
+
+System memory changes as csv:
+
+$ below diff --a "08:00" --b "09:00" -O csv system -f mem
+
+"#,
+        about = DIFF_ABOUT,
+    )
+});
+
+#[derive(Debug, Parser, Default, Clone)]
+pub struct DiffOpts {
+    /// Time of the earlier sample, same format as replay
+    #[clap(long = "a")]
+    pub a: String,
+    /// Time of the later sample, same format as replay
+    #[clap(long = "b")]
+    pub b: String,
+    /// Days adjuster applied to both times, same as -r option in replay.
+    #[clap(short = 'r')]
+    pub yesterdays: Option<String>,
+    /// Order by absolute (abs) or relative (rel) change. Default to abs
+    #[clap(long)]
+    pub sort: Option<DiffOrder>,
+    /// Only show the N most significant changes, 0 for all.
+    #[clap(long, default_value = "0")]
+    pub top: usize,
+    /// Output format. Choose from raw, csv, tsv, kv, json. Default to raw
+    #[clap(long, short = 'O')]
+    pub output_format: Option<OutputFormat>,
+    /// Output destination, default to stdout.
+    #[clap(long, short)]
+    pub output: Option<String>,
+    /// Disable title in raw, csv or tsv format output
+    #[clap(long)]
+    pub disable_title: bool,
+    /// Dump raw data without units or conversion
+    #[clap(long)]
+    pub raw: bool,
+}
+
+#[derive(Debug, Parser, Clone)]
+pub enum DiffCommand {
+    /// Compare cgroups
+    Cgroup {
+        /// Select which fields to compare.
+        #[clap(short, long, num_args = 1..)]
+        fields: Option<Vec<CgroupOptionField>>,
+    },
+    /// Compare processes
+    Process {
+        /// Select which fields to compare.
+        #[clap(short, long, num_args = 1..)]
+        fields: Option<Vec<ProcessOptionField>>,
+    },
+    /// Compare system stats
+    System {
+        /// Select which fields to compare.
+        #[clap(short, long, num_args = 1..)]
+        fields: Option<Vec<SystemOptionField>>,
+    },
+}
+
+const TITLES: [&str; 7] = ["Entity", "State", "Field", "A", "B", "Delta", "Delta%"];
+
+type DiffRow = [String; 7];
+
+/// Only FieldIds take part in a diff, CommonFields such as datetime are
+/// dropped.
+fn expand_field_ids<F: FieldId + Clone, A: AggField<F>>(
+    fields: &Option<Vec<DumpOptionField<F, A>>>,
+    default: &[DumpOptionField<F, A>],
+) -> Vec<F> {
+    expand_fields(fields.as_deref().unwrap_or(default), false)
+        .into_iter()
+        .filter_map(|field| match field {
+            DumpField::FieldId(field_id) => Some(field_id),
+            DumpField::Common(_) => None,
+        })
+        .collect()
+}
+
+fn get_rows<Q: HasRenderConfigForDump>(
+    a: &BTreeMap<String, &Q>,
+    b: &BTreeMap<String, &Q>,
+    field_ids: &[Q::FieldId],
+    opts: &DiffOpts,
+) -> Vec<DiffRow>
+where
+    Q::FieldId: Clone + ToString,
+{
+    let mut diffs = diff_queriables(a, b, field_ids);
+    sort_diffs(&mut diffs, opts.sort.unwrap_or_default());
+    if opts.top > 0 {
+        diffs.truncate(opts.top);
+    }
+    diffs
+        .iter()
+        .map(|diff| {
+            let mut rc = Q::get_render_config_for_dump(&diff.field_id);
+            if opts.raw {
+                rc.format = None;
+                rc.suffix = None;
+            }
+            let render = |field: &Option<Field>| match field {
+                Some(_) => rc.render(field.clone(), false),
+                None => "-".to_owned(),
+            };
+            [
+                diff.entity.clone(),
+                diff.state.to_string(),
+                diff.field_id.to_string(),
+                render(&diff.a),
+                render(&diff.b),
+                diff.delta().map_or("-".to_owned(), |delta| {
+                    rc.render_delta(delta, diff.b.as_ref().or(diff.a.as_ref()), false)
+                }),
+                diff.relative_string(),
+            ]
+        })
+        .collect()
+}
+
+/// Compares the entities selected by cmd between two models.
+pub fn diff_models(
+    model_a: &Model,
+    model_b: &Model,
+    opts: &DiffOpts,
+    cmd: &DiffCommand,
+) -> Vec<DiffRow> {
+    match cmd {
+        DiffCommand::Cgroup { fields } => get_rows(
+            &cgroup_entities(&model_a.cgroup),
+            &cgroup_entities(&model_b.cgroup),
+            &expand_field_ids(fields, command::DEFAULT_CGROUP_FIELDS),
+            opts,
+        ),
+        DiffCommand::Process { fields } => get_rows(
+            &process_entities(&model_a.process),
+            &process_entities(&model_b.process),
+            &expand_field_ids(fields, command::DEFAULT_PROCESS_FIELDS),
+            opts,
+        ),
+        DiffCommand::System { fields } => get_rows(
+            &BTreeMap::from([("system".to_owned(), &model_a.system)]),
+            &BTreeMap::from([("system".to_owned(), &model_b.system)]),
+            &expand_field_ids(fields, command::DEFAULT_SYSTEM_FIELDS),
+            opts,
+        ),
+    }
+}
+
+fn write_separated(rows: &[DiffRow], sep: &str, disable_title: bool) -> String {
+    let mut res = String::new();
+    if !disable_title {
+        for title in TITLES {
+            res.push_str(title);
+            res.push_str(sep);
+        }
+        res.push('\n');
+    }
+    for row in rows {
+        for col in row {
+            res.push_str(col);
+            res.push_str(sep);
+        }
+        res.push('\n');
+    }
+    res
+}
+
+fn write_raw(rows: &[DiffRow], disable_title: bool) -> String {
+    let mut widths = TITLES.map(str::len);
+    for row in rows {
+        for (width, col) in widths.iter_mut().zip(row) {
+            *width = (*width).max(col.chars().count());
+        }
+    }
+    let mut res = String::new();
+    let titles = TITLES.map(str::to_owned);
+    let lines = if disable_title {
+        rows.iter().collect::<Vec<_>>()
+    } else {
+        std::iter::once(&titles).chain(rows).collect()
+    };
+    for line in lines {
+        for (col, width) in line.iter().zip(widths) {
+            res.push_str(&format!("{:width$} ", col, width = width));
+        }
+        res.push('\n');
+    }
+    res
+}
+
+pub fn write_rows(
+    rows: &[DiffRow],
+    output: &mut dyn Write,
+    output_format: Option<OutputFormat>,
+    disable_title: bool,
+) -> Result<()> {
+    let res = match output_format {
+        None | Some(OutputFormat::Raw) => write_raw(rows, disable_title),
+        Some(OutputFormat::Csv) => write_separated(rows, ",", disable_title),
+        Some(OutputFormat::Tsv) => write_separated(rows, "\t", disable_title),
+        Some(OutputFormat::KeyVal) => {
+            let mut res = String::new();
+            for row in rows {
+                for (title, col) in TITLES.iter().zip(row) {
+                    res.push_str(&format!("{}: {}\n", title, col));
+                }
+                res.push('\n');
+            }
+            res
+        }
+        Some(OutputFormat::Json) => {
+            let rows: Vec<Value> = rows
+                .iter()
+                .map(|row| {
+                    let mut obj = json!({});
+                    for (title, col) in TITLES.iter().zip(row) {
+                        obj[title] = json!(col);
+                    }
+                    obj
+                })
+                .collect();
+            let mut res = serde_json::to_string_pretty(&rows)?;
+            res.push('\n');
+            res
+        }
+        Some(OutputFormat::OpenMetrics) => bail!("openmetrics is not supported by diff"),
+    };
+    output.write_all(res.as_bytes())?;
+    Ok(())
+}
+
+pub fn run(
+    logger: slog::Logger,
+    dir: PathBuf,
+    host: Option<String>,
+    port: Option<u16>,
+    snapshot: Option<String>,
+    opts: DiffOpts,
+    cmd: DiffCommand,
+) -> Result<()> {
+    let time_a = cliutil::system_time_from_date_and_adjuster(&opts.a, opts.yesterdays.as_deref())
+        .context("Invalid --a time")?;
+    let time_b = cliutil::system_time_from_date_and_adjuster(&opts.b, opts.yesterdays.as_deref())
+        .context("Invalid --b time")?;
+
+    let mut advance = new_advance(logger, dir, host, port, snapshot, time_a)?;
+    let model_a = advance
+        .jump_sample_to(time_a)
+        .with_context(|| format!("No sample found at --a {}", opts.a))?;
+    let model_b = advance
+        .jump_sample_to(time_b)
+        .with_context(|| format!("No sample found at --b {}", opts.b))?;
+
+    let rows = diff_models(&model_a, &model_b, &opts, &cmd);
+    let mut output: Box<dyn Write> = match opts.output.as_ref() {
+        Some(file_path) => Box::new(File::create(file_path)?),
+        None => Box::new(io::stdout()),
+    };
+    if !opts.disable_title && matches!(opts.output_format, None | Some(OutputFormat::Raw)) {
+        writeln!(
+            output,
+            "A: {}\nB: {}\n",
+            timestamp_to_datetime(&(get_unix_timestamp(model_a.timestamp) as i64)),
+            timestamp_to_datetime(&(get_unix_timestamp(model_b.timestamp) as i64)),
+        )?;
+    }
+    write_rows(
+        &rows,
+        output.as_mut(),
+        opts.output_format,
+        opts.disable_title,
+    )
+}
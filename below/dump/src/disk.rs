@@ -20,6 +20,7 @@ pub struct Disk {
     opts: GeneralOpt,
     select: Option<SingleDiskModelFieldId>,
     fields: Vec<DiskField>,
+    where_expr: Option<FilterExpr<SingleDiskModelFieldId>>,
 }
 
 impl Disk {
@@ -27,12 +28,13 @@ impl Disk {
         opts: &GeneralOpt,
         select: Option<SingleDiskModelFieldId>,
         fields: Vec<DiskField>,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        Ok(Self {
             opts: opts.to_owned(),
             select,
             fields,
-        }
+            where_expr: bind_where_expr(opts)?,
+        })
     }
 }
 
@@ -63,6 +65,11 @@ impl Dumper for Disk {
                     _ => Some(model),
                 },
             )
+            .filter(|model| {
+                self.where_expr
+                    .as_ref()
+                    .map_or(true, |expr| expr.eval(model))
+            })
             .collect();
 
         if let Some(field_id) = &self.select {
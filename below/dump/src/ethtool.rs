@@ -3,14 +3,16 @@ use super::*;
 pub struct EthtoolQueue {
     opts: GeneralOpt,
     fields: Vec<EthtoolQueueField>,
+    where_expr: Option<FilterExpr<model::SingleQueueModelFieldId>>,
 }
 
 impl EthtoolQueue {
-    pub fn new(opts: &GeneralOpt, fields: Vec<EthtoolQueueField>) -> Self {
-        Self {
+    pub fn new(opts: &GeneralOpt, fields: Vec<EthtoolQueueField>) -> Result<Self> {
+        Ok(Self {
             opts: opts.to_owned(),
             fields,
-        }
+            where_expr: bind_where_expr(opts)?,
+        })
     }
 }
 
@@ -29,6 +31,11 @@ impl Dumper for EthtoolQueue {
                 queues.push(queue);
             }
         }
+        queues.retain(|queue| {
+            self.where_expr
+                .as_ref()
+                .map_or(true, |expr| expr.eval(queue))
+        });
 
         // Return if we filtered everything.
         if queues.is_empty() {
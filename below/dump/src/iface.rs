@@ -20,6 +20,7 @@ pub struct Iface {
     opts: GeneralOpt,
     select: Option<SingleNetModelFieldId>,
     fields: Vec<IfaceField>,
+    where_expr: Option<FilterExpr<SingleNetModelFieldId>>,
 }
 
 impl Iface {
@@ -27,12 +28,13 @@ impl Iface {
         opts: &GeneralOpt,
         select: Option<SingleNetModelFieldId>,
         fields: Vec<IfaceField>,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        Ok(Self {
             opts: opts.to_owned(),
             select,
             fields,
-        }
+            where_expr: bind_where_expr(opts)?,
+        })
     }
 }
 
@@ -66,6 +68,11 @@ impl Dumper for Iface {
                     _ => true,
                 },
             )
+            .filter(|(_, model)| {
+                self.where_expr
+                    .as_ref()
+                    .map_or(true, |expr| expr.eval(model))
+            })
             .map(|(_, model)| {
                 match self.opts.output_format {
                     Some(OutputFormat::Raw) | None => write!(
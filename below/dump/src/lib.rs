@@ -32,6 +32,8 @@ use common::cliutil;
 use common::util::get_belowrc_dump_section_key;
 use common::util::get_belowrc_filename;
 use common::util::timestamp_to_datetime;
+use common::util::ConfigIssue;
+use model::filter_expr::FilterExpr;
 use model::Field;
 use model::FieldId;
 use model::Queriable;
@@ -47,13 +49,16 @@ use toml::value::Value as TValue;
 
 pub mod btrfs;
 pub mod cgroup;
+pub mod collector;
 pub mod command;
+pub mod diff;
 pub mod disk;
 pub mod ethtool;
 pub mod iface;
 pub mod network;
 pub mod print;
 pub mod process;
+pub mod query;
 pub mod system;
 pub mod tc;
 pub mod tmain;
@@ -122,6 +127,16 @@ pub type IfaceField = DumpField<model::SingleNetModelFieldId>;
 pub type TransportField = DumpField<model::NetworkModelFieldId>;
 pub type EthtoolQueueField = DumpField<model::SingleQueueModelFieldId>;
 pub type TcField = DumpField<model::SingleTcModelFieldId>;
+pub type CollectorField = DumpField<model::CollectorHealthModelFieldId>;
+
+/// Bind the --where expression, if any, to the FieldId of the dumped model.
+fn bind_where_expr<F: FieldId + FromStr>(opts: &GeneralOpt) -> Result<Option<FilterExpr<F>>> {
+    opts.where_expr
+        .as_ref()
+        .map(|expr| expr.bind())
+        .transpose()
+        .context("Invalid --where expression")
+}
 
 fn get_advance(
     logger: slog::Logger,
@@ -138,6 +153,21 @@ fn get_advance(
         opts.yesterdays.as_deref(),
     )?;
 
+    let advance = new_advance(logger, dir, host, port, snapshot, time_begin)?;
+
+    Ok((time_begin, time_end, advance))
+}
+
+/// Create an initialized Advance reading from the local store, a remote host
+/// or a snapshot, starting at time_begin.
+fn new_advance(
+    logger: slog::Logger,
+    dir: PathBuf,
+    host: Option<String>,
+    port: Option<u16>,
+    snapshot: Option<String>,
+    time_begin: SystemTime,
+) -> Result<Advance> {
     let mut advance = match (host, snapshot) {
         (None, None) => new_advance_local(logger.clone(), dir, time_begin),
         (Some(host), None) => new_advance_remote(logger.clone(), host, port, time_begin)?,
@@ -159,7 +189,7 @@ fn get_advance(
 
     advance.initialize();
 
-    Ok((time_begin, time_end, advance))
+    Ok(advance)
 }
 
 /// Try to read $HOME/.config/below/belowrc file and generate a list of keys which will
@@ -214,6 +244,67 @@ pub fn parse_pattern<T: FromStr>(
     )
 }
 
+/// Check the [dump.<subcommand>] patterns of a belowrc, which parse_pattern
+/// only finds invalid when they are used.
+pub fn check_patterns(dumprc: &TValue) -> Vec<ConfigIssue> {
+    fn check<T: FromStr<Err = Error>>(key: &str, fields: &TValue, issues: &mut Vec<ConfigIssue>) {
+        match fields.as_array() {
+            Some(fields) => {
+                for field in fields {
+                    match field.as_str().map(T::from_str) {
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => issues.push(ConfigIssue::error(key, format!("{:#}", e))),
+                        None => issues.push(ConfigIssue::error(
+                            key,
+                            format!("Field {} is not a string", field),
+                        )),
+                    }
+                }
+            }
+            None => issues.push(ConfigIssue::error(
+                key,
+                "Pattern must be an array of fields",
+            )),
+        }
+    }
+
+    let section = get_belowrc_dump_section_key();
+    let subcommands = match dumprc.as_table() {
+        Some(subcommands) => subcommands,
+        None => return vec![ConfigIssue::error(section, "Not a table")],
+    };
+    let mut issues = Vec::new();
+    for (subcommand, patterns) in subcommands {
+        let key = format!("{}.{}", section, subcommand);
+        let check_pattern: fn(&str, &TValue, &mut Vec<ConfigIssue>) = match subcommand.as_str() {
+            "system" => check::<command::SystemOptionField>,
+            "disk" => check::<command::DiskOptionField>,
+            "btrfs" => check::<command::BtrfsOptionField>,
+            "process" => check::<command::ProcessOptionField>,
+            "cgroup" => check::<command::CgroupOptionField>,
+            "iface" => check::<command::IfaceOptionField>,
+            "network" => check::<command::NetworkOptionField>,
+            "transport" => check::<command::TransportOptionField>,
+            "ethtool_queue" => check::<command::EthtoolQueueOptionField>,
+            "tc" => check::<command::TcOptionField>,
+            "collector" => check::<command::CollectorOptionField>,
+            _ => {
+                issues.push(ConfigIssue::warning(key, "Unknown dump subcommand"));
+                continue;
+            }
+        };
+        match patterns.as_table() {
+            Some(patterns) => {
+                for (pattern, fields) in patterns {
+                    check_pattern(&format!("{}.{}", key, pattern), fields, &mut issues);
+                }
+            }
+            None => issues.push(ConfigIssue::error(key, "Not a table")),
+        }
+    }
+    issues
+}
+
 pub fn run(
     logger: slog::Logger,
     errs: Receiver<Error>,
@@ -247,7 +338,7 @@ pub fn run(
                 },
                 detail,
             );
-            let system = system::System::new(&opts, fields);
+            let system = system::System::new(&opts, fields)?;
             let mut output: Box<dyn Write> = match opts.output.as_ref() {
                 Some(file_path) => Box::new(File::create(file_path)?),
                 None => Box::new(io::stdout()),
@@ -260,6 +351,7 @@ pub fn run(
                 output.as_mut(),
                 opts.output_format,
                 opts.br,
+                opts.step.map(Into::into),
                 errs,
             )
         }
@@ -285,7 +377,7 @@ pub fn run(
                 },
                 detail,
             );
-            let disk = disk::Disk::new(&opts, select, fields);
+            let disk = disk::Disk::new(&opts, select, fields)?;
             let mut output: Box<dyn Write> = match opts.output.as_ref() {
                 Some(file_path) => Box::new(File::create(file_path)?),
                 None => Box::new(io::stdout()),
@@ -298,6 +390,7 @@ pub fn run(
                 output.as_mut(),
                 opts.output_format,
                 opts.br,
+                opts.step.map(Into::into),
                 errs,
             )
         }
@@ -323,7 +416,7 @@ pub fn run(
                 },
                 detail,
             );
-            let btrfs = btrfs::Btrfs::new(&opts, select, fields);
+            let btrfs = btrfs::Btrfs::new(&opts, select, fields)?;
             let mut output: Box<dyn Write> = match opts.output.as_ref() {
                 Some(file_path) => Box::new(File::create(file_path)?),
                 None => Box::new(io::stdout()),
@@ -336,6 +429,7 @@ pub fn run(
                 output.as_mut(),
                 opts.output_format,
                 opts.br,
+                opts.step.map(Into::into),
                 errs,
             )
         }
@@ -361,7 +455,7 @@ pub fn run(
                 },
                 detail,
             );
-            let process = process::Process::new(&opts, select, fields);
+            let process = process::Process::new(&opts, select, fields)?;
             let mut output: Box<dyn Write> = match opts.output.as_ref() {
                 Some(file_path) => Box::new(File::create(file_path)?),
                 None => Box::new(io::stdout()),
@@ -374,6 +468,7 @@ pub fn run(
                 output.as_mut(),
                 opts.output_format,
                 opts.br,
+                opts.step.map(Into::into),
                 errs,
             )
         }
@@ -399,7 +494,7 @@ pub fn run(
                 },
                 detail,
             );
-            let cgroup = cgroup::Cgroup::new(&opts, select, fields);
+            let cgroup = cgroup::Cgroup::new(&opts, select, fields)?;
             let mut output: Box<dyn Write> = match opts.output.as_ref() {
                 Some(file_path) => Box::new(File::create(file_path)?),
                 None => Box::new(io::stdout()),
@@ -412,6 +507,7 @@ pub fn run(
                 output.as_mut(),
                 opts.output_format,
                 opts.br,
+                opts.step.map(Into::into),
                 errs,
             )
         }
@@ -437,7 +533,7 @@ pub fn run(
                 },
                 detail,
             );
-            let iface = iface::Iface::new(&opts, select, fields);
+            let iface = iface::Iface::new(&opts, select, fields)?;
             let mut output: Box<dyn Write> = match opts.output.as_ref() {
                 Some(file_path) => Box::new(File::create(file_path)?),
                 None => Box::new(io::stdout()),
@@ -450,6 +546,7 @@ pub fn run(
                 output.as_mut(),
                 opts.output_format,
                 opts.br,
+                opts.step.map(Into::into),
                 errs,
             )
         }
@@ -474,7 +571,7 @@ pub fn run(
                 },
                 detail,
             );
-            let network = network::Network::new(&opts, fields);
+            let network = network::Network::new(&opts, fields)?;
             let mut output: Box<dyn Write> = match opts.output.as_ref() {
                 Some(file_path) => Box::new(File::create(file_path)?),
                 None => Box::new(io::stdout()),
@@ -487,6 +584,7 @@ pub fn run(
                 output.as_mut(),
                 opts.output_format,
                 opts.br,
+                opts.step.map(Into::into),
                 errs,
             )
         }
@@ -511,7 +609,7 @@ pub fn run(
                 },
                 detail,
             );
-            let transport = transport::Transport::new(&opts, fields);
+            let transport = transport::Transport::new(&opts, fields)?;
             let mut output: Box<dyn Write> = match opts.output.as_ref() {
                 Some(file_path) => Box::new(File::create(file_path)?),
                 None => Box::new(io::stdout()),
@@ -524,6 +622,7 @@ pub fn run(
                 output.as_mut(),
                 opts.output_format,
                 opts.br,
+                opts.step.map(Into::into),
                 errs,
             )
         }
@@ -548,7 +647,7 @@ pub fn run(
                 },
                 detail,
             );
-            let ethtool = ethtool::EthtoolQueue::new(&opts, fields);
+            let ethtool = ethtool::EthtoolQueue::new(&opts, fields)?;
             let mut output: Box<dyn Write> = match opts.output.as_ref() {
                 Some(file_path) => Box::new(File::create(file_path)?),
                 None => Box::new(io::stdout()),
@@ -561,6 +660,7 @@ pub fn run(
                 output.as_mut(),
                 opts.output_format,
                 opts.br,
+                opts.step.map(Into::into),
                 errs,
             )
         }
@@ -584,7 +684,7 @@ pub fn run(
                 },
                 detail,
             );
-            let tc = tc::Tc::new(&opts, fields);
+            let tc = tc::Tc::new(&opts, fields)?;
             let mut output: Box<dyn Write> = match opts.output.as_ref() {
                 Some(file_path) => Box::new(File::create(file_path)?),
                 None => Box::new(io::stdout()),
@@ -597,6 +697,45 @@ pub fn run(
                 output.as_mut(),
                 opts.output_format,
                 opts.br,
+                opts.step.map(Into::into),
+                errs,
+            )
+        }
+        DumpCommand::Collector {
+            fields,
+            opts,
+            pattern,
+        } => {
+            let (time_begin, time_end, advance) =
+                get_advance(logger, dir, host, port, snapshot, &opts)?;
+            let default = opts.everything || opts.default;
+            let detail = opts.everything || opts.detail;
+            let fields = if let Some(pattern_key) = pattern {
+                parse_pattern(filename, pattern_key, "collector")
+            } else {
+                fields
+            };
+            let fields = expand_fields(
+                match fields.as_ref() {
+                    Some(fields) if !default => fields,
+                    _ => command::DEFAULT_COLLECTOR_FIELDS,
+                },
+                detail,
+            );
+            let collector = collector::Collector::new(&opts, fields)?;
+            let mut output: Box<dyn Write> = match opts.output.as_ref() {
+                Some(file_path) => Box::new(File::create(file_path)?),
+                None => Box::new(io::stdout()),
+            };
+            dump_timeseries(
+                advance,
+                time_begin,
+                time_end,
+                &collector,
+                output.as_mut(),
+                opts.output_format,
+                opts.br,
+                opts.step.map(Into::into),
                 errs,
             )
         }
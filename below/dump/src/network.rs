@@ -17,14 +17,16 @@ use super::*;
 pub struct Network {
     opts: GeneralOpt,
     fields: Vec<NetworkField>,
+    where_expr: Option<FilterExpr<model::NetworkModelFieldId>>,
 }
 
 impl Network {
-    pub fn new(opts: &GeneralOpt, fields: Vec<NetworkField>) -> Self {
-        Self {
+    pub fn new(opts: &GeneralOpt, fields: Vec<NetworkField>) -> Result<Self> {
+        Ok(Self {
             opts: opts.to_owned(),
             fields,
-        }
+            where_expr: bind_where_expr(opts)?,
+        })
     }
 }
 
@@ -37,6 +39,15 @@ impl Dumper for Network {
         round: &mut usize,
         comma_flag: bool,
     ) -> Result<IterExecResult> {
+        // Samples not matching --where are skipped as a whole
+        if !self
+            .where_expr
+            .as_ref()
+            .map_or(true, |expr| expr.eval(&model.network))
+        {
+            return Ok(IterExecResult::Skip);
+        }
+
         match self.opts.output_format {
             Some(OutputFormat::Raw) | None => write!(
                 output,
@@ -20,6 +20,7 @@ pub struct Process {
     opts: GeneralOpt,
     select: Option<SingleProcessModelFieldId>,
     fields: Vec<ProcessField>,
+    where_expr: Option<FilterExpr<SingleProcessModelFieldId>>,
 }
 
 impl Process {
@@ -27,12 +28,13 @@ impl Process {
         opts: &GeneralOpt,
         select: Option<SingleProcessModelFieldId>,
         fields: Vec<ProcessField>,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        Ok(Self {
             opts: opts.to_owned(),
             select,
             fields,
-        }
+            where_expr: bind_where_expr(opts)?,
+        })
     }
 }
 
@@ -62,6 +64,7 @@ impl Dumper for Process {
                     _ => Some(spm),
                 },
             )
+            .filter(|spm| self.where_expr.as_ref().map_or(true, |expr| expr.eval(spm)))
             .collect();
 
         // Return if we filtered everything.
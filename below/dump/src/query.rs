@@ -0,0 +1,409 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::Parser;
+use model::CgroupModel;
+use model::CgroupPath;
+use model::Model;
+use model::ModelFieldId;
+use model::QueriableContainer;
+use once_cell::sync::Lazy;
+use render::RenderConfig;
+
+use super::*;
+
+pub const QUERY_ABOUT: &str = "Print a timeseries of arbitrary field paths";
+
+pub static QUERY_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
+    format!(
+        r#"{about}
+
+Each field path is a field id of the whole model, e.g. system.cpu.usage_pct,
+process.processes.<pid>.cpu.usage_pct or
+cgroup.path:/system.slice/foo.service/.mem.total. A `*` in place of a cpu,
+disk, interface, pid or other key matches every key, and `*` as a component of
+a cgroup path matches every child cgroup. Wildcards are expanded against the
+first sample, so entities appearing later in the time range are not
+included.
+
+********************** Example Commands **********************
+
+Usage of every cpu over the last hour:
+
+$ below query -b "1h ago" "system.cpus.*.usage_pct"
+
+Memory of the direct children of system.slice as csv:
+
+$ below query -b "08:00" -e "09:00" -O csv "cgroup.path:/system.slice/*/.mem.total"
+
+Read rate of every disk, one row per minute:
+
+$ below query -b "1h ago" --step 1m "system.disks.*.read_bytes_per_sec"
+
+"#,
+        about = QUERY_ABOUT,
+    )
+});
+
+#[derive(Debug, Parser, Default, Clone)]
+pub struct QueryOpts {
+    /// Field paths to print, e.g. system.cpu.usage_pct
+    #[clap(required = true)]
+    pub field_paths: Vec<String>,
+    /// Begin time, same format as replay
+    #[clap(long, short)]
+    pub begin: String,
+    /// End time, same format as replay
+    #[clap(long, short, group = "time")]
+    pub end: Option<String>,
+    /// Time string specifying the duration, e.g. "10 min"{n}
+    /// Keywords: days min, h, sec{n}
+    /// Relative: {humantime}, e.g. "2 days 3 hr 15m 10sec"{n}
+    /// _
+    #[clap(long, group = "time")]
+    pub duration: Option<String>,
+    /// Downsample the output to one row per step, e.g. "1m". Rates are
+    /// averaged over the whole step rather than taken from a single sample.
+    #[clap(long)]
+    pub step: Option<humantime::Duration>,
+    /// Days adjuster, same as -r option in replay.
+    #[clap(short = 'r')]
+    pub yesterdays: Option<String>,
+    /// Repeat title, for each N line, it will render a line of title. Only for raw output format.
+    #[clap(long = "repeat-title")]
+    pub repeat_title: Option<usize>,
+    /// Output format. Choose from raw, csv, tsv, kv, json, openmetrics. Default to raw
+    #[clap(long, short = 'O')]
+    pub output_format: Option<OutputFormat>,
+    /// Output destination, default to stdout.
+    #[clap(long, short)]
+    pub output: Option<String>,
+    /// Disable title in raw, csv or tsv format output
+    #[clap(long)]
+    pub disable_title: bool,
+    /// Line break symbol between samples
+    #[clap(long)]
+    pub br: Option<String>,
+    /// Dump raw data without units or conversion
+    #[clap(long)]
+    pub raw: bool,
+}
+
+/// Names of the fields of SingleCgroupModel in serialized form, for the
+/// fields whose field id uses a preferred name.
+const CGROUP_SERIALIZED_NAMES: [(&str, &str); 5] = [
+    ("props", "properties"),
+    ("mem", "memory"),
+    ("io_details", "io"),
+    ("io", "io_total"),
+    ("mem_numa", "memory_numa_stat"),
+];
+
+/// Name of the field of SingleCgroupModel in serialized form whose field id
+/// starts with name.
+pub fn cgroup_serialized_name(name: &str) -> &str {
+    CGROUP_SERIALIZED_NAMES
+        .iter()
+        .find(|(preferred, _)| *preferred == name)
+        .map_or(name, |(_, serialized)| *serialized)
+}
+
+/// Follow path through the serialized form of a model. Keys may contain dots,
+/// e.g. VLAN interfaces, so the longest matching key is taken.
+fn walk_value<'a>(mut value: &'a Value, mut path: &str) -> Option<&'a Value> {
+    while !path.is_empty() {
+        let (next, len) = match value {
+            Value::Object(map) => map
+                .iter()
+                .filter(|(key, _)| {
+                    path.strip_prefix(key.as_str())
+                        .map_or(false, |rest| rest.is_empty() || rest.starts_with('.'))
+                })
+                .max_by_key(|(key, _)| key.len())
+                .map(|(key, next)| (next, key.len()))?,
+            Value::Array(array) => {
+                let idx = path.split('.').next()?;
+                (array.get(idx.parse::<usize>().ok()?)?, idx.len())
+            }
+            _ => return None,
+        };
+        value = next;
+        path = path[len..].strip_prefix('.').unwrap_or("");
+    }
+    Some(value)
+}
+
+/// Keys of the container at prefix, which is a field path ending right before
+/// a `*`.
+fn wildcard_keys(model: &Model, prefix: &str) -> Result<Vec<String>> {
+    if let Some(cgroup_prefix) = prefix.strip_prefix("cgroup.") {
+        let (path, rest) = match CgroupModel::split(cgroup_prefix) {
+            Some((path, rest)) => (path, rest),
+            // The wildcard is a component of the cgroup path
+            None => {
+                let path = cgroup_prefix
+                    .strip_suffix('/')
+                    .context("`*` must be a whole cgroup path component")?;
+                let cgroup = CgroupPath::from_str(path)?
+                    .path
+                    .iter()
+                    .try_fold(&model.cgroup, |cgroup, part| {
+                        cgroup.children.get(part.as_str())
+                    });
+                return Ok(cgroup.map_or_else(Vec::new, |cgroup| {
+                    cgroup
+                        .children
+                        .iter()
+                        .map(|child| child.data.name.clone())
+                        .collect()
+                }));
+            }
+        };
+        let cgroup = match model.cgroup.get_item(&CgroupPath::from_str(path)?) {
+            Some(cgroup) => cgroup,
+            None => return Ok(Vec::new()),
+        };
+        let rest = match rest.split_once('.') {
+            Some((name, rest)) => format!("{}.{}", cgroup_serialized_name(name), rest),
+            None => rest.to_owned(),
+        };
+        return Ok(container_keys(&serde_json::to_value(cgroup)?, &rest));
+    }
+    Ok(container_keys(&serde_json::to_value(model)?, prefix))
+}
+
+fn container_keys(value: &Value, prefix: &str) -> Vec<String> {
+    let path = prefix.strip_suffix('.').unwrap_or(prefix);
+    match walk_value(value, path) {
+        Some(Value::Object(map)) => map.keys().cloned().collect(),
+        Some(Value::Array(array)) => (0..array.len()).map(|idx| idx.to_string()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Expand the wildcards of a field path against model and parse the results.
+/// Expansions that are not valid field ids, e.g. a `*` matching the name of a
+/// struct field rather than a key, are dropped.
+pub fn expand_field_path(model: &Model, field_path: &str) -> Result<Vec<ModelFieldId>> {
+    if !field_path.contains('*') {
+        return ModelFieldId::from_str(field_path)
+            .map(|field_id| vec![field_id])
+            .with_context(|| format!("Invalid field path {}", field_path));
+    }
+    let mut paths = vec![field_path.to_owned()];
+    while paths.iter().any(|path| path.contains('*')) {
+        let mut expanded = Vec::new();
+        for path in paths {
+            match path.find('*') {
+                Some(pos) => {
+                    for key in wildcard_keys(model, &path[..pos])? {
+                        expanded.push(format!("{}{}{}", &path[..pos], key, &path[pos + 1..]));
+                    }
+                }
+                None => expanded.push(path),
+            }
+        }
+        paths = expanded;
+    }
+    let field_ids: Vec<ModelFieldId> = paths
+        .iter()
+        .filter_map(|path| ModelFieldId::from_str(path).ok())
+        .collect();
+    if field_ids.is_empty() {
+        bail!("{} matched no field in the first sample", field_path);
+    }
+    Ok(field_ids)
+}
+
+pub struct Query {
+    opts: QueryOpts,
+    fields: Vec<DumpField<ModelFieldId>>,
+}
+
+impl Query {
+    pub fn new(opts: &QueryOpts, field_ids: Vec<ModelFieldId>) -> Self {
+        Self {
+            opts: opts.to_owned(),
+            fields: std::iter::once(DumpField::Common(CommonField::Datetime))
+                .chain(field_ids.into_iter().map(DumpField::FieldId))
+                .collect(),
+        }
+    }
+
+    /// Render config of field, titled with its field path since the usual
+    /// titles such as "Usage" are ambiguous outside of their model.
+    fn get_render_config(&self, field: &DumpField<ModelFieldId>) -> RenderConfig {
+        let mut config = field.get_render_config();
+        if let DumpField::FieldId(field_id) = field {
+            config.title = Some(field_id.to_string());
+            config.width = None;
+        }
+        if self.opts.raw {
+            config.format = None;
+            config.suffix = None;
+        }
+        config
+    }
+
+    fn render_line(
+        &self,
+        ctx: &CommonFieldContext,
+        model: &Model,
+        sep: &str,
+        fixed_width: bool,
+    ) -> String {
+        let mut line = String::new();
+        for field in &self.fields {
+            let config = self.get_render_config(field);
+            line.push_str(&config.render(field.get_field(ctx, model), fixed_width));
+            line.push_str(sep);
+        }
+        line.push('\n');
+        line
+    }
+
+    fn render_title_line(&self, sep: &str, fixed_width: bool) -> String {
+        let mut line = String::new();
+        for field in &self.fields {
+            line.push_str(&self.get_render_config(field).render_title(fixed_width));
+            line.push_str(sep);
+        }
+        line.push('\n');
+        line
+    }
+}
+
+impl Dumper for Query {
+    fn dump_model(
+        &self,
+        ctx: &CommonFieldContext,
+        model: &Model,
+        output: &mut dyn Write,
+        round: &mut usize,
+        comma_flag: bool,
+    ) -> Result<IterExecResult> {
+        let title = !self.opts.disable_title && *round == 0;
+        match self.opts.output_format {
+            Some(OutputFormat::Raw) | None => {
+                let repeat = self.opts.repeat_title.unwrap_or(0);
+                if !self.opts.disable_title
+                    && (*round == 0 || (repeat != 0 && *round % repeat == 0))
+                {
+                    write!(output, "{}", self.render_title_line(" ", true))?;
+                }
+                write!(output, "{}", self.render_line(ctx, model, " ", true))?;
+            }
+            Some(OutputFormat::Csv) => {
+                if title {
+                    write!(output, "{}", self.render_title_line(",", false))?;
+                }
+                write!(output, "{}", self.render_line(ctx, model, ",", false))?;
+            }
+            Some(OutputFormat::Tsv) => {
+                if title {
+                    write!(output, "{}", self.render_title_line("\t", false))?;
+                }
+                write!(output, "{}", self.render_line(ctx, model, "\t", false))?;
+            }
+            Some(OutputFormat::KeyVal) => {
+                for field in &self.fields {
+                    let config = self.get_render_config(field);
+                    writeln!(
+                        output,
+                        "{}: {}",
+                        config.render_title(false),
+                        config.render(field.get_field(ctx, model), false)
+                    )?;
+                }
+                writeln!(output)?;
+            }
+            Some(OutputFormat::Json) => {
+                let mut par = json!({});
+                for field in &self.fields {
+                    let config = self.get_render_config(field);
+                    par[config.render_title(false)] =
+                        json!(config.render(field.get_field(ctx, model), false));
+                }
+                if comma_flag {
+                    write!(output, ",{}", par)?;
+                } else {
+                    write!(output, "{}", par)?;
+                }
+            }
+            Some(OutputFormat::OpenMetrics) => {
+                for field in &self.fields {
+                    if let DumpField::FieldId(field_id) = field {
+                        // OpenMetrics only allows [a-zA-Z0-9_:] in metric names
+                        let key: String = field_id
+                            .to_string()
+                            .chars()
+                            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                            .collect();
+                        if let Some(metric) = field.dump_field_openmetrics(&key, ctx, model) {
+                            write!(output, "{}", metric)?;
+                        }
+                    }
+                }
+            }
+        };
+
+        *round += 1;
+
+        Ok(IterExecResult::Success)
+    }
+}
+
+pub fn run(
+    logger: slog::Logger,
+    errs: Receiver<Error>,
+    dir: PathBuf,
+    host: Option<String>,
+    port: Option<u16>,
+    snapshot: Option<String>,
+    opts: QueryOpts,
+) -> Result<()> {
+    let (time_begin, time_end) = cliutil::system_time_range_from_date_and_adjuster(
+        opts.begin.as_str(),
+        opts.end.as_deref(),
+        opts.duration.as_deref(),
+        opts.yesterdays.as_deref(),
+    )?;
+    let mut advance = new_advance(logger, dir, host, port, snapshot, time_begin)?;
+
+    // Wildcards are expanded once so that every row has the same columns
+    let first_model = advance
+        .jump_sample_to(time_begin)
+        .context("No initial sample could be found!")?;
+    let mut field_ids = Vec::new();
+    for field_path in &opts.field_paths {
+        field_ids.extend(expand_field_path(&first_model, field_path)?);
+    }
+
+    let query = Query::new(&opts, field_ids);
+    let mut output: Box<dyn Write> = match opts.output.as_ref() {
+        Some(file_path) => Box::new(File::create(file_path)?),
+        None => Box::new(io::stdout()),
+    };
+    dump_timeseries(
+        advance,
+        time_begin,
+        time_end,
+        &query,
+        output.as_mut(),
+        opts.output_format,
+        opts.br,
+        opts.step.map(Into::into),
+        errs,
+    )
+}
@@ -17,14 +17,16 @@ use super::*;
 pub struct System {
     opts: GeneralOpt,
     fields: Vec<SystemField>,
+    where_expr: Option<FilterExpr<model::SystemModelFieldId>>,
 }
 
 impl System {
-    pub fn new(opts: &GeneralOpt, fields: Vec<SystemField>) -> Self {
-        Self {
+    pub fn new(opts: &GeneralOpt, fields: Vec<SystemField>) -> Result<Self> {
+        Ok(Self {
             opts: opts.to_owned(),
             fields,
-        }
+            where_expr: bind_where_expr(opts)?,
+        })
     }
 }
 
@@ -37,6 +39,15 @@ impl Dumper for System {
         round: &mut usize,
         comma_flag: bool,
     ) -> Result<IterExecResult> {
+        // Samples not matching --where are skipped as a whole
+        if !self
+            .where_expr
+            .as_ref()
+            .map_or(true, |expr| expr.eval(&model.system))
+        {
+            return Ok(IterExecResult::Skip);
+        }
+
         let mut fields = self.fields.clone();
 
         if self.opts.detail || self.opts.everything {
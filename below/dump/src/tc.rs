@@ -5,14 +5,16 @@ use super::*;
 pub struct Tc {
     opts: GeneralOpt,
     fields: Vec<TcField>,
+    where_expr: Option<FilterExpr<model::SingleTcModelFieldId>>,
 }
 
 impl Tc {
-    pub fn new(opts: &GeneralOpt, fields: Vec<TcField>) -> Self {
-        Self {
+    pub fn new(opts: &GeneralOpt, fields: Vec<TcField>) -> Result<Self> {
+        Ok(Self {
             opts: opts.to_owned(),
             fields,
-        }
+            where_expr: bind_where_expr(opts)?,
+        })
     }
 }
 
@@ -26,7 +28,11 @@ impl Dumper for Tc {
         comma_flag: bool,
     ) -> Result<IterExecResult> {
         let tcs: Vec<&SingleTcModel> = match &model.tc {
-            Some(tc_model) => tc_model.tc.iter().collect(),
+            Some(tc_model) => tc_model
+                .tc
+                .iter()
+                .filter(|tc| self.where_expr.as_ref().map_or(true, |expr| expr.eval(tc)))
+                .collect(),
             None => Vec::new(),
         };
         if tcs.is_empty() {
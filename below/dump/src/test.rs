@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::time::Duration;
 
 use command::expand_fields;
@@ -45,7 +46,7 @@ fn test_dump_sys_content() {
         )));
     }
     opts.output_format = Some(OutputFormat::Json);
-    let system_dumper = system::System::new(&opts, fields.clone());
+    let system_dumper = system::System::new(&opts, fields.clone()).expect("Fail to create dumper");
 
     // update model again to populate cpu and io data
     let model = collector
@@ -203,7 +204,8 @@ fn test_dump_process_content() {
     let mut opts: GeneralOpt = Default::default();
     let fields = command::expand_fields(command::DEFAULT_PROCESS_FIELDS, true);
     opts.output_format = Some(OutputFormat::Json);
-    let process_dumper = process::Process::new(&opts, None, fields.clone());
+    let process_dumper =
+        process::Process::new(&opts, None, fields.clone()).expect("Fail to create dumper");
 
     // update model again to populate cpu and io data
     let model = collector
@@ -331,7 +333,8 @@ fn test_dump_proc_select() {
         &opts,
         Some(model::SingleProcessModelFieldId::Pid),
         fields.clone(),
-    );
+    )
+    .expect("Fail to create dumper");
 
     let mut process_content: Vec<u8> = Vec::new();
     let mut round = 0;
@@ -356,7 +359,8 @@ fn test_dump_proc_select() {
         &opts,
         Some(model::SingleProcessModelFieldId::Pid),
         fields.clone(),
-    );
+    )
+    .expect("Fail to create dumper");
 
     process_content = Vec::new();
     round = 0;
@@ -382,7 +386,8 @@ fn test_dump_proc_select() {
     opts.sort = false;
     opts.rsort = true;
     let process_dumper =
-        process::Process::new(&opts, Some(model::SingleProcessModelFieldId::Pid), fields);
+        process::Process::new(&opts, Some(model::SingleProcessModelFieldId::Pid), fields)
+            .expect("Fail to create dumper");
 
     process_content = Vec::new();
     round = 0;
@@ -405,6 +410,102 @@ fn test_dump_proc_select() {
     }
 }
 
+#[test]
+fn test_dump_proc_where() {
+    let logger = get_logger();
+    let mut collector = Collector::new(logger.clone(), Default::default());
+    collector
+        .collect_and_update_model()
+        .expect("Fail to get model");
+    let model = collector
+        .collect_and_update_model()
+        .expect("Fail to get model");
+
+    let fields = command::expand_fields(command::DEFAULT_PROCESS_FIELDS, true);
+    let (pid, spm) = model.process.processes.iter().last().unwrap();
+    let mut opts = GeneralOpt {
+        everything: true,
+        output_format: Some(OutputFormat::Json),
+        where_expr: Some(
+            format!(
+                "pid == {} && (comm == '{}' || !(pid > 0))",
+                pid,
+                spm.comm.as_ref().unwrap()
+            )
+            .parse()
+            .expect("Fail to parse where expression"),
+        ),
+        ..Default::default()
+    };
+    let process_dumper =
+        process::Process::new(&opts, None, fields.clone()).expect("Fail to create dumper");
+
+    let mut process_content: Vec<u8> = Vec::new();
+    let mut round = 0;
+    let ctx = CommonFieldContext {
+        timestamp: 0,
+        hostname: "h".to_string(),
+    };
+    process_dumper
+        .dump_model(&ctx, &model, &mut process_content, &mut round, false)
+        .expect("Failed to dump process model");
+
+    let jval: Value =
+        serde_json::from_slice(&process_content).expect("Fail parse json of process dump");
+    assert_eq!(jval.as_array().unwrap().len(), 1);
+    assert_eq!(jval[0]["Pid"].as_str().unwrap(), pid.to_string());
+
+    // Unknown fields are only detected when binding to the dumped model
+    opts.where_expr = Some("no_such_field > 1".parse().unwrap());
+    assert!(process::Process::new(&opts, None, fields).is_err());
+}
+
+#[test]
+fn test_dump_system_where() {
+    let logger = get_logger();
+    let mut collector = Collector::new(logger.clone(), Default::default());
+    let model = collector
+        .collect_and_update_model()
+        .expect("Fail to get model");
+
+    let fields = command::expand_fields(command::DEFAULT_SYSTEM_FIELDS, true);
+    let ctx = CommonFieldContext {
+        timestamp: 0,
+        hostname: "h".to_string(),
+    };
+    // Dumpers with one row per sample skip samples that do not match
+    for (expr, expected) in [
+        (
+            format!("hostname == '{}'", model.system.hostname),
+            tmain::IterExecResult::Success,
+        ),
+        (
+            format!("hostname != '{}'", model.system.hostname),
+            tmain::IterExecResult::Skip,
+        ),
+    ] {
+        let opts = GeneralOpt {
+            where_expr: Some(expr.parse().expect("Fail to parse where expression")),
+            ..Default::default()
+        };
+        let system_dumper =
+            system::System::new(&opts, fields.clone()).expect("Fail to create dumper");
+        let mut content: Vec<u8> = Vec::new();
+        let result = system_dumper
+            .dump_model(&ctx, &model, &mut content, &mut 0, false)
+            .expect("Failed to dump system model");
+        assert!(result == expected, "Unexpected result for: {}", expr);
+        assert_eq!(content.is_empty(), expected == tmain::IterExecResult::Skip);
+    }
+
+    // Fields are bound to the model of each dumper
+    let opts = GeneralOpt {
+        where_expr: Some("pid == 1".parse().unwrap()),
+        ..Default::default()
+    };
+    assert!(system::System::new(&opts, fields).is_err());
+}
+
 fn traverse_cgroup_tree(model: &model::CgroupModel, jval: &Value) {
     for dump_field in expand_fields(command::DEFAULT_CGROUP_FIELDS, true) {
         match dump_field {
@@ -445,7 +546,7 @@ fn test_dump_cgroup_content() {
     let mut opts: GeneralOpt = Default::default();
     let fields = command::expand_fields(command::DEFAULT_CGROUP_FIELDS, true);
     opts.output_format = Some(OutputFormat::Json);
-    let cgroup_dumper = cgroup::Cgroup::new(&opts, None, fields);
+    let cgroup_dumper = cgroup::Cgroup::new(&opts, None, fields).expect("Fail to create dumper");
 
     // update model again to populate cpu and io data
     let model = collector
@@ -573,7 +674,8 @@ fn test_dump_iface_content() {
     let mut opts: GeneralOpt = Default::default();
     let fields = command::expand_fields(command::DEFAULT_IFACE_FIELDS, true);
     opts.output_format = Some(OutputFormat::Json);
-    let iface_dumper = iface::Iface::new(&opts, None, fields.clone());
+    let iface_dumper =
+        iface::Iface::new(&opts, None, fields.clone()).expect("Fail to create dumper");
 
     // update model again to populate net data
     let model = collector
@@ -693,7 +795,8 @@ fn test_dump_network_content() {
     let mut opts: GeneralOpt = Default::default();
     let fields = command::expand_fields(command::DEFAULT_NETWORK_FIELDS, true);
     opts.output_format = Some(OutputFormat::Json);
-    let network_dumper = network::Network::new(&opts, fields.clone());
+    let network_dumper =
+        network::Network::new(&opts, fields.clone()).expect("Fail to create dumper");
 
     // update model again to populate net data
     let model = collector
@@ -814,7 +917,8 @@ fn test_dump_transport_content() {
     let mut opts: GeneralOpt = Default::default();
     let fields = command::expand_fields(command::DEFAULT_TRANSPORT_FIELDS, true);
     opts.output_format = Some(OutputFormat::Json);
-    let transport_dumper = transport::Transport::new(&opts, fields.clone());
+    let transport_dumper =
+        transport::Transport::new(&opts, fields.clone()).expect("Fail to create dumper");
 
     // update model again to populate net data
     let model = collector
@@ -989,13 +1093,15 @@ fn test_dump_queue_content() {
         gpu: None,
         resctrl: None,
         tc: None,
+        collector: None,
     };
 
     let mut opts: GeneralOpt = Default::default();
     let fields = command::expand_fields(command::DEFAULT_ETHTOOL_QUEUE_FIELDS, true);
 
     opts.output_format = Some(OutputFormat::Json);
-    let queue_dumper = ethtool::EthtoolQueue::new(&opts, fields.clone());
+    let queue_dumper =
+        ethtool::EthtoolQueue::new(&opts, fields.clone()).expect("Fail to create dumper");
 
     let mut queue_content: Vec<u8> = Vec::new();
     let mut round = 0;
@@ -1075,7 +1181,7 @@ fn test_dump_disk_content() {
     let mut opts: GeneralOpt = Default::default();
     let fields = command::expand_fields(command::DEFAULT_DISK_FIELDS, true);
     opts.output_format = Some(OutputFormat::Json);
-    let disk_dumper = disk::Disk::new(&opts, None, fields.clone());
+    let disk_dumper = disk::Disk::new(&opts, None, fields.clone()).expect("Fail to create dumper");
 
     // update model again to populate disk data
     let model = collector
@@ -1344,13 +1450,14 @@ fn test_dump_tc_content() {
         gpu: None,
         resctrl: None,
         tc: Some(model::TcModel { tc: tc_models }),
+        collector: None,
     };
 
     let mut opts: GeneralOpt = Default::default();
     let fields = command::expand_fields(command::DEFAULT_TC_FIELDS, true);
 
     opts.output_format = Some(OutputFormat::Json);
-    let queue_dumper = tc::Tc::new(&opts, fields.clone());
+    let queue_dumper = tc::Tc::new(&opts, fields.clone()).expect("Fail to create dumper");
 
     let mut queue_content: Vec<u8> = Vec::new();
     let mut round = 0;
@@ -1439,3 +1546,230 @@ fn test_dump_tc_content() {
     ]);
     assert_eq!(jval, expected_json);
 }
+
+#[test]
+fn test_diff_process() {
+    let logger = get_logger();
+    let mut collector = Collector::new(logger.clone(), Default::default());
+    collector
+        .collect_and_update_model()
+        .expect("Fail to get model");
+    let model_a = collector
+        .collect_and_update_model()
+        .expect("Fail to get model");
+
+    // Make one process disappear and change the rss of another
+    let mut model_b = model_a.clone();
+    let (gone_pid, _) = model_a.process.processes.iter().next().unwrap();
+    model_b.process.processes.remove(gone_pid);
+    let (pid, spm) = model_b.process.processes.iter_mut().last().unwrap();
+    let mem = spm.mem.get_or_insert_with(Default::default);
+    mem.rss_bytes = Some(mem.rss_bytes.unwrap_or(0) + 4096);
+    let pid = *pid;
+
+    let opts = diff::DiffOpts {
+        raw: true,
+        ..Default::default()
+    };
+    let cmd = diff::DiffCommand::Process {
+        fields: Some(vec!["mem.rss_bytes".parse().unwrap()]),
+    };
+    let rows = diff::diff_models(&model_a, &model_b, &opts, &cmd);
+    assert_eq!(rows.len(), 2);
+    assert!(
+        rows.iter()
+            .any(|row| row[0].starts_with(&format!("{} (", gone_pid)) && row[1] == "disappeared")
+    );
+    let changed = rows
+        .iter()
+        .find(|row| row[0].starts_with(&format!("{} (", pid)))
+        .expect("Changed process not in diff");
+    assert_eq!(changed[1], "present");
+    assert_eq!(changed[2], "mem.rss_bytes");
+    assert_eq!(changed[5], "+4096");
+
+    let mut content: Vec<u8> = Vec::new();
+    diff::write_rows(&rows, &mut content, Some(OutputFormat::Json), false)
+        .expect("Failed to write diff");
+    let jval: Value = serde_json::from_slice(&content).expect("Fail parse json of diff");
+    assert_eq!(jval.as_array().unwrap().len(), 2);
+    assert!(
+        diff::write_rows(&rows, &mut Vec::new(), Some(OutputFormat::OpenMetrics), false).is_err()
+    );
+}
+
+#[test]
+fn test_query_cgroup_serialized_names() {
+    // Every field of SingleCgroupModel must map to its own serialized name,
+    // so that wildcards expand to all of them
+    let serialized = serde_json::to_value(model::SingleCgroupModel::default())
+        .expect("Failed to serialize cgroup model");
+    let serialized_names: BTreeSet<String> = serialized
+        .as_object()
+        .expect("Cgroup model not serialized to an object")
+        .keys()
+        .cloned()
+        .collect();
+    let mut mapped: BTreeMap<String, String> = BTreeMap::new();
+    for field_id in enum_iterator::all::<model::SingleCgroupModelFieldId>() {
+        let field_id = field_id.to_string();
+        let name = field_id.split('.').next().unwrap();
+        mapped.insert(
+            name.to_owned(),
+            query::cgroup_serialized_name(name).to_owned(),
+        );
+    }
+    for (name, serialized_name) in &mapped {
+        assert!(
+            serialized_names.contains(serialized_name),
+            "{} maps to {}, which is not a serialized field of SingleCgroupModel",
+            name,
+            serialized_name
+        );
+    }
+    assert_eq!(
+        mapped.values().collect::<BTreeSet<_>>().len(),
+        mapped.len(),
+        "Fields of SingleCgroupModel must map to distinct serialized names: {:?}",
+        mapped
+    );
+}
+
+#[test]
+fn test_query() {
+    let logger = get_logger();
+    let mut collector = Collector::new(logger.clone(), Default::default());
+    collector
+        .collect_and_update_model()
+        .expect("Fail to get model");
+    let mut model = collector
+        .collect_and_update_model()
+        .expect("Fail to get model");
+    let mut child = model::CgroupModel::default();
+    child.data.name = "foo.slice".to_owned();
+    child.data.full_path = "/foo.slice".to_owned();
+    model.cgroup.children.insert(child);
+
+    let cpus =
+        query::expand_field_path(&model, "system.cpus.*.usage_pct").expect("Failed to expand cpus");
+    assert_eq!(cpus.len(), model.system.cpus.len());
+    assert_eq!(
+        cpus[0].to_string(),
+        format!(
+            "system.cpus.{}.usage_pct",
+            model.system.cpus.keys().next().unwrap()
+        )
+    );
+    let cgroups = query::expand_field_path(&model, "cgroup.path:/*/.full_path")
+        .expect("Failed to expand cgroups");
+    assert!(
+        cgroups
+            .iter()
+            .any(|field_id| field_id.to_string() == "cgroup.path:/foo.slice/.full_path")
+    );
+    assert!(query::expand_field_path(&model, "system.no_such_field").is_err());
+    assert!(query::expand_field_path(&model, "system.cpus.*.no_such_field").is_err());
+
+    let opts = query::QueryOpts {
+        output_format: Some(OutputFormat::Csv),
+        raw: true,
+        ..Default::default()
+    };
+    let field_ids = vec![
+        "system.hostname".parse().unwrap(),
+        "cgroup.path:/foo.slice/.full_path".parse().unwrap(),
+    ];
+    let query = query::Query::new(&opts, field_ids);
+    let ctx = CommonFieldContext {
+        timestamp: 0,
+        hostname: "h".to_owned(),
+    };
+    let mut content: Vec<u8> = Vec::new();
+    query
+        .dump_model(&ctx, &model, &mut content, &mut 0, false)
+        .expect("Failed to dump query");
+    let content = String::from_utf8(content).expect("Invalid utf8");
+    let mut lines = content.lines();
+    assert_eq!(
+        lines.next(),
+        Some("Datetime,system.hostname,cgroup.path:/foo.slice/.full_path,")
+    );
+    assert_eq!(
+        lines
+            .next()
+            .map(|line| line.split(',').skip(1).collect::<Vec<_>>()),
+        Some(vec![model.system.hostname.as_str(), "/foo.slice", ""])
+    );
+}
+
+#[test]
+fn test_dump_collector() {
+    let mut model = model::Model {
+        time_elapsed: Duration::from_secs(5),
+        timestamp: SystemTime::now(),
+        system: model::SystemModel::default(),
+        cgroup: model::CgroupModel::default(),
+        process: model::ProcessModel::default(),
+        network: model::NetworkModel::default(),
+        gpu: None,
+        resctrl: None,
+        tc: None,
+        collector: None,
+    };
+
+    let mut opts: GeneralOpt = Default::default();
+    opts.output_format = Some(OutputFormat::Json);
+    let fields = command::expand_fields(command::DEFAULT_COLLECTOR_FIELDS, true);
+    let collector_dumper = collector::Collector::new(&opts, fields).expect("Fail to create dumper");
+    let ctx = CommonFieldContext {
+        timestamp: 0,
+        hostname: "h".to_string(),
+    };
+    let mut round = 0;
+
+    // Samples of older versions have no collector health
+    let mut content: Vec<u8> = Vec::new();
+    let result = collector_dumper
+        .dump_model(&ctx, &model, &mut content, &mut round, false)
+        .expect("Failed to dump collector model");
+    assert!(result == tmain::IterExecResult::Skip);
+    assert!(content.is_empty());
+
+    model.collector = Some(model::CollectorHealthModel {
+        collect_latency_usec: Some(12500),
+        cgroup_latency_usec: Some(8000),
+        procfs_latency_usec: Some(3000),
+        netstat_latency_usec: Some(800),
+        ethtool_latency_usec: None,
+        btrfs_latency_usec: None,
+        tc_latency_usec: None,
+        gpu_latency_usec: None,
+        errors: Some(2),
+        exitstat_lost_events: Some(0),
+        store_write_latency_usec: Some(1500000),
+        stale_collectors: Some(["btrfs".to_owned(), "io_stat".to_owned()].into()),
+    });
+    let result = collector_dumper
+        .dump_model(&ctx, &model, &mut content, &mut round, false)
+        .expect("Failed to dump collector model");
+    assert!(result == tmain::IterExecResult::Success);
+
+    let jval: Value = serde_json::from_slice(&content).expect("Fail parse json of collector dump");
+    let expected_json = json!({
+        "Datetime": "1969-12-31 16:00:00",
+        "Collect": "12.5 ms",
+        "Cgroup": "8 ms",
+        "Procfs": "3 ms",
+        "Netstat": "800 us",
+        "Ethtool": "?",
+        "Btrfs": "?",
+        "Tc": "?",
+        "Gpu": "?",
+        "Store Write": "1.5 s",
+        "Errors": "2",
+        "Exitstat Lost": "0",
+        "Stale": "btrfs io_stat",
+        "Timestamp": "0"
+    });
+    assert_eq!(jval, expected_json);
+}
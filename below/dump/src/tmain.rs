@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::time::Duration;
+
 use super::*;
 
 #[derive(PartialEq)]
@@ -47,6 +49,7 @@ pub fn dump_timeseries(
     output: &mut dyn Write,
     output_format: Option<OutputFormat>,
     br: Option<String>,
+    step: Option<Duration>,
     errs: Receiver<Error>,
 ) -> Result<()> {
     let mut model = match advance.jump_sample_to(time_begin) {
@@ -61,6 +64,15 @@ pub fn dump_timeseries(
 
     cliutil::check_initial_sample_time_in_time_range(model.timestamp, time_begin, time_end)?;
 
+    // With a step, every dumped model covers a whole step starting at the
+    // first sample in range.
+    if let Some(step) = step {
+        model = match advance.advance_step(step) {
+            Some(m) => m,
+            None => bail!("Not enough samples to dump a single step"),
+        };
+    }
+
     let json = output_format == Some(OutputFormat::Json);
     let csv = output_format == Some(OutputFormat::Csv);
     let openmetrics = output_format == Some(OutputFormat::OpenMetrics);
@@ -104,7 +116,14 @@ pub fn dump_timeseries(
             break;
         }
 
-        model = match advance.advance(Direction::Forward) {
+        let next_model = match step {
+            // Drop the last step if it ends after time_end
+            Some(step) => advance
+                .advance_step(step)
+                .filter(|m| m.timestamp <= time_end),
+            None => advance.advance(Direction::Forward),
+        };
+        model = match next_model {
             Some(m) => m,
             None => break,
         };
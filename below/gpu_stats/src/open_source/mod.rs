@@ -12,21 +12,275 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! GPU stats read from the vendor neutral DRM interfaces: the sysfs files of
+//! each card and the DRM client keys of /proc/<pid>/fdinfo.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
 use serde::Deserialize;
 use serde::Serialize;
+use thiserror::Error;
+
+#[cfg(test)]
+mod test;
+
+pub const DEFAULT_SYSFS_ROOT: &str = "/sys";
+pub const DEFAULT_PROC_ROOT: &str = "/proc";
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("{1:?}: {0:?}")]
+    IoError(PathBuf, #[source] std::io::Error),
+}
 
-// Open source gpu_stats not implemented yet
+pub type Result<T> = std::result::Result<T, Error>;
 
+/// Stats of a DRM card, e.g. card0.
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
-pub struct GpuMap;
+pub struct GpuDeviceStats {
+    pub driver: Option<String>,
+    pub pci_slot: Option<String>,
+    pub busy_pct: Option<u64>,
+    pub vram_used_bytes: Option<u64>,
+    pub vram_total_bytes: Option<u64>,
+    pub temp_millicelsius: Option<i64>,
+    pub power_microwatts: Option<u64>,
+}
 
-impl std::fmt::Display for GpuMap {
-    fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        Ok(())
-    }
+/// DRM usage of a process, summed over its DRM clients.
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GpuProcessStats {
+    pub comm: Option<String>,
+    /// Cumulative busy time of each engine in ns, from drm-engine-<engine>
+    pub engine_ns: BTreeMap<String, u64>,
+    /// Memory of each region in bytes, from drm-memory-<region>
+    pub memory_bytes: BTreeMap<String, u64>,
+}
+
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GpuMap {
+    /// Keyed by card name
+    pub devices: BTreeMap<String, GpuDeviceStats>,
+    /// Keyed by pid. Only processes with a DRM client are present.
+    pub processes: BTreeMap<i32, GpuProcessStats>,
 }
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct GpuSample {
     pub gpu_map: GpuMap,
 }
+
+/// Missing files are expected as most are driver specific.
+fn read_file(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::IoError(path.to_path_buf(), e)),
+    }
+}
+
+fn read_value<T: FromStr>(path: &Path) -> Result<Option<T>> {
+    Ok(read_file(path)?.and_then(|content| content.trim().parse().ok()))
+}
+
+/// Entries of dir, or none if it does not exist. Entries that disappear while
+/// listing, e.g. exited processes, are skipped.
+fn read_dir_names(dir: &Path) -> Result<Vec<String>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::IoError(dir.to_path_buf(), e)),
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Parse a fdinfo value such as "123 ns" or "4096 KiB" to ns or bytes.
+fn parse_fdinfo_value(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let num = parts.next()?.parse::<u64>().ok()?;
+    let multiplier = match parts.next() {
+        None | Some("ns") => 1,
+        Some("KiB") => 1 << 10,
+        Some("MiB") => 1 << 20,
+        Some("GiB") => 1 << 30,
+        Some(_) => return None,
+    };
+    Some(num * multiplier)
+}
+
+/// A DRM client of a file descriptor, as listed in its fdinfo.
+#[derive(Default, Debug, PartialEq)]
+struct DrmClient {
+    pdev: Option<String>,
+    client_id: Option<String>,
+    engine_ns: BTreeMap<String, u64>,
+    memory_bytes: BTreeMap<String, u64>,
+}
+
+impl DrmClient {
+    /// None if the fdinfo does not belong to a DRM file.
+    fn parse(fdinfo: &str) -> Option<Self> {
+        let mut client = Self::default();
+        let mut is_drm = false;
+        for line in fdinfo.lines() {
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            if key == "drm-driver" {
+                is_drm = true;
+            } else if key == "drm-pdev" {
+                client.pdev = Some(value.to_owned());
+            } else if key == "drm-client-id" {
+                client.client_id = Some(value.to_owned());
+            } else if let Some(engine) = key.strip_prefix("drm-engine-") {
+                // drm-engine-capacity-<engine> is the number of engines, not
+                // a busy time.
+                if !engine.starts_with("capacity-") {
+                    if let Some(ns) = parse_fdinfo_value(value) {
+                        client.engine_ns.insert(engine.to_owned(), ns);
+                    }
+                }
+            } else if let Some(region) = key.strip_prefix("drm-memory-") {
+                if let Some(bytes) = parse_fdinfo_value(value) {
+                    client.memory_bytes.insert(region.to_owned(), bytes);
+                }
+            }
+        }
+        is_drm.then_some(client)
+    }
+}
+
+pub struct GpuReader {
+    sysfs_root: PathBuf,
+    proc_root: PathBuf,
+}
+
+impl GpuReader {
+    pub fn new(sysfs_root: PathBuf, proc_root: PathBuf) -> GpuReader {
+        GpuReader {
+            sysfs_root,
+            proc_root,
+        }
+    }
+
+    pub fn root() -> GpuReader {
+        Self::new(DEFAULT_SYSFS_ROOT.into(), DEFAULT_PROC_ROOT.into())
+    }
+
+    pub fn read_sample(&self) -> Result<GpuSample> {
+        Ok(GpuSample {
+            gpu_map: GpuMap {
+                devices: self.read_devices()?,
+                processes: self.read_processes()?,
+            },
+        })
+    }
+
+    fn read_devices(&self) -> Result<BTreeMap<String, GpuDeviceStats>> {
+        let drm_dir = self.sysfs_root.join("class/drm");
+        let mut devices = BTreeMap::new();
+        for name in read_dir_names(&drm_dir)? {
+            // Skip connectors like card0-DP-1 and render nodes
+            let is_card = name
+                .strip_prefix("card")
+                .map_or(false, |id| id.parse::<u32>().is_ok());
+            if is_card {
+                devices.insert(name.clone(), self.read_device(&drm_dir.join(name))?);
+            }
+        }
+        Ok(devices)
+    }
+
+    fn read_device(&self, card_dir: &Path) -> Result<GpuDeviceStats> {
+        let device_dir = card_dir.join("device");
+        let uevent = read_file(&device_dir.join("uevent"))?.unwrap_or_default();
+        let uevent_value = |key: &str| {
+            uevent
+                .lines()
+                .find_map(|line| line.strip_prefix(key)?.strip_prefix('=').map(str::to_owned))
+        };
+        let mut stats = GpuDeviceStats {
+            driver: uevent_value("DRIVER"),
+            pci_slot: uevent_value("PCI_SLOT_NAME"),
+            busy_pct: read_value(&device_dir.join("gpu_busy_percent"))?,
+            vram_used_bytes: read_value(&device_dir.join("mem_info_vram_used"))?,
+            vram_total_bytes: read_value(&device_dir.join("mem_info_vram_total"))?,
+            ..Default::default()
+        };
+        let hwmon_dir = device_dir.join("hwmon");
+        if let Some(hwmon) = read_dir_names(&hwmon_dir)?.first() {
+            let hwmon_dir = hwmon_dir.join(hwmon);
+            stats.temp_millicelsius = read_value(&hwmon_dir.join("temp1_input"))?;
+            stats.power_microwatts = match read_value(&hwmon_dir.join("power1_average"))? {
+                Some(power) => Some(power),
+                None => read_value(&hwmon_dir.join("power1_input"))?,
+            };
+        }
+        Ok(stats)
+    }
+
+    fn read_processes(&self) -> Result<BTreeMap<i32, GpuProcessStats>> {
+        let mut processes = BTreeMap::new();
+        for name in read_dir_names(&self.proc_root)? {
+            let pid = match name.parse::<i32>() {
+                Ok(pid) => pid,
+                Err(_) => continue,
+            };
+            // Processes may exit while being read or, without privileges, have
+            // fds that cannot be read. Like procfs, skip them rather than
+            // failing the whole sample.
+            if let Ok(Some(stats)) = self.read_process(&self.proc_root.join(name)) {
+                processes.insert(pid, stats);
+            }
+        }
+        Ok(processes)
+    }
+
+    fn read_process(&self, pid_dir: &Path) -> Result<Option<GpuProcessStats>> {
+        let mut stats = GpuProcessStats::default();
+        // A client shared by several fds, e.g. after dup(), is counted once
+        let mut seen_clients = BTreeSet::new();
+        let fd_dir = pid_dir.join("fd");
+        for fd in read_dir_names(&fd_dir)? {
+            // Only read the fdinfo of DRM device files
+            let is_drm = std::fs::read_link(fd_dir.join(&fd))
+                .map_or(false, |target| target.starts_with("/dev/dri"));
+            if !is_drm {
+                continue;
+            }
+            let client = match read_file(&pid_dir.join("fdinfo").join(&fd))?
+                .as_deref()
+                .and_then(DrmClient::parse)
+            {
+                Some(client) => client,
+                None => continue,
+            };
+            if client.client_id.is_some()
+                && !seen_clients.insert((client.pdev.clone(), client.client_id.clone()))
+            {
+                continue;
+            }
+            for (engine, ns) in client.engine_ns {
+                *stats.engine_ns.entry(engine).or_default() += ns;
+            }
+            for (region, bytes) in client.memory_bytes {
+                *stats.memory_bytes.entry(region).or_default() += bytes;
+            }
+        }
+        if seen_clients.is_empty() && stats.engine_ns.is_empty() && stats.memory_bytes.is_empty() {
+            return Ok(None);
+        }
+        stats.comm = read_file(&pid_dir.join("comm"))?.map(|comm| comm.trim_end().to_owned());
+        Ok(Some(stats))
+    }
+}
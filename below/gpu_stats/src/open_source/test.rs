@@ -0,0 +1,179 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs::create_dir_all;
+use std::os::unix::fs::symlink;
+
+use tempfile::TempDir;
+
+use super::*;
+
+/// Fake sysfs and procfs trees.
+struct TestRoots {
+    tempdir: TempDir,
+}
+
+impl TestRoots {
+    fn new() -> Self {
+        TestRoots {
+            tempdir: TempDir::with_prefix("below_gpu_stats_test.")
+                .expect("Failed to create temp dir"),
+        }
+    }
+
+    fn sysfs(&self) -> PathBuf {
+        self.tempdir.path().join("sys")
+    }
+
+    fn proc(&self) -> PathBuf {
+        self.tempdir.path().join("proc")
+    }
+
+    fn reader(&self) -> GpuReader {
+        GpuReader::new(self.sysfs(), self.proc())
+    }
+
+    fn create_file_with_content<P: AsRef<Path>>(&self, p: P, content: &str) {
+        let path = self.tempdir.path().join(p);
+        create_dir_all(path.parent().unwrap()).expect("Failed to create parent dirs");
+        std::fs::write(&path, content)
+            .unwrap_or_else(|_| panic!("Failed to write {}", path.display()));
+    }
+
+    /// Open fd of pid on target, with fdinfo as its fdinfo.
+    fn create_fd(&self, pid: i32, fd: u32, target: &str, fdinfo: &str) {
+        let fd_dir = self.proc().join(pid.to_string()).join("fd");
+        create_dir_all(&fd_dir).expect("Failed to create fd dir");
+        symlink(target, fd_dir.join(fd.to_string())).expect("Failed to create fd symlink");
+        self.create_file_with_content(format!("proc/{}/fdinfo/{}", pid, fd), fdinfo);
+    }
+}
+
+#[test]
+fn test_read_devices() {
+    let roots = TestRoots::new();
+    roots.create_file_with_content(
+        "sys/class/drm/card0/device/uevent",
+        "DRIVER=amdgpu\nPCI_CLASS=30000\nPCI_SLOT_NAME=0000:03:00.0\n",
+    );
+    roots.create_file_with_content("sys/class/drm/card0/device/gpu_busy_percent", "37\n");
+    roots.create_file_with_content("sys/class/drm/card0/device/mem_info_vram_used", "1024\n");
+    roots.create_file_with_content("sys/class/drm/card0/device/mem_info_vram_total", "4096\n");
+    roots.create_file_with_content(
+        "sys/class/drm/card0/device/hwmon/hwmon3/temp1_input",
+        "52000\n",
+    );
+    roots.create_file_with_content(
+        "sys/class/drm/card0/device/hwmon/hwmon3/power1_average",
+        "25000000\n",
+    );
+    // Card without the amdgpu files
+    roots.create_file_with_content("sys/class/drm/card1/device/uevent", "DRIVER=i915\n");
+    // Connectors and render nodes are not cards
+    roots.create_file_with_content("sys/class/drm/card0-DP-1/status", "connected\n");
+    roots.create_file_with_content("sys/class/drm/renderD128/dev", "226:128\n");
+
+    let devices = roots
+        .reader()
+        .read_sample()
+        .expect("Failed to read")
+        .gpu_map
+        .devices;
+    assert_eq!(devices.keys().collect::<Vec<_>>(), vec!["card0", "card1"]);
+    assert_eq!(
+        devices["card0"],
+        GpuDeviceStats {
+            driver: Some("amdgpu".to_owned()),
+            pci_slot: Some("0000:03:00.0".to_owned()),
+            busy_pct: Some(37),
+            vram_used_bytes: Some(1024),
+            vram_total_bytes: Some(4096),
+            temp_millicelsius: Some(52000),
+            power_microwatts: Some(25000000),
+        }
+    );
+    assert_eq!(
+        devices["card1"],
+        GpuDeviceStats {
+            driver: Some("i915".to_owned()),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_read_processes() {
+    let roots = TestRoots::new();
+    let fdinfo = "pos:\t0\nflags:\t02100002\nmnt_id:\t25\n\
+        drm-driver:\tamdgpu\ndrm-pdev:\t0000:03:00.0\ndrm-client-id:\t7\n\
+        drm-engine-gfx:\t1500 ns\ndrm-engine-capacity-gfx:\t2\n\
+        drm-memory-vram:\t2048 KiB\ndrm-memory-gtt:\t4 KiB\n";
+    roots.create_file_with_content("proc/100/comm", "game\n");
+    roots.create_fd(100, 5, "/dev/dri/renderD128", fdinfo);
+    // The same client through a dup'ed fd is counted once
+    roots.create_fd(100, 6, "/dev/dri/renderD128", fdinfo);
+    roots.create_fd(
+        100,
+        7,
+        "/dev/dri/card0",
+        "drm-driver:\tamdgpu\ndrm-pdev:\t0000:03:00.0\ndrm-client-id:\t8\n\
+        drm-engine-gfx:\t500 ns\ndrm-engine-compute:\t20 ns\n",
+    );
+    // Processes without DRM fds are skipped
+    roots.create_file_with_content("proc/200/comm", "shell\n");
+    roots.create_fd(200, 0, "/dev/null", "pos:\t0\n");
+    roots.create_file_with_content("proc/self/comm", "below\n");
+    // Processes that cannot be read, e.g. as they exit, are skipped
+    roots.create_file_with_content("proc/300/fd", "");
+    roots.create_fd(400, 3, "/dev/dri/renderD128", fdinfo);
+    std::fs::remove_file(roots.proc().join("400/fdinfo/3")).unwrap();
+    create_dir_all(roots.proc().join("400/fdinfo/3")).unwrap();
+    roots.create_file_with_content("sys/class/drm/card0/device/uevent", "DRIVER=amdgpu\n");
+
+    let gpu_map = roots
+        .reader()
+        .read_sample()
+        .expect("Failed to read")
+        .gpu_map;
+    assert_eq!(gpu_map.devices.keys().collect::<Vec<_>>(), vec!["card0"]);
+    let processes = gpu_map.processes;
+    assert_eq!(processes.keys().collect::<Vec<_>>(), vec![&100]);
+    assert_eq!(
+        processes[&100],
+        GpuProcessStats {
+            comm: Some("game".to_owned()),
+            engine_ns: BTreeMap::from([("compute".to_owned(), 20), ("gfx".to_owned(), 2000)]),
+            memory_bytes: BTreeMap::from([
+                ("gtt".to_owned(), 4 << 10),
+                ("vram".to_owned(), 2048 << 10)
+            ]),
+        }
+    );
+}
+
+#[test]
+fn test_missing_roots() {
+    let roots = TestRoots::new();
+    let sample = roots.reader().read_sample().expect("Failed to read");
+    assert_eq!(sample, GpuSample::default());
+}
+
+#[test]
+fn test_parse_fdinfo_value() {
+    assert_eq!(parse_fdinfo_value("123 ns"), Some(123));
+    assert_eq!(parse_fdinfo_value("2 MiB"), Some(2 << 20));
+    assert_eq!(parse_fdinfo_value("42"), Some(42));
+    assert_eq!(parse_fdinfo_value("1 parsec"), None);
+    assert_eq!(parse_fdinfo_value(""), None);
+}
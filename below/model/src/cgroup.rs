@@ -21,6 +21,8 @@ pub struct SingleCgroupModel {
     pub name: String,
     pub full_path: String,
     pub inode_number: Option<u64>,
+    #[queriable(subquery)]
+    pub container: Option<ContainerModel>,
     #[queriable(ignore)]
     pub depth: u32,
     #[queriable(subquery)]
@@ -145,12 +147,16 @@ impl PartialEq for CgroupModel {
 impl Eq for CgroupModel {}
 
 impl CgroupModel {
+    /// io_delta is the duration to compute io rates over, as io.stat may be
+    /// carried forward from an earlier sample. None if they are unknown.
     pub fn new(
         name: String,
         full_path: String,
         depth: u32,
         sample: &CgroupSample,
         last: Option<(&CgroupSample, Duration)>,
+        io_delta: Option<Duration>,
+        container_names: Option<&BTreeMap<String, String>>,
     ) -> CgroupModel {
         let last_if_inode_matches =
             last.and_then(|(s, d)| match (s.inode_number, sample.inode_number) {
@@ -164,18 +170,27 @@ impl CgroupModel {
         let (cpu, io, io_total, recreate_flag) = if let Some((last, delta)) = last_if_inode_matches
         {
             // We have cumulative data, create cpu, io models
-            let cpu = match (last.cpu_stat.as_ref(), sample.cpu_stat.as_ref()) {
+            let mut cpu = match (last.cpu_stat.as_ref(), sample.cpu_stat.as_ref()) {
                 (Some(begin), Some(end)) => Some(CgroupCpuModel::new(begin, end, delta)),
                 _ => None,
             };
-            let io = match (last.io_stat.as_ref(), sample.io_stat.as_ref()) {
-                (Some(begin), Some(end)) => Some(
+            if let (Some(cpu), Some(begin), Some(end)) = (
+                cpu.as_mut(),
+                last.run_delay.as_ref(),
+                sample.run_delay.as_ref(),
+            ) {
+                let run_delay = end.delta(begin);
+                cpu.run_delay_usec_p50 = run_delay.percentile(50.0);
+                cpu.run_delay_usec_p99 = run_delay.percentile(99.0);
+            }
+            let io = match (last.io_stat.as_ref(), sample.io_stat.as_ref(), io_delta) {
+                (Some(begin), Some(end), Some(io_delta)) => Some(
                     end.iter()
                         .filter_map(|(device_name, end_io_stat)| {
                             begin.get(device_name).map(|begin_io_stat| {
                                 (
                                     device_name.clone(),
-                                    CgroupIoModel::new(begin_io_stat, end_io_stat, delta),
+                                    CgroupIoModel::new(begin_io_stat, end_io_stat, io_delta),
                                 )
                             })
                         })
@@ -197,7 +212,7 @@ impl CgroupModel {
 
         let memory = Some(CgroupMemoryModel::new(sample, last));
 
-        let pids = Some(CgroupPidsModel::new(sample));
+        let pids = Some(CgroupPidsModel::new(sample, last_if_inode_matches));
 
         let pressure = sample.pressure.as_ref().map(CgroupPressureModel::new);
 
@@ -242,15 +257,19 @@ impl CgroupModel {
                             .get(child_name)
                             .map(|child_last| (child_last, delta))
                     }),
+                    io_delta,
+                    container_names,
                 )
             })
             .collect::<BTreeSet<CgroupModel>>();
         let nr_descendants: u32 = children.iter().fold(0, |acc, c| acc + c.count);
+        let container = ContainerModel::new(&full_path, container_names);
         CgroupModel {
             data: SingleCgroupModel {
                 name,
                 full_path,
                 inode_number: sample.inode_number.map(|ino| ino as u64),
+                container,
                 properties,
                 cpu,
                 memory,
@@ -302,6 +321,10 @@ pub struct CgroupCpuModel {
     pub nr_periods_per_sec: Option<f64>,
     pub nr_throttled_per_sec: Option<f64>,
     pub throttled_pct: Option<f64>,
+    /// Run queue latency percentiles over the interval, rounded up to a
+    /// power of two
+    pub run_delay_usec_p50: Option<u64>,
+    pub run_delay_usec_p99: Option<u64>,
 }
 
 impl CgroupCpuModel {
@@ -317,6 +340,8 @@ impl CgroupCpuModel {
             nr_periods_per_sec: count_per_sec!(begin.nr_periods, end.nr_periods, delta),
             nr_throttled_per_sec: count_per_sec!(begin.nr_throttled, end.nr_throttled, delta),
             throttled_pct: usec_pct!(begin.throttled_usec, end.throttled_usec, delta),
+            run_delay_usec_p50: None,
+            run_delay_usec_p99: None,
         }
     }
 }
@@ -676,6 +701,7 @@ impl CgroupMemoryModel {
 #[::below_derive::queriable_derives]
 pub struct CgroupPidsModel {
     pub tids_current: Option<u64>,
+    pub exec_per_sec: Option<f64>,
 }
 
 impl std::ops::Add for CgroupPidsModel {
@@ -684,14 +710,20 @@ impl std::ops::Add for CgroupPidsModel {
     fn add(self, other: Self) -> Self::Output {
         Self {
             tids_current: opt_add(self.tids_current, other.tids_current),
+            exec_per_sec: opt_add(self.exec_per_sec, other.exec_per_sec),
         }
     }
 }
 
 impl CgroupPidsModel {
-    pub fn new(sample: &CgroupSample) -> Self {
+    pub fn new(sample: &CgroupSample, last: Option<(&CgroupSample, Duration)>) -> Self {
         let tids_current = sample.tids_current;
-        CgroupPidsModel { tids_current }
+        let exec_per_sec = last
+            .and_then(|(last, delta)| count_per_sec!(last.exec_count, sample.exec_count, delta));
+        CgroupPidsModel {
+            tids_current,
+            exec_per_sec,
+        }
     }
 }
 
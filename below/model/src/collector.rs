@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cell::Cell;
+use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -21,13 +25,19 @@ use regex::Regex;
 use slog::error;
 
 use super::*;
+use crate::collector_health::timed;
 use crate::collector_plugin;
 
 pub struct CollectorOptions {
     pub cgroup_root: PathBuf,
     pub exit_data: Arc<Mutex<procfs::PidMap>>,
+    pub cgroup_id_map: Arc<Mutex<CgroupIdMap>>,
+    /// Exec counts from the exec tracking BPF program, None if disabled
+    pub exec_counter: Option<Arc<Mutex<ExecCounter>>>,
     pub collect_io_stat: bool,
     pub disable_disk_stat: bool,
+    pub disable_slabinfo: bool,
+    pub disable_netstat: bool,
     pub enable_btrfs_stats: bool,
     pub enable_ethtool_stats: bool,
     pub enable_ksm_stats: bool,
@@ -40,6 +50,44 @@ pub struct CollectorOptions {
         Option<collector_plugin::Consumer<crate::gpu_stats_collector_plugin::SampleType>>,
     pub tc_stats_receiver:
         Option<collector_plugin::Consumer<crate::tc_collector_plugin::SampleType>>,
+    pub run_delay_receiver: Option<collector_plugin::Consumer<crate::run_delay::SampleType>>,
+    pub container_name_lookup: Option<Arc<dyn ContainerNameLookup>>,
+    /// Events lost by the exitstat perf buffers, None if exitstats are
+    /// disabled. Reset by every collection.
+    pub exitstat_lost_events: Option<Arc<AtomicU64>>,
+    pub interval_multiples: IntervalMultiples,
+    /// Applied to the cmdlines of the processes of every sample
+    pub redactor: Option<Redactor>,
+}
+
+/// Subsystems that are only collected every Nth sample. The samples in
+/// between carry their last value forward and list them as stale in
+/// `CollectorHealth`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IntervalMultiples {
+    pub io_stat: u64,
+    pub disk_stat: u64,
+    pub slabinfo: u64,
+    pub netstat: u64,
+    pub btrfs: u64,
+    pub ethtool: u64,
+    pub ksm: u64,
+    pub resctrl: u64,
+}
+
+impl Default for IntervalMultiples {
+    fn default() -> Self {
+        Self {
+            io_stat: 1,
+            disk_stat: 1,
+            slabinfo: 1,
+            netstat: 1,
+            btrfs: 1,
+            ethtool: 1,
+            ksm: 1,
+            resctrl: 1,
+        }
+    }
 }
 
 impl Default for CollectorOptions {
@@ -47,8 +95,12 @@ impl Default for CollectorOptions {
         Self {
             cgroup_root: Path::new(cgroupfs::DEFAULT_CG_ROOT).to_path_buf(),
             exit_data: Default::default(),
+            cgroup_id_map: Default::default(),
+            exec_counter: None,
             collect_io_stat: true,
             disable_disk_stat: false,
+            disable_slabinfo: false,
+            disable_netstat: false,
             enable_btrfs_stats: false,
             enable_ethtool_stats: false,
             enable_ksm_stats: false,
@@ -59,6 +111,11 @@ impl Default for CollectorOptions {
             cgroup_re: None,
             gpu_stats_receiver: None,
             tc_stats_receiver: None,
+            run_delay_receiver: None,
+            container_name_lookup: None,
+            exitstat_lost_events: None,
+            interval_multiples: Default::default(),
+            redactor: None,
         }
     }
 }
@@ -69,6 +126,12 @@ pub struct Collector {
     proc_reader: procfs::ProcReader,
     prev_sample: Option<(Sample, Instant)>,
     collector_options: CollectorOptions,
+    /// Number of samples collected so far, to tell which subsystems are due
+    nr_samples: u64,
+    carried_forward: CarriedForward,
+    /// Root of the cgroup v1 controllers, looked up once on hosts without
+    /// cgroup2
+    cgroup_v1_root: Option<cgroupfs::CgroupV1Reader>,
 }
 
 impl Collector {
@@ -78,11 +141,33 @@ impl Collector {
             proc_reader: procfs::ProcReader::new(),
             prev_sample: None,
             collector_options,
+            nr_samples: 0,
+            carried_forward: Default::default(),
+            cgroup_v1_root: None,
         }
     }
 
+    /// Options of the following collections, e.g. to apply a reloaded config
+    pub fn collector_options_mut(&mut self) -> &mut CollectorOptions {
+        &mut self.collector_options
+    }
+
     pub fn collect_sample(&mut self) -> Result<Sample> {
-        collect_sample(&self.logger, &mut self.proc_reader, &self.collector_options)
+        let now = Instant::now();
+        let mut sample = collect_sample(
+            &self.logger,
+            &mut self.proc_reader,
+            &self.collector_options,
+            self.nr_samples,
+            &mut self.cgroup_v1_root,
+        )?;
+        if let Some(redactor) = self.collector_options.redactor.as_ref() {
+            redactor.redact_sample(&mut sample);
+        }
+        self.carried_forward
+            .update(&mut sample, &self.collector_options, self.nr_samples, now);
+        self.nr_samples += 1;
+        Ok(sample)
     }
 
     /// Collect a new `Sample`, returning an updated Model
@@ -101,6 +186,233 @@ impl Collector {
     }
 }
 
+/// Last values of the subsystems with an interval multiple above 1
+#[derive(Default, Debug)]
+struct CarriedForward {
+    /// When each subsystem was last due
+    due_at: HashMap<String, Instant>,
+    /// Keyed by cgroup path, so cgroups created in between have no io.stat
+    /// until it is due again
+    io_stat: HashMap<String, BTreeMap<String, cgroupfs::IoStat>>,
+    disks: Option<procfs::DiskMap>,
+    slabinfo: Option<procfs::SlabInfoMap>,
+    netstats: Option<procfs::NetStat>,
+    btrfs: Option<Option<btrfs::BtrfsMap>>,
+    ethtool: Option<Option<ethtool::EthtoolStats>>,
+    ksm: Option<Option<procfs::Ksm>>,
+    resctrl: Option<Option<resctrlfs::ResctrlSample>>,
+}
+
+impl CarriedForward {
+    /// Remembers the subsystems of sample that were due and fills in the
+    /// others with their last values.
+    fn update(
+        &mut self,
+        sample: &mut Sample,
+        options: &CollectorOptions,
+        nr_samples: u64,
+        now: Instant,
+    ) {
+        let multiples = &options.interval_multiples;
+        let mut carry = Carry {
+            nr_samples,
+            now,
+            due_at: &mut self.due_at,
+            stale: BTreeMap::new(),
+        };
+        if options.collect_io_stat && multiples.io_stat > 1 {
+            if carry.is_due("io_stat", multiples.io_stat) {
+                self.io_stat.clear();
+                walk_cgroups("", &mut sample.cgroup, &mut |path, cgroup| {
+                    if let Some(io_stat) = cgroup.io_stat.as_ref() {
+                        self.io_stat.insert(path.to_owned(), io_stat.clone());
+                    }
+                });
+            } else {
+                walk_cgroups("", &mut sample.cgroup, &mut |path, cgroup| {
+                    cgroup.io_stat = self.io_stat.get(path).cloned();
+                });
+                carry.mark_stale("io_stat");
+            }
+        }
+        let system = &mut sample.system;
+        if !options.disable_disk_stat {
+            carry.apply(
+                "disk_stat",
+                multiples.disk_stat,
+                &mut system.disks,
+                &mut self.disks,
+            );
+        }
+        if !options.disable_slabinfo {
+            carry.apply(
+                "slabinfo",
+                multiples.slabinfo,
+                &mut system.slabinfo,
+                &mut self.slabinfo,
+            );
+        }
+        if !options.disable_netstat {
+            carry.apply(
+                "netstat",
+                multiples.netstat,
+                &mut sample.netstats,
+                &mut self.netstats,
+            );
+        }
+        if options.enable_btrfs_stats {
+            carry.apply("btrfs", multiples.btrfs, &mut system.btrfs, &mut self.btrfs);
+        }
+        if options.enable_ethtool_stats {
+            carry.apply(
+                "ethtool",
+                multiples.ethtool,
+                &mut sample.ethtool,
+                &mut self.ethtool,
+            );
+        }
+        if options.enable_ksm_stats {
+            carry.apply("ksm", multiples.ksm, &mut system.ksm, &mut self.ksm);
+        }
+        if options.enable_resctrl_stats {
+            carry.apply(
+                "resctrl",
+                multiples.resctrl,
+                &mut sample.resctrl,
+                &mut self.resctrl,
+            );
+        }
+        if !carry.stale.is_empty() {
+            if let Some(health) = sample.collector_health.as_mut() {
+                health.stale_age_usec = Some(carry.stale);
+            }
+        }
+    }
+}
+
+struct Carry<'a> {
+    nr_samples: u64,
+    now: Instant,
+    due_at: &'a mut HashMap<String, Instant>,
+    /// Age of the carried values of each stale subsystem in usec
+    stale: BTreeMap<String, u64>,
+}
+
+impl Carry<'_> {
+    /// Whether subsystem name is due, remembering when it was.
+    fn is_due(&mut self, name: &str, multiple: u64) -> bool {
+        let due = self.nr_samples.is_multiple_of(multiple);
+        if due {
+            self.due_at.insert(name.to_owned(), self.now);
+        }
+        due
+    }
+
+    fn mark_stale(&mut self, name: &str) {
+        let age = self.due_at.get(name).map_or(Duration::ZERO, |due_at| {
+            self.now.saturating_duration_since(*due_at)
+        });
+        self.stale.insert(name.to_owned(), age.as_micros() as u64);
+    }
+
+    /// Remembers value if it was due, otherwise replaces it with the last
+    /// value and marks it stale.
+    fn apply<T: Clone>(&mut self, name: &str, multiple: u64, value: &mut T, last: &mut Option<T>) {
+        if multiple <= 1 {
+            return;
+        }
+        if self.is_due(name, multiple) {
+            *last = Some(value.clone());
+        } else if let Some(last) = last {
+            *value = last.clone();
+            self.mark_stale(name);
+        }
+    }
+}
+
+/// Calls f with every cgroup in the tree and its path.
+fn walk_cgroups(path: &str, cgroup: &mut CgroupSample, f: &mut dyn FnMut(&str, &mut CgroupSample)) {
+    let path_or_root = if path.is_empty() { "/" } else { path };
+    f(path_or_root, cgroup);
+    for (child_name, child) in cgroup.children.iter_mut().flatten() {
+        walk_cgroups(&format!("{}/{}", path, child_name), child, f);
+    }
+}
+
+/// Maps cgroup ids, i.e. the inode numbers of cgroup2 directories as returned
+/// by bpf_get_current_cgroup_id(), to cgroup paths in the format of
+/// /proc/<pid>/cgroup. Rebuilt by every cgroup walk of the collector, so
+/// cgroups created since the last walk cannot be resolved.
+#[derive(Default, Debug)]
+pub struct CgroupIdMap {
+    paths: HashMap<u64, String>,
+}
+
+impl CgroupIdMap {
+    pub fn update(&mut self, cgroup: &CgroupSample) {
+        fn walk(path: &str, cgroup: &CgroupSample, paths: &mut HashMap<u64, String>) {
+            if let Some(inode_number) = cgroup.inode_number {
+                let path = if path.is_empty() { "/" } else { path };
+                paths.insert(inode_number as u64, path.to_owned());
+            }
+            for (child_name, child) in cgroup.children.iter().flatten() {
+                walk(&format!("{}/{}", path, child_name), child, paths);
+            }
+        }
+        self.paths.clear();
+        walk("", cgroup, &mut self.paths);
+    }
+
+    pub fn resolve(&self, cgroup_id: u64) -> Option<&str> {
+        self.paths.get(&cgroup_id).map(String::as_str)
+    }
+}
+
+/// Cumulative number of execs per cgroup path as resolved by `CgroupIdMap`.
+/// An exec is counted in its cgroup and all ancestors, so that the count of a
+/// cgroup does not drop when one of its descendants is removed.
+#[derive(Default, Debug)]
+pub struct ExecCounter {
+    counts: HashMap<String, u64>,
+}
+
+impl ExecCounter {
+    /// Counts an exec in the cgroup at path, e.g. /system.slice/foo.service
+    pub fn record(&mut self, path: &str) {
+        let mut path = path;
+        loop {
+            *self.counts.entry(path.to_owned()).or_default() += 1;
+            path = match path.rfind('/') {
+                Some(0) if path.len() > 1 => "/",
+                Some(idx) if idx > 0 => &path[..idx],
+                _ => break,
+            };
+        }
+    }
+
+    /// Sets exec_count of every cgroup in the tree and forgets the counts of
+    /// cgroups that no longer exist.
+    pub fn fill(&mut self, cgroup: &mut CgroupSample) {
+        fn walk(
+            path: &str,
+            cgroup: &mut CgroupSample,
+            counts: &HashMap<String, u64>,
+            seen: &mut HashMap<String, u64>,
+        ) {
+            let path_or_root = if path.is_empty() { "/" } else { path };
+            let count = counts.get(path_or_root).copied().unwrap_or_default();
+            seen.insert(path_or_root.to_owned(), count);
+            cgroup.exec_count = Some(count);
+            for (child_name, child) in cgroup.children.iter_mut().flatten() {
+                walk(&format!("{}/{}", path, child_name), child, counts, seen);
+            }
+        }
+        let mut seen = HashMap::new();
+        walk("", cgroup, &self.counts, &mut seen);
+        self.counts = seen;
+    }
+}
+
 pub fn opt_add<T: std::ops::Add<T, Output = T>>(a: Option<T>, b: Option<T>) -> Option<T> {
     match (a, b) {
         (Some(a), Some(b)) => Some(a + b),
@@ -184,7 +496,22 @@ fn collect_sample(
     logger: &slog::Logger,
     reader: &mut procfs::ProcReader,
     options: &CollectorOptions,
+    nr_samples: u64,
+    cgroup_v1_root: &mut Option<cgroupfs::CgroupV1Reader>,
 ) -> Result<Sample> {
+    let collect_instant = Instant::now();
+    // Whether a subsystem with the given interval multiple is collected
+    let due = |multiple: u64| nr_samples.is_multiple_of(multiple);
+    let multiples = &options.interval_multiples;
+    let collect_io_stat = options.collect_io_stat && due(multiples.io_stat);
+    let mut health = CollectorHealth::default();
+    // Errors that are logged rather than failing the collection
+    let errors = Cell::new(0);
+    let log_error = |e: &dyn std::fmt::Display| {
+        error!(logger, "{:#}", e);
+        errors.set(errors.get() + 1);
+    };
+
     let btrfs_reader =
         btrfs::BtrfsReader::new(options.btrfs_samples, options.btrfs_min_pct, logger.clone());
     let ethtool_reader = ethtool::EthtoolReader::new();
@@ -200,27 +527,81 @@ fn collect_sample(
             .expect("tried to acquire poisoned lock"),
     );
 
-    Ok(Sample {
-        cgroup: collect_cgroup_sample(
-            &cgroupfs::CgroupReader::new(options.cgroup_root.to_owned())?,
-            options.collect_io_stat,
-            logger,
-            &options.cgroup_re,
-        )?,
-        processes: merge_procfs_and_exit_data(reader.read_all_pids()?, exit_pidmap),
-        netstats: match procfs::NetReader::new(logger.clone()).and_then(|v| v.read_netstat()) {
-            Ok(ns) => ns,
-            Err(e) => {
-                error!(logger, "{:#}", e);
-                Default::default()
+    let cgroup_instant = Instant::now();
+    let cgroup = match cgroupfs::CgroupReader::new(options.cgroup_root.to_owned()) {
+        Ok(reader) => {
+            let mut cgroup =
+                collect_cgroup_sample(&reader, collect_io_stat, logger, &options.cgroup_re)?;
+            // Only cgroup2 inode numbers match the cgroup ids seen by BPF
+            options
+                .cgroup_id_map
+                .lock()
+                .expect("tried to acquire poisoned lock")
+                .update(&cgroup);
+            if let Some(exec_counter) = options.exec_counter.as_ref() {
+                exec_counter
+                    .lock()
+                    .expect("tried to acquire poisoned lock")
+                    .fill(&mut cgroup);
             }
+            if let Some(run_delay_receiver) = &options.run_delay_receiver {
+                // Run delay stats are best effort, e.g. BPF may be denied
+                match run_delay_receiver.try_take() {
+                    Ok(Some(hists)) => {
+                        crate::run_delay::fill_cgroup_run_delay(&mut cgroup, &hists);
+                    }
+                    Ok(None) => {}
+                    Err(e) => log_error(&e.context("Run delay collector had an error")),
+                }
+            }
+            cgroup
+        }
+        // Legacy or hybrid hierarchy, fall back to the v1 controllers
+        Err(cgroupfs::Error::NotCgroup2(_)) => {
+            let root = match cgroup_v1_root {
+                Some(root) => root,
+                None => cgroup_v1_root.insert(cgroupfs::CgroupV1Reader::new(
+                    options.cgroup_root.to_owned(),
+                )?),
+            };
+            collect_cgroup_v1_sample(root, collect_io_stat, logger, &options.cgroup_re)?
+        }
+        Err(e) => return Err(e.into()),
+    };
+    health.cgroup_latency_usec = Some(cgroup_instant.elapsed().as_micros() as u64);
+
+    let mut sample = Sample {
+        container_names: options
+            .container_name_lookup
+            .as_ref()
+            .map(|lookup| lookup_container_names(&cgroup, lookup.as_ref())),
+        cgroup,
+        processes: timed(&mut health.procfs_latency_usec, || reader.read_all_pids())
+            .map(|pids| merge_procfs_and_exit_data(pids, exit_pidmap))?,
+        netstats: if options.disable_netstat || !due(multiples.netstat) {
+            Default::default()
+        } else {
+            timed(
+                &mut health.netstat_latency_usec,
+                || match procfs::NetReader::new(logger.clone()).and_then(|v| v.read_netstat()) {
+                    Ok(ns) => ns,
+                    Err(e) => {
+                        log_error(&e);
+                        Default::default()
+                    }
+                },
+            )
         },
         system: SystemSample {
             stat: reader.read_stat()?,
             meminfo: reader.read_meminfo()?,
             vmstat: reader.read_vmstat()?,
-            slabinfo: reader.read_slabinfo().unwrap_or_default(),
-            ksm: if !options.enable_ksm_stats {
+            slabinfo: if options.disable_slabinfo || !due(multiples.slabinfo) {
+                Default::default()
+            } else {
+                reader.read_slabinfo().unwrap_or_default()
+            },
+            ksm: if !options.enable_ksm_stats || !due(multiples.ksm) {
                 None
             } else {
                 Some(ksm_reader.read_ksm())
@@ -229,18 +610,18 @@ fn collect_sample(
             kernel_version: match reader.read_kernel_version() {
                 Ok(k) => Some(k),
                 Err(e) => {
-                    error!(logger, "{:#}", e);
+                    log_error(&e);
                     None
                 }
             },
             os_release: match get_os_release() {
                 Ok(o) => Some(o),
                 Err(e) => {
-                    error!(logger, "{:#}", e);
+                    log_error(&e);
                     None
                 }
             },
-            disks: if options.disable_disk_stat {
+            disks: if options.disable_disk_stat || !due(multiples.disk_stat) {
                 Default::default()
             } else {
                 match reader.read_disk_stats_and_fsinfo() {
@@ -254,21 +635,23 @@ fn collect_sample(
                         })
                         .collect(),
                     Err(e) => {
-                        error!(logger, "{:#}", e);
+                        log_error(&e);
                         Default::default()
                     }
                 }
             },
-            btrfs: if !options.enable_btrfs_stats {
+            btrfs: if !options.enable_btrfs_stats || !due(multiples.btrfs) {
                 Default::default()
             } else {
-                match btrfs_reader.sample() {
-                    Ok(btrfs) => Some(btrfs),
-                    Err(e) => {
-                        error!(logger, "{:#}", e);
-                        Default::default()
+                timed(&mut health.btrfs_latency_usec, || {
+                    match btrfs_reader.sample() {
+                        Ok(btrfs) => Some(btrfs),
+                        Err(e) => {
+                            log_error(&e);
+                            Default::default()
+                        }
                     }
-                }
+                })
             },
         },
         gpus: {
@@ -281,34 +664,37 @@ fn collect_sample(
                 // sample and read that, but then we have to decide how
                 // stale the data can be.
                 Some(
-                    gpu_stats_receiver
-                        .try_take()
-                        .context("GPU stats collector had an error")?
-                        .unwrap_or_default(),
+                    timed(&mut health.gpu_latency_usec, || {
+                        gpu_stats_receiver.try_take()
+                    })
+                    .context("GPU stats collector had an error")?
+                    .unwrap_or_default(),
                 )
             } else {
                 None
             }
         },
-        ethtool: if !options.enable_ethtool_stats {
+        ethtool: if !options.enable_ethtool_stats || !due(multiples.ethtool) {
             Default::default()
         } else {
-            match ethtool_reader.read_stats::<ethtool::Ethtool>() {
+            timed(&mut health.ethtool_latency_usec, || match ethtool_reader
+                .read_stats::<ethtool::Ethtool>()
+            {
                 Ok(ethtool_stats) => Some(ethtool_stats),
                 Err(e) => {
-                    error!(logger, "{:#}", e);
+                    log_error(&e);
                     Default::default()
                 }
-            }
+            })
         },
-        resctrl: if !options.enable_resctrl_stats {
+        resctrl: if !options.enable_resctrl_stats || !due(multiples.resctrl) {
             None
         } else {
             match resctrlfs::ResctrlReader::root() {
                 Ok(resctrl_reader) => match resctrl_reader.read_all() {
                     Ok(resctrl) => Some(resctrl),
                     Err(e) => {
-                        error!(logger, "{:#}", e);
+                        log_error(&e);
                         None
                     }
                 },
@@ -321,15 +707,24 @@ fn collect_sample(
         },
         tc: if let Some(tc_stats_receiver) = &options.tc_stats_receiver {
             Some(
-                tc_stats_receiver
-                    .try_take()
+                timed(&mut health.tc_latency_usec, || tc_stats_receiver.try_take())
                     .context("TC stats collector had an error")?
                     .unwrap_or_default(),
             )
         } else {
             None
         },
-    })
+        collector_health: None,
+    };
+
+    health.errors = Some(errors.get());
+    health.exitstat_lost_events = options
+        .exitstat_lost_events
+        .as_ref()
+        .map(|lost_events| lost_events.swap(0, Ordering::Relaxed));
+    health.collect_latency_usec = Some(collect_instant.elapsed().as_micros() as u64);
+    sample.collector_health = Some(health);
+    Ok(sample)
 }
 
 /// cgroupfs can give us a NotFound error if the cgroup doesn't have
@@ -466,6 +861,68 @@ fn collect_cgroup_sample(
         cpu_max: wrap(reader.read_cpu_max())?,
         cgroup_controllers: wrap(reader.read_cgroup_controllers())?,
         cgroup_subtree_control: wrap(reader.read_cgroup_subtree_control())?,
+        exec_count: None, // Filled in by ExecCounter
+        run_delay: None,  // Filled in by fill_cgroup_run_delay
+    })
+}
+
+/// Same as collect_cgroup_sample but for cgroup v1. Fields without a v1
+/// equivalent are left as None.
+fn collect_cgroup_v1_sample(
+    reader: &cgroupfs::CgroupV1Reader,
+    collect_io_stat: bool,
+    logger: &slog::Logger,
+    cgroup_re: &Option<Regex>,
+) -> Result<CgroupSample> {
+    let io_stat = if collect_io_stat {
+        io_stat_wrap(reader.read_io_stat())?
+    } else {
+        None
+    };
+    Ok(CgroupSample {
+        cpu_stat: wrap(reader.read_cpu_stat())?,
+        io_stat,
+        tids_current: wrap(reader.read_pids_current())?,
+        tids_max: wrap(reader.read_pids_max())?,
+        memory_current: wrap(reader.read_memory_current().map(|v| v as i64))?,
+        memory_stat: wrap(reader.read_memory_stat())?,
+        children: wrap(reader.child_cgroup_iter())
+            .context("Failed to get iterator over cgroup children")?
+            .map(|child_iter| {
+                child_iter
+                    .filter(|child| {
+                        if let Some(cgroup_re) = cgroup_re.as_ref() {
+                            !cgroup_re.is_match(&child.name().to_string_lossy())
+                        } else {
+                            true
+                        }
+                    })
+                    .map(|child| {
+                        collect_cgroup_v1_sample(&child, collect_io_stat, logger, cgroup_re).map(
+                            |child_sample| {
+                                (
+                                    child
+                                        .name()
+                                        .file_name()
+                                        .expect("Unexpected .. in cgroup path")
+                                        .to_string_lossy()
+                                        .to_string(),
+                                    child_sample,
+                                )
+                            },
+                        )
+                    })
+                    .collect::<Result<BTreeMap<String, CgroupSample>>>()
+            })
+            .transpose()?,
+        inode_number: match reader.read_inode_number() {
+            Ok(st_ino) => Some(st_ino as i64),
+            Err(e) => {
+                error!(logger, "Fail to collect inode number: {:#}", e);
+                None
+            }
+        },
+        ..Default::default()
     })
 }
 
@@ -520,3 +977,150 @@ macro_rules! get_option_rate {
             .map(|s| s as u64)
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_carry_forward() {
+        let options = CollectorOptions {
+            enable_ksm_stats: true,
+            interval_multiples: IntervalMultiples {
+                io_stat: 2,
+                ksm: 3,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let io_stat: BTreeMap<String, cgroupfs::IoStat> =
+            [("sda".to_owned(), Default::default())].into();
+        let collected = |nr_samples: u64| {
+            let ksm = procfs::Ksm {
+                full_scans: Some(nr_samples),
+                ..Default::default()
+            };
+            let due = |multiple: u64| nr_samples.is_multiple_of(multiple);
+            Sample {
+                cgroup: CgroupSample {
+                    io_stat: due(2).then(|| io_stat.clone()),
+                    children: Some(
+                        [(
+                            "foo.slice".to_owned(),
+                            CgroupSample {
+                                io_stat: due(2).then(|| io_stat.clone()),
+                                ..Default::default()
+                            },
+                        )]
+                        .into(),
+                    ),
+                    ..Default::default()
+                },
+                system: SystemSample {
+                    ksm: due(3).then_some(ksm),
+                    ..Default::default()
+                },
+                collector_health: Some(Default::default()),
+                ..Default::default()
+            }
+        };
+
+        let mut carried_forward = CarriedForward::default();
+        let mut samples = Vec::new();
+        let start = Instant::now();
+        for nr_samples in 0..4 {
+            let mut sample = collected(nr_samples);
+            let now = start + Duration::from_secs(nr_samples);
+            carried_forward.update(&mut sample, &options, nr_samples, now);
+            samples.push(sample);
+        }
+
+        for sample in &samples {
+            assert_eq!(sample.cgroup.io_stat, Some(io_stat.clone()));
+            let child = &sample.cgroup.children.as_ref().unwrap()["foo.slice"];
+            assert_eq!(child.io_stat, Some(io_stat.clone()));
+        }
+        let full_scans = samples
+            .iter()
+            .map(|s| s.system.ksm.as_ref().and_then(|ksm| ksm.full_scans))
+            .collect::<Vec<_>>();
+        assert_eq!(full_scans, vec![Some(0), Some(0), Some(0), Some(3)]);
+        let stale = samples
+            .iter()
+            .map(|s| {
+                s.collector_health
+                    .as_ref()
+                    .and_then(|health| health.stale_age_usec.as_ref())
+                    .map(|stale| stale.keys().cloned().collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            stale,
+            vec![
+                None,
+                Some(vec!["io_stat".to_owned(), "ksm".to_owned()]),
+                Some(vec!["ksm".to_owned()]),
+                Some(vec!["io_stat".to_owned()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_carry_forward_rates() {
+        let options = CollectorOptions {
+            interval_multiples: IntervalMultiples {
+                disk_stat: 3,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        // 100 sectors read per second, collected every third second
+        let collected = |nr_samples: u64| Sample {
+            system: SystemSample {
+                disks: if nr_samples.is_multiple_of(3) {
+                    [(
+                        "sda".to_owned(),
+                        procfs::DiskStat {
+                            read_sectors: Some(nr_samples * 100),
+                            ..Default::default()
+                        },
+                    )]
+                    .into()
+                } else {
+                    Default::default()
+                },
+                ..Default::default()
+            },
+            collector_health: Some(Default::default()),
+            ..Default::default()
+        };
+
+        let mut carried_forward = CarriedForward::default();
+        let mut samples = Vec::new();
+        let start = Instant::now();
+        for nr_samples in 0..5 {
+            let mut sample = collected(nr_samples);
+            let now = start + Duration::from_secs(nr_samples);
+            carried_forward.update(&mut sample, &options, nr_samples, now);
+            samples.push(sample);
+        }
+
+        let read_bytes_per_sec = samples
+            .windows(2)
+            .map(|pair| {
+                let model = Model::new(
+                    SystemTime::now(),
+                    &pair[1],
+                    Some((&pair[0], Duration::from_secs(1))),
+                );
+                model.system.disks["sda"].read_bytes_per_sec
+            })
+            .collect::<Vec<_>>();
+        // No rate while carried, then the rate over the three seconds since
+        // the disk stats were last collected
+        assert_eq!(
+            read_bytes_per_sec,
+            vec![None, None, Some(100.0 * 512.0), None]
+        );
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cost of collecting each sample, recorded with the sample so that one can
+//! tell whether below itself is what slows down the host.
+
+use super::*;
+
+/// Latencies of the subsystems of a collection in usec. Subsystems that are
+/// disabled are None. gpu and tc stats are collected by their own threads,
+/// so their latency is the time it took to take the latest stats from them.
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CollectorHealth {
+    pub collect_latency_usec: Option<u64>,
+    pub cgroup_latency_usec: Option<u64>,
+    pub procfs_latency_usec: Option<u64>,
+    pub netstat_latency_usec: Option<u64>,
+    pub ethtool_latency_usec: Option<u64>,
+    pub btrfs_latency_usec: Option<u64>,
+    pub tc_latency_usec: Option<u64>,
+    pub gpu_latency_usec: Option<u64>,
+    /// Errors that were logged but did not fail the collection
+    pub errors: Option<u64>,
+    /// Events lost by the exitstat perf buffers since the previous sample,
+    /// None if exitstats are disabled
+    pub exitstat_lost_events: Option<u64>,
+    /// Latency of writing the previous sample to the store, which is only
+    /// known once it is written
+    pub store_write_latency_usec: Option<u64>,
+    /// Subsystems that were not due in this sample and carry the value of
+    /// an earlier one, with the time since it was collected in usec so that
+    /// rates are computed over the whole time the counters changed. None if
+    /// there are none
+    pub stale_age_usec: Option<BTreeMap<String, u64>>,
+}
+
+/// Times f, storing its latency in latency_usec.
+pub(crate) fn timed<T>(latency_usec: &mut Option<u64>, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    *latency_usec = Some(start.elapsed().as_micros() as u64);
+    result
+}
+
+#[::below_derive::queriable_derives]
+pub struct CollectorHealthModel {
+    pub collect_latency_usec: Option<u64>,
+    pub cgroup_latency_usec: Option<u64>,
+    pub procfs_latency_usec: Option<u64>,
+    pub netstat_latency_usec: Option<u64>,
+    pub ethtool_latency_usec: Option<u64>,
+    pub btrfs_latency_usec: Option<u64>,
+    pub tc_latency_usec: Option<u64>,
+    pub gpu_latency_usec: Option<u64>,
+    pub errors: Option<u64>,
+    pub exitstat_lost_events: Option<u64>,
+    pub store_write_latency_usec: Option<u64>,
+    pub stale_collectors: Option<BTreeSet<String>>,
+}
+
+impl Nameable for CollectorHealthModel {
+    fn name() -> &'static str {
+        "collector"
+    }
+}
+
+impl CollectorHealthModel {
+    pub fn new(sample: &CollectorHealth) -> Self {
+        Self {
+            collect_latency_usec: sample.collect_latency_usec,
+            cgroup_latency_usec: sample.cgroup_latency_usec,
+            procfs_latency_usec: sample.procfs_latency_usec,
+            netstat_latency_usec: sample.netstat_latency_usec,
+            ethtool_latency_usec: sample.ethtool_latency_usec,
+            btrfs_latency_usec: sample.btrfs_latency_usec,
+            tc_latency_usec: sample.tc_latency_usec,
+            gpu_latency_usec: sample.gpu_latency_usec,
+            errors: sample.errors,
+            exitstat_lost_events: sample.exitstat_lost_events,
+            store_write_latency_usec: sample.store_write_latency_usec,
+            stale_collectors: sample
+                .stale_age_usec
+                .as_ref()
+                .map(|stale| stale.keys().cloned().collect()),
+        }
+    }
+}
@@ -23,7 +23,7 @@
 ///
 /// This list also servers as documentation for available field ids that could
 /// be used in other below crates. A test ensures that this list is up-to-date.
-pub const COMMON_MODEL_FIELD_IDS: [&str; 478] = [
+pub const COMMON_MODEL_FIELD_IDS: [&str; 507] = [
     "system.hostname",
     "system.kernel_version",
     "system.os_release",
@@ -165,6 +165,11 @@ pub const COMMON_MODEL_FIELD_IDS: [&str; 478] = [
     "cgroup.[path:/<cgroup_path>/.]name",
     "cgroup.[path:/<cgroup_path>/.]full_path",
     "cgroup.[path:/<cgroup_path>/.]inode_number",
+    "cgroup.[path:/<cgroup_path>/.]container.runtime",
+    "cgroup.[path:/<cgroup_path>/.]container.id",
+    "cgroup.[path:/<cgroup_path>/.]container.pod_uid",
+    "cgroup.[path:/<cgroup_path>/.]container.unit",
+    "cgroup.[path:/<cgroup_path>/.]container.name",
     "cgroup.[path:/<cgroup_path>/.]props.cgroup_controllers",
     "cgroup.[path:/<cgroup_path>/.]props.cgroup_subtree_control",
     "cgroup.[path:/<cgroup_path>/.]props.cpu_max_period_usec",
@@ -187,7 +192,10 @@ pub const COMMON_MODEL_FIELD_IDS: [&str; 478] = [
     "cgroup.[path:/<cgroup_path>/.]cpu.nr_periods_per_sec",
     "cgroup.[path:/<cgroup_path>/.]cpu.nr_throttled_per_sec",
     "cgroup.[path:/<cgroup_path>/.]cpu.throttled_pct",
+    "cgroup.[path:/<cgroup_path>/.]cpu.run_delay_usec_p50",
+    "cgroup.[path:/<cgroup_path>/.]cpu.run_delay_usec_p99",
     "cgroup.[path:/<cgroup_path>/.]pids.tids_current",
+    "cgroup.[path:/<cgroup_path>/.]pids.exec_per_sec",
     "cgroup.[path:/<cgroup_path>/.]mem.total",
     "cgroup.[path:/<cgroup_path>/.]mem.swap",
     "cgroup.[path:/<cgroup_path>/.]mem.anon",
@@ -336,6 +344,11 @@ pub const COMMON_MODEL_FIELD_IDS: [&str; 478] = [
     "process.processes.<key>.state",
     "process.processes.<key>.uptime_secs",
     "process.processes.<key>.cgroup",
+    "process.processes.<key>.container.runtime",
+    "process.processes.<key>.container.id",
+    "process.processes.<key>.container.pod_uid",
+    "process.processes.<key>.container.unit",
+    "process.processes.<key>.container.name",
     "process.processes.<key>.io.rbytes_per_sec",
     "process.processes.<key>.io.wbytes_per_sec",
     "process.processes.<key>.io.rwbytes_per_sec",
@@ -357,6 +370,10 @@ pub const COMMON_MODEL_FIELD_IDS: [&str; 478] = [
     "process.processes.<key>.cpu.num_threads",
     "process.processes.<key>.cmdline",
     "process.processes.<key>.exe_path",
+    "process.processes.<key>.subtree.num_procs",
+    "process.processes.<key>.subtree.cpu_usage_pct",
+    "process.processes.<key>.subtree.rss_bytes",
+    "process.processes.<key>.subtree.rwbytes_per_sec",
     "network.interfaces.<key>.interface",
     "network.interfaces.<key>.rx_bytes_per_sec",
     "network.interfaces.<key>.tx_bytes_per_sec",
@@ -502,4 +519,16 @@ pub const COMMON_MODEL_FIELD_IDS: [&str; 478] = [
     "tc.tc.<idx>.xstats.fq_codel.new_flow_count_per_sec",
     "tc.tc.<idx>.xstats.fq_codel.new_flows_len",
     "tc.tc.<idx>.xstats.fq_codel.old_flows_len",
+    "collector.collect_latency_usec",
+    "collector.cgroup_latency_usec",
+    "collector.procfs_latency_usec",
+    "collector.netstat_latency_usec",
+    "collector.ethtool_latency_usec",
+    "collector.btrfs_latency_usec",
+    "collector.tc_latency_usec",
+    "collector.gpu_latency_usec",
+    "collector.errors",
+    "collector.exitstat_lost_events",
+    "collector.store_write_latency_usec",
+    "collector.stale_collectors",
 ];
@@ -0,0 +1,322 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Container labels derived from cgroup paths.
+//!
+//! Container runtimes and systemd name cgroups after what runs in them, e.g.
+//! `/system.slice/docker-<id>.scope` or
+//! `/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod<uid>.slice/cri-containerd-<id>.scope`.
+//! The path alone gives the container id, pod uid and systemd unit. Friendly
+//! names come from a `ContainerNameLookup` at collection time and are stored
+//! in the sample, so that replay shows the names known while recording.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::bail;
+
+use super::*;
+
+/// Length of the short container id shown when no friendly name is known.
+const SHORT_ID_LEN: usize = 12;
+
+#[::below_derive::queriable_derives]
+pub struct ContainerModel {
+    /// docker, containerd, podman or crio. None if the id is not prefixed by
+    /// the runtime, e.g. with the cgroupfs driver of kubelet.
+    pub runtime: Option<String>,
+    pub id: Option<String>,
+    pub pod_uid: Option<String>,
+    /// Innermost systemd service or scope
+    pub unit: Option<String>,
+    /// Friendly name of the container or pod if known, otherwise the short
+    /// container id
+    pub name: Option<String>,
+}
+
+fn is_container_id(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn is_uid(s: &str) -> bool {
+    s.len() == 36 && s.bytes().all(|b| b.is_ascii_hexdigit() || b == b'-')
+}
+
+/// Runtime and container id of a single cgroup name, e.g. docker-<id>.scope
+fn parse_container(name: &str) -> Option<(Option<&'static str>, &str)> {
+    let name = name.strip_suffix(".scope").unwrap_or(name);
+    // conmon is the monitor process of podman and CRI-O, not the container
+    if name.starts_with("libpod-conmon-") || name.starts_with("crio-conmon-") {
+        return None;
+    }
+    let (runtime, id) = [
+        ("docker-", "docker"),
+        ("cri-containerd-", "containerd"),
+        ("libpod-", "podman"),
+        ("crio-", "crio"),
+    ]
+    .iter()
+    .find_map(|(prefix, runtime)| Some((Some(*runtime), name.strip_prefix(prefix)?)))
+    .unwrap_or((None, name));
+    is_container_id(id).then_some((runtime, id))
+}
+
+/// Pod uid of a single cgroup name, e.g. pod<uid> with the cgroupfs driver
+/// or kubepods-burstable-pod<uid>.slice with the systemd driver, where the
+/// dashes of the uid are replaced by underscores.
+fn parse_pod(name: &str) -> Option<String> {
+    let uid = match name.strip_suffix(".slice") {
+        Some(slice) => slice.rsplit_once("-pod")?.1.replace('_', "-"),
+        None => name.strip_prefix("pod")?.to_owned(),
+    };
+    is_uid(&uid).then_some(uid)
+}
+
+impl ContainerModel {
+    /// Labels of the cgroup at path, or None if it is neither in a container,
+    /// a pod nor a systemd unit. The innermost match of each label wins.
+    pub fn from_cgroup_path(path: &str) -> Option<ContainerModel> {
+        let mut model = ContainerModel::default();
+        let mut parent = "";
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            if let Some((runtime, id)) = parse_container(name) {
+                model.runtime = runtime
+                    .or_else(|| (parent == "docker").then_some("docker"))
+                    .map(str::to_owned);
+                model.id = Some(id.to_owned());
+            } else if let Some(uid) = parse_pod(name) {
+                model.pod_uid = Some(uid);
+            }
+            if name.ends_with(".service") || name.ends_with(".scope") {
+                model.unit = Some(name.to_owned());
+            }
+            parent = name;
+        }
+        (model != ContainerModel::default()).then_some(model)
+    }
+
+    /// Key of the container or pod used to look up its friendly name.
+    pub fn key(&self) -> Option<&str> {
+        self.id.as_deref().or(self.pod_uid.as_deref())
+    }
+
+    /// Same as from_cgroup_path, with the name taken from names, which maps
+    /// container ids and pod uids to friendly names.
+    pub fn new(path: &str, names: Option<&BTreeMap<String, String>>) -> Option<ContainerModel> {
+        let mut model = ContainerModel::from_cgroup_path(path)?;
+        model.name = model
+            .key()
+            .and_then(|key| names?.get(key).cloned())
+            .or_else(|| model.id.as_ref().map(|id| id[..SHORT_ID_LEN].to_owned()));
+        Some(model)
+    }
+}
+
+/// Source of friendly names for containers and pods, e.g. a mapping file or
+/// the metadata API of a container runtime.
+pub trait ContainerNameLookup: Send + Sync {
+    /// Friendly name of the container or pod identified by key, a container
+    /// id or a pod uid.
+    fn lookup(&self, key: &str) -> Option<String>;
+}
+
+/// Names from a file with one `<container id or pod uid> <name>` entry per
+/// line. Container ids may be shortened to at least 12 characters. Empty
+/// lines and lines starting with # are ignored.
+#[derive(Debug, Default)]
+pub struct MappingFile {
+    names: BTreeMap<String, String>,
+}
+
+impl MappingFile {
+    pub fn load(path: &Path) -> Result<MappingFile> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read container names from {}", path.display()))?;
+        content
+            .parse()
+            .with_context(|| format!("Failed to parse container names from {}", path.display()))
+    }
+}
+
+impl FromStr for MappingFile {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut names = BTreeMap::new();
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once(char::is_whitespace) {
+                Some((key, name)) if key.len() >= SHORT_ID_LEN => {
+                    names.insert(key.to_owned(), name.trim().to_owned());
+                }
+                _ => bail!("Invalid entry at line {}: {}", idx + 1, line),
+            }
+        }
+        Ok(MappingFile { names })
+    }
+}
+
+impl ContainerNameLookup for MappingFile {
+    fn lookup(&self, key: &str) -> Option<String> {
+        self.names
+            .get(key)
+            .or_else(|| {
+                self.names
+                    .iter()
+                    .find_map(|(prefix, name)| key.starts_with(prefix.as_str()).then_some(name))
+            })
+            .cloned()
+    }
+}
+
+/// Friendly names of all containers and pods in the cgroup tree, keyed by
+/// container id or pod uid.
+pub fn lookup_container_names(
+    cgroup: &CgroupSample,
+    lookup: &dyn ContainerNameLookup,
+) -> BTreeMap<String, String> {
+    fn walk(
+        path: &str,
+        cgroup: &CgroupSample,
+        lookup: &dyn ContainerNameLookup,
+        names: &mut BTreeMap<String, String>,
+    ) {
+        let name = path.rsplit('/').next().unwrap_or("");
+        if let Some(key) = parse_container(name)
+            .map(|(_, id)| id.to_owned())
+            .or_else(|| parse_pod(name))
+        {
+            if let Some(name) = lookup.lookup(&key) {
+                names.insert(key, name);
+            }
+        }
+        for (child_name, child) in cgroup.children.iter().flatten() {
+            walk(&format!("{}/{}", path, child_name), child, lookup, names);
+        }
+    }
+    let mut names = BTreeMap::new();
+    walk("", cgroup, lookup, &mut names);
+    names
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ID: &str = "4f1e3bd9c0a2b6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f";
+    const UID: &str = "0a1b2c3d-4e5f-6789-abcd-ef0123456789";
+
+    #[test]
+    fn test_from_cgroup_path() {
+        let labels = |path: &str| {
+            ContainerModel::from_cgroup_path(path).map(|m| (m.runtime, m.id, m.pod_uid, m.unit))
+        };
+        let s = |s: &str| Some(s.to_owned());
+        assert_eq!(
+            labels(&format!("/system.slice/docker-{}.scope", ID)),
+            Some((s("docker"), s(ID), None, s(&format!("docker-{}.scope", ID))))
+        );
+        assert_eq!(
+            labels(&format!("/docker/{}", ID)),
+            Some((s("docker"), s(ID), None, None))
+        );
+        assert_eq!(
+            labels(&format!(
+                "/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod{}.slice/cri-containerd-{}.scope",
+                UID.replace('-', "_"),
+                ID
+            )),
+            Some((
+                s("containerd"),
+                s(ID),
+                s(UID),
+                s(&format!("cri-containerd-{}.scope", ID))
+            ))
+        );
+        assert_eq!(
+            labels(&format!("/kubepods/besteffort/pod{}/{}", UID, ID)),
+            Some((None, s(ID), s(UID), None))
+        );
+        assert_eq!(
+            labels(&format!("/machine.slice/libpod-{}.scope/container", ID)),
+            Some((s("podman"), s(ID), None, s(&format!("libpod-{}.scope", ID))))
+        );
+        assert_eq!(
+            labels(&format!("/machine.slice/crio-conmon-{}.scope", ID)),
+            Some((None, None, None, s(&format!("crio-conmon-{}.scope", ID))))
+        );
+        assert_eq!(
+            labels(&format!("/kubepods.slice/crio-{}.scope", ID)),
+            Some((s("crio"), s(ID), None, s(&format!("crio-{}.scope", ID))))
+        );
+        assert_eq!(
+            labels("/system.slice/sshd.service"),
+            Some((None, None, None, s("sshd.service")))
+        );
+        assert_eq!(labels("/user.slice"), None);
+        assert_eq!(labels(""), None);
+
+        // Container ids are exactly 64 hex characters
+        assert_eq!(labels(&format!("/docker/{}", &ID[..63])), None);
+        assert_eq!(labels(&format!("/docker/{}0", ID)), None);
+        assert_eq!(
+            labels(&format!("/system.slice/docker-{}0.scope", ID)),
+            Some((None, None, None, s(&format!("docker-{}0.scope", ID))))
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        let mapping: MappingFile = format!(
+            "# comment\n\n{} web frontend\n{} my-pod\n",
+            &ID[..SHORT_ID_LEN],
+            UID
+        )
+        .parse()
+        .expect("Failed to parse mapping");
+        assert_eq!(mapping.lookup(ID), Some("web frontend".to_owned()));
+        assert_eq!(mapping.lookup(UID), Some("my-pod".to_owned()));
+        assert_eq!(mapping.lookup("unknown"), None);
+        assert!("short name\n".parse::<MappingFile>().is_err());
+
+        let mut pod = CgroupSample::default();
+        pod.children = Some(BTreeMap::from([(ID.to_owned(), CgroupSample::default())]));
+        let mut root = CgroupSample::default();
+        root.children = Some(BTreeMap::from([(format!("pod{}", UID), pod)]));
+        let names = lookup_container_names(&root, &mapping);
+        assert_eq!(
+            names,
+            BTreeMap::from([
+                (ID.to_owned(), "web frontend".to_owned()),
+                (UID.to_owned(), "my-pod".to_owned()),
+            ])
+        );
+
+        let path = format!("/pod{}/{}", UID, ID);
+        assert_eq!(
+            ContainerModel::new(&path, Some(&names)).and_then(|m| m.name),
+            Some("web frontend".to_owned())
+        );
+        assert_eq!(
+            ContainerModel::new(&path, None).and_then(|m| m.name),
+            Some(ID[..SHORT_ID_LEN].to_owned())
+        );
+        assert_eq!(
+            ContainerModel::new(&format!("/pod{}", UID), Some(&names)).and_then(|m| m.name),
+            Some("my-pod".to_owned())
+        );
+    }
+}
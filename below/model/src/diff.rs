@@ -0,0 +1,298 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Comparison of the same entities between two Models taken at different
+//! points in time. Used by `below diff` and by the "compare to marked sample"
+//! popup of the TUI.
+//!
+//! Entities (cgroups, processes, the system) are keyed by a string label so
+//! that the entities of both models can be matched. Only numeric fields are
+//! compared. An entity that only exists in one of the models is reported as
+//! appeared or disappeared, with the missing side treated as zero.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::CgroupModel;
+use crate::Field;
+use crate::ProcessModel;
+use crate::Queriable;
+use crate::SingleCgroupModel;
+use crate::SingleProcessModel;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntityState {
+    /// Entity only exists in the later model.
+    Appeared,
+    /// Entity only exists in the earlier model.
+    Disappeared,
+    /// Entity exists in both models.
+    Present,
+}
+
+impl fmt::Display for EntityState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Appeared => "appeared",
+            Self::Disappeared => "disappeared",
+            Self::Present => "present",
+        })
+    }
+}
+
+/// How to order diff rows, from the most to the least significant change.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    below_derive::EnumFromStr,
+    below_derive::EnumToString
+)]
+pub enum DiffOrder {
+    /// By the absolute value of the delta.
+    #[default]
+    Abs,
+    /// By the absolute value of the delta relative to the earlier value.
+    /// Appeared entities and changes from zero come first.
+    Rel,
+}
+
+/// Change of a single field of a single entity.
+#[derive(Clone, Debug)]
+pub struct FieldDiff<F> {
+    pub entity: String,
+    pub state: EntityState,
+    pub field_id: F,
+    /// Value in the earlier model.
+    pub a: Option<Field>,
+    /// Value in the later model.
+    pub b: Option<Field>,
+}
+
+/// Value of a numeric field as f64, None if missing or not numeric.
+pub fn numeric(field: &Option<Field>) -> Option<f64> {
+    match field {
+        Some(
+            field @ (Field::U32(_)
+            | Field::U64(_)
+            | Field::I32(_)
+            | Field::I64(_)
+            | Field::F32(_)
+            | Field::F64(_)),
+        ) => Some(field.clone().into()),
+        _ => None,
+    }
+}
+
+impl<F> FieldDiff<F> {
+    /// b - a. None if the field is not numeric or is missing on one side of
+    /// an entity that exists in both models.
+    pub fn delta(&self) -> Option<f64> {
+        match (numeric(&self.a), numeric(&self.b), self.state) {
+            (Some(a), Some(b), EntityState::Present) => Some(b - a),
+            (None, Some(b), EntityState::Appeared) => Some(b),
+            (Some(a), None, EntityState::Disappeared) => Some(-a),
+            _ => None,
+        }
+    }
+
+    /// Delta relative to the earlier value, e.g. 0.5 for a 50% increase.
+    /// None if there is no earlier value or it is zero.
+    pub fn relative(&self) -> Option<f64> {
+        let delta = self.delta()?;
+        match numeric(&self.a) {
+            Some(a) if a != 0.0 => Some(delta / a.abs()),
+            _ => None,
+        }
+    }
+
+    /// Relative change as a percentage, or whether the entity is new or gone.
+    pub fn relative_string(&self) -> String {
+        match (self.state, self.relative()) {
+            (EntityState::Appeared, _) => "new".to_owned(),
+            (EntityState::Disappeared, _) => "gone".to_owned(),
+            (_, Some(relative)) => format!("{:+.1}%", relative * 100.0),
+            (_, None) => "-".to_owned(),
+        }
+    }
+
+    fn significance(&self, order: DiffOrder) -> f64 {
+        let delta = match self.delta() {
+            Some(delta) => delta,
+            None => return f64::NEG_INFINITY,
+        };
+        match order {
+            DiffOrder::Abs => delta.abs(),
+            DiffOrder::Rel => self.relative().map_or(f64::INFINITY, f64::abs),
+        }
+    }
+}
+
+/// Compares `field_ids` of each entity in `a` and `b`. Rows are emitted in
+/// entity order, skipping non-numeric fields and fields that did not change
+/// for entities present in both models.
+pub fn diff_queriables<Q: Queriable>(
+    a: &BTreeMap<String, &Q>,
+    b: &BTreeMap<String, &Q>,
+    field_ids: &[Q::FieldId],
+) -> Vec<FieldDiff<Q::FieldId>>
+where
+    Q::FieldId: Clone,
+{
+    let mut entities: Vec<&String> = a.keys().chain(b.keys()).collect();
+    entities.sort();
+    entities.dedup();
+
+    let mut diffs = Vec::new();
+    for entity in entities {
+        let (lhs, rhs) = (a.get(entity), b.get(entity));
+        let state = match (lhs, rhs) {
+            (Some(_), Some(_)) => EntityState::Present,
+            (None, Some(_)) => EntityState::Appeared,
+            _ => EntityState::Disappeared,
+        };
+        for field_id in field_ids {
+            let diff = FieldDiff {
+                entity: entity.clone(),
+                state,
+                field_id: field_id.clone(),
+                a: lhs.and_then(|q| q.query(field_id)),
+                b: rhs.and_then(|q| q.query(field_id)),
+            };
+            match diff.delta() {
+                Some(delta) if delta != 0.0 || state != EntityState::Present => diffs.push(diff),
+                _ => {}
+            }
+        }
+    }
+    diffs
+}
+
+/// Sorts diff rows from the most to the least significant change. The sort is
+/// stable so rows of equal significance stay in entity order.
+pub fn sort_diffs<F>(diffs: &mut [FieldDiff<F>], order: DiffOrder) {
+    diffs.sort_by(|lhs, rhs| {
+        rhs.significance(order)
+            .partial_cmp(&lhs.significance(order))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// All cgroups of the tree keyed by full path.
+pub fn cgroup_entities(model: &CgroupModel) -> BTreeMap<String, &SingleCgroupModel> {
+    let mut entities = BTreeMap::new();
+    let mut stack = vec![model];
+    while let Some(cgroup) = stack.pop() {
+        entities.insert(cgroup.data.full_path.clone(), &cgroup.data);
+        stack.extend(cgroup.children.iter());
+    }
+    entities
+}
+
+/// All processes keyed by "pid (comm)" so that a reused pid shows up as a
+/// different entity.
+pub fn process_entities(model: &ProcessModel) -> BTreeMap<String, &SingleProcessModel> {
+    model
+        .processes
+        .iter()
+        .map(|(pid, process)| {
+            let comm = process.comm.as_deref().unwrap_or("?");
+            (format!("{} ({})", pid, comm), process)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ProcessCpuModel;
+    use crate::SingleProcessModelFieldId;
+
+    fn process(pid: i32, comm: &str, usage_pct: f64) -> SingleProcessModel {
+        SingleProcessModel {
+            pid: Some(pid),
+            comm: Some(comm.to_owned()),
+            cpu: Some(ProcessCpuModel {
+                usage_pct: Some(usage_pct),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn model(processes: Vec<SingleProcessModel>) -> ProcessModel {
+        ProcessModel {
+            processes: processes.into_iter().map(|p| (p.pid.unwrap(), p)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff_processes() {
+        let a = model(vec![
+            process(1, "init", 1.0),
+            process(2, "idle", 5.0),
+            process(3, "gone", 20.0),
+        ]);
+        let b = model(vec![
+            process(1, "init", 4.0),
+            process(2, "idle", 5.0),
+            process(4, "new", 2.0),
+        ]);
+        let field_ids: Vec<SingleProcessModelFieldId> =
+            vec!["cpu.usage_pct".parse().unwrap(), "comm".parse().unwrap()];
+        let mut diffs = diff_queriables(&process_entities(&a), &process_entities(&b), &field_ids);
+        let summary = |diffs: &[FieldDiff<_>]| {
+            diffs
+                .iter()
+                .map(|d| (d.entity.clone(), d.state, d.delta().unwrap()))
+                .collect::<Vec<_>>()
+        };
+        // Unchanged and non-numeric fields are skipped
+        assert_eq!(
+            summary(&diffs),
+            vec![
+                ("1 (init)".to_owned(), EntityState::Present, 3.0),
+                ("3 (gone)".to_owned(), EntityState::Disappeared, -20.0),
+                ("4 (new)".to_owned(), EntityState::Appeared, 2.0),
+            ]
+        );
+
+        sort_diffs(&mut diffs, DiffOrder::Abs);
+        assert_eq!(diffs[0].entity, "3 (gone)");
+        assert_eq!(diffs[1].entity, "1 (init)");
+
+        sort_diffs(&mut diffs, DiffOrder::Rel);
+        assert_eq!(diffs[0].entity, "4 (new)");
+        assert_eq!(diffs[1].entity, "1 (init)");
+        assert_eq!(diffs[1].relative(), Some(3.0));
+        assert_eq!(diffs[2].relative(), Some(-1.0));
+    }
+
+    #[test]
+    fn test_cgroup_entities() {
+        let mut child = CgroupModel::default();
+        child.data.name = "foo".to_owned();
+        child.data.full_path = "/foo".to_owned();
+        let mut root = CgroupModel::default();
+        root.data.full_path = "".to_owned();
+        root.children.insert(child);
+        let entities = cgroup_entities(&root);
+        assert_eq!(
+            entities.keys().collect::<Vec<_>>(),
+            vec![&"".to_owned(), &"/foo".to_owned()]
+        );
+    }
+}
@@ -0,0 +1,555 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Boolean filter expressions evaluated against the fields of a Queriable.
+//!
+//! Grammar (lowest to highest precedence):
+//!
+//! ```text
+//! expr    := and ("||" and)*
+//! and     := unary ("&&" unary)*
+//! unary   := "!" unary | "(" expr ")" | field op value
+//! op      := "==" | "!=" | "<" | "<=" | ">" | ">=" | "=~" | "~" | "!~"
+//! value   := number | number% | 'string' | "string" | bareword
+//! ```
+//!
+//! A bareword is anything that does not parse as a number, so `comm == 7zip`
+//! compares against the string `7zip`.
+//!
+//! `~` is a shorthand for `=~`. A `%` after a number is only allowed for
+//! percentage fields, whose names end with `_pct`, and is otherwise ignored
+//! as percentages are stored as such, e.g. `cpu.usage_pct > 10%`.
+//!
+//! Fields are the string representation of the FieldId of the Queriable, e.g.
+//! `cpu.usage_pct` for SingleProcessModel. For example:
+//!
+//! ```text
+//! cpu.usage_pct > 50 && cgroup =~ 'workload.slice'
+//! ```
+//!
+//! A comparison against a field that has no value (None) is always false.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Result;
+use regex::Regex;
+
+use crate::Field;
+use crate::FieldId;
+use crate::Queriable;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+
+    fn test(&self, ord: std::cmp::Ordering) -> bool {
+        use std::cmp::Ordering::*;
+        match self {
+            Self::Eq => ord == Equal,
+            Self::Ne => ord != Equal,
+            Self::Lt => ord == Less,
+            Self::Le => ord != Greater,
+            Self::Gt => ord == Greater,
+            Self::Ge => ord != Less,
+        }
+    }
+}
+
+/// Right hand side of a comparison.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    Num(f64),
+    Pct(f64),
+    Str(String),
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Num(v) => v.fmt(f),
+            Self::Pct(v) => write!(f, "{}%", v),
+            Self::Str(v) => write!(f, "'{}'", v),
+        }
+    }
+}
+
+/// Filter expression over fields of type F. A FilterExpr<String> is the
+/// unbound form produced by the parser, which can be bound to a concrete
+/// FieldId with `bind`.
+#[derive(Clone, Debug)]
+pub enum FilterExpr<F> {
+    Compare {
+        field: F,
+        op: CompareOp,
+        value: Literal,
+    },
+    Match {
+        field: F,
+        regex: Regex,
+        negate: bool,
+    },
+    Not(Box<FilterExpr<F>>),
+    And(Box<FilterExpr<F>>, Box<FilterExpr<F>>),
+    Or(Box<FilterExpr<F>>, Box<FilterExpr<F>>),
+}
+
+fn field_as_f64(field: &Field) -> Option<f64> {
+    match field {
+        Field::U32(_)
+        | Field::U64(_)
+        | Field::I32(_)
+        | Field::I64(_)
+        | Field::F32(_)
+        | Field::F64(_) => Some(field.clone().into()),
+        _ => field.to_string().parse().ok(),
+    }
+}
+
+fn compare(field: &Field, op: CompareOp, value: &Literal) -> bool {
+    match value {
+        Literal::Num(rhs) | Literal::Pct(rhs) => match field_as_f64(field) {
+            Some(lhs) => lhs.partial_cmp(rhs).map_or(false, |ord| op.test(ord)),
+            None => false,
+        },
+        Literal::Str(rhs) => op.test(field.to_string().as_str().cmp(rhs.as_str())),
+    }
+}
+
+impl<F> FilterExpr<F> {
+    /// Convert the fields of this expression into another type, failing on
+    /// the first field that cannot be converted.
+    pub fn try_map_fields<T, E>(
+        &self,
+        f: &impl Fn(&F) -> Result<T, E>,
+    ) -> Result<FilterExpr<T>, E> {
+        Ok(match self {
+            Self::Compare { field, op, value } => FilterExpr::Compare {
+                field: f(field)?,
+                op: *op,
+                value: value.clone(),
+            },
+            Self::Match {
+                field,
+                regex,
+                negate,
+            } => FilterExpr::Match {
+                field: f(field)?,
+                regex: regex.clone(),
+                negate: *negate,
+            },
+            Self::Not(e) => FilterExpr::Not(Box::new(e.try_map_fields(f)?)),
+            Self::And(l, r) => FilterExpr::And(
+                Box::new(l.try_map_fields(f)?),
+                Box::new(r.try_map_fields(f)?),
+            ),
+            Self::Or(l, r) => FilterExpr::Or(
+                Box::new(l.try_map_fields(f)?),
+                Box::new(r.try_map_fields(f)?),
+            ),
+        })
+    }
+
+    /// Collect all fields referenced by this expression.
+    pub fn fields(&self) -> Vec<&F> {
+        match self {
+            Self::Compare { field, .. } | Self::Match { field, .. } => vec![field],
+            Self::Not(e) => e.fields(),
+            Self::And(l, r) | Self::Or(l, r) => {
+                let mut res = l.fields();
+                res.extend(r.fields());
+                res
+            }
+        }
+    }
+}
+
+impl<F: fmt::Display> FilterExpr<F> {
+    /// Bind an expression to a concrete field type by parsing its field names.
+    pub fn bind<T: FromStr>(&self) -> Result<FilterExpr<T>> {
+        self.check_pct()?;
+        self.try_map_fields(&|field: &F| {
+            let name = field.to_string();
+            T::from_str(&name).map_err(|_| anyhow!("Unknown field in filter expression: {}", name))
+        })
+    }
+
+    /// Fail if a percentage is compared to a field that is not one.
+    fn check_pct(&self) -> Result<()> {
+        match self {
+            Self::Compare {
+                field,
+                value: Literal::Pct(v),
+                ..
+            } if !field.to_string().ends_with("_pct") => bail!(
+                "{}% compared to {} in filter expression, which is not a percentage",
+                v,
+                field
+            ),
+            Self::Compare { .. } | Self::Match { .. } => Ok(()),
+            Self::Not(e) => e.check_pct(),
+            Self::And(l, r) | Self::Or(l, r) => {
+                l.check_pct()?;
+                r.check_pct()
+            }
+        }
+    }
+}
+
+impl<F: FieldId> FilterExpr<F> {
+    /// Evaluate the expression against a Queriable.
+    pub fn eval(&self, queriable: &F::Queriable) -> bool {
+        match self {
+            Self::Compare { field, op, value } => queriable
+                .query(field)
+                .map_or(false, |v| compare(&v, *op, value)),
+            Self::Match {
+                field,
+                regex,
+                negate,
+            } => queriable
+                .query(field)
+                .map_or(false, |v| regex.is_match(&v.to_string()) != *negate),
+            Self::Not(e) => !e.eval(queriable),
+            Self::And(l, r) => l.eval(queriable) && r.eval(queriable),
+            Self::Or(l, r) => l.eval(queriable) || r.eval(queriable),
+        }
+    }
+}
+
+impl<F: fmt::Display> fmt::Display for FilterExpr<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compare { field, op, value } => write!(f, "{} {} {}", field, op.as_str(), value),
+            Self::Match {
+                field,
+                regex,
+                negate,
+            } => write!(
+                f,
+                "{} {} '{}'",
+                field,
+                if *negate { "!~" } else { "=~" },
+                regex.as_str()
+            ),
+            Self::Not(e) => write!(f, "!({})", e),
+            Self::And(l, r) => write!(f, "({} && {})", l, r),
+            Self::Or(l, r) => write!(f, "({} || {})", l, r),
+        }
+    }
+}
+
+impl<F: FromStr> FromStr for FilterExpr<F> {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            bail!("Unexpected token in filter expression: {:?}", token);
+        }
+        expr.bind()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Num(f64),
+    Pct(f64),
+    Str(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | ':' | '/' | '-')
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    const OPS: &[&str] = &[
+        "&&", "||", "==", "!=", "<=", ">=", "=~", "!~", "<", ">", "!", "~",
+    ];
+    let chars: Vec<char> = s.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    'outer: while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '(' || c == ')' {
+            tokens.push(if c == '(' {
+                Token::LParen
+            } else {
+                Token::RParen
+            });
+            i += 1;
+            continue;
+        }
+        if c == '\'' || c == '"' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|&x| x == c)
+                .ok_or_else(|| anyhow!("Unterminated string in filter expression: {}", s))?;
+            tokens.push(Token::Str(chars[i + 1..i + 1 + end].iter().collect()));
+            i += end + 2;
+            continue;
+        }
+        for op in OPS {
+            if chars[i..].starts_with(&op.chars().collect::<Vec<_>>()) {
+                tokens.push(Token::Op(op));
+                i += op.len();
+                continue 'outer;
+            }
+        }
+        if c.is_ascii_digit() || c == '-' || c == '.' {
+            let len = chars[i..]
+                .iter()
+                .position(|&x| !(x.is_ascii_alphanumeric() || matches!(x, '.' | '-' | '+')))
+                .unwrap_or(chars.len() - i);
+            let word: String = chars[i..i + len].iter().collect();
+            // Words that merely start like a number, e.g. 7zip, are barewords
+            if let Ok(num) = word.parse() {
+                i += len;
+                if chars.get(i) == Some(&'%') {
+                    tokens.push(Token::Pct(num));
+                    i += 1;
+                } else {
+                    tokens.push(Token::Num(num));
+                }
+                continue;
+            }
+        }
+        if is_ident_char(c) {
+            let len = chars[i..]
+                .iter()
+                .position(|&x| !is_ident_char(x))
+                .unwrap_or(chars.len() - i);
+            tokens.push(Token::Ident(chars[i..i + len].iter().collect()));
+            i += len;
+            continue;
+        }
+        bail!("Unexpected character in filter expression: {}", c);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_op(&mut self, op: &'static str) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr<String>> {
+        let mut lhs = self.parse_and()?;
+        while self.eat_op("||") {
+            lhs = FilterExpr::Or(Box::new(lhs), Box::new(self.parse_and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr<String>> {
+        let mut lhs = self.parse_unary()?;
+        while self.eat_op("&&") {
+            lhs = FilterExpr::And(Box::new(lhs), Box::new(self.parse_unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterExpr<String>> {
+        match self.next() {
+            Some(Token::Op("!")) => Ok(FilterExpr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => bail!("Expected ')' in filter expression"),
+                }
+            }
+            Some(Token::Ident(field)) => self.parse_condition(field),
+            Some(token) => bail!("Expected field name in filter expression, got {:?}", token),
+            None => bail!("Unexpected end of filter expression"),
+        }
+    }
+
+    fn parse_condition(&mut self, field: String) -> Result<FilterExpr<String>> {
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => bail!(
+                "Expected operator after field {} in filter expression",
+                field
+            ),
+        };
+        let value = match self.next() {
+            Some(Token::Num(v)) => Literal::Num(v),
+            Some(Token::Pct(v)) => Literal::Pct(v),
+            Some(Token::Str(v)) | Some(Token::Ident(v)) => Literal::Str(v),
+            _ => bail!("Expected value after {} {} in filter expression", field, op),
+        };
+        let op = match op {
+            "=~" | "~" | "!~" => {
+                let pattern = match value {
+                    Literal::Num(_) | Literal::Pct(_) => value.to_string(),
+                    Literal::Str(v) => v,
+                };
+                return Ok(FilterExpr::Match {
+                    field,
+                    regex: Regex::new(&pattern)?,
+                    negate: op == "!~",
+                });
+            }
+            "==" => CompareOp::Eq,
+            "!=" => CompareOp::Ne,
+            "<" => CompareOp::Lt,
+            "<=" => CompareOp::Le,
+            ">" => CompareOp::Gt,
+            ">=" => CompareOp::Ge,
+            _ => bail!("Unexpected operator {} in filter expression", op),
+        };
+        Ok(FilterExpr::Compare { field, op, value })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ProcessCpuModel;
+    use crate::SingleProcessModel;
+    use crate::SingleProcessModelFieldId;
+
+    fn process(comm: &str, cgroup: &str, usage_pct: f64) -> SingleProcessModel {
+        SingleProcessModel {
+            pid: Some(1),
+            comm: Some(comm.to_owned()),
+            cgroup: Some(cgroup.to_owned()),
+            cpu: Some(ProcessCpuModel {
+                usage_pct: Some(usage_pct),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn eval(expr: &str, model: &SingleProcessModel) -> bool {
+        FilterExpr::<SingleProcessModelFieldId>::from_str(expr)
+            .expect("Failed to parse expression")
+            .eval(model)
+    }
+
+    #[test]
+    fn test_compare() {
+        let model = process("below", "/workload.slice/foo.service", 60.0);
+        assert!(eval("cpu.usage_pct > 50", &model));
+        assert!(eval("cpu.usage_pct >= 60", &model));
+        assert!(!eval("cpu.usage_pct < 50", &model));
+        assert!(eval("pid == 1", &model));
+        assert!(eval("comm == below", &model));
+        assert!(eval("comm != 'other'", &model));
+        assert!(eval("comm == 7zip", &process("7zip", "/", 0.0)));
+        assert!(eval("comm == 7-zip", &process("7-zip", "/", 0.0)));
+        assert!(!eval("comm == 7zip", &model));
+        // Fields without values never match
+        assert!(!eval("io.rbytes_per_sec > 0", &model));
+    }
+
+    #[test]
+    fn test_boolean_ops() {
+        let model = process("below", "/workload.slice/foo.service", 60.0);
+        assert!(eval(
+            "cpu.usage_pct > 50 && cgroup =~ 'workload.slice'",
+            &model
+        ));
+        assert!(!eval("cpu.usage_pct > 50 && cgroup !~ workload", &model));
+        assert!(eval("cpu.usage_pct > 90 || comm =~ '^bel'", &model));
+        assert!(eval("!(pid == 2)", &model));
+        assert!(eval("cpu.usage_pct > 50% && cgroup ~ workload", &model));
+        assert!(!eval("!pid == 1 || (pid == 2 && comm == below)", &model));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for expr in [
+            "",
+            "cpu.usage_pct >",
+            "cpu.usage_pct 50",
+            "(pid == 1",
+            "pid == 1)",
+            "comm == 'below",
+            "no_such_field == 1",
+            "comm =~ '('",
+            "pid > 1%",
+            "comm == 1% || cpu.usage_pct > 1%",
+        ] {
+            assert!(
+                FilterExpr::<SingleProcessModelFieldId>::from_str(expr).is_err(),
+                "Expected error for: {}",
+                expr
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let expr = FilterExpr::<String>::from_str("!(pid == 1) && comm =~ 'a.b' || cgroup < x")
+            .expect("Failed to parse expression");
+        let reparsed =
+            FilterExpr::<String>::from_str(&expr.to_string()).expect("Failed to reparse");
+        assert_eq!(expr.to_string(), reparsed.to_string());
+    }
+}
@@ -34,12 +34,18 @@ use serde::Serialize;
 #[macro_use]
 pub mod collector;
 pub mod cgroup;
+pub mod collector_health;
 pub mod collector_plugin;
 #[cfg(test)]
 mod common_field_ids;
+pub mod container;
+pub mod diff;
+pub mod filter_expr;
 pub mod network;
 pub mod process;
+pub mod redact;
 pub mod resctrl;
+pub mod run_delay;
 pub mod sample;
 mod sample_model;
 pub mod system;
@@ -50,8 +56,11 @@ open_source_shim!(pub);
 
 pub use cgroup::*;
 pub use collector::*;
+pub use collector_health::*;
+pub use container::*;
 pub use network::*;
 pub use process::*;
+pub use redact::*;
 pub use resctrl::*;
 pub use sample::*;
 pub use system::*;
@@ -497,6 +506,8 @@ pub struct Model {
     pub resctrl: Option<ResctrlModel>,
     #[queriable(subquery)]
     pub tc: Option<TcModel>,
+    #[queriable(subquery)]
+    pub collector: Option<CollectorHealthModel>,
 }
 
 impl Model {
@@ -504,38 +515,40 @@ impl Model {
     /// `CumulativeSample` as well as the `Duration` since it was
     /// collected.
     pub fn new(timestamp: SystemTime, sample: &Sample, last: Option<(&Sample, Duration)>) -> Self {
+        let carried_last = |collector: &str| {
+            last.and_then(|(s, d)| Some((s, carried_duration(sample, s, d, collector)?)))
+        };
         Model {
             time_elapsed: last.map(|(_, d)| d).unwrap_or_default(),
             timestamp,
-            system: SystemModel::new(&sample.system, last.map(|(s, d)| (&s.system, d))),
+            system: SystemModel::new(
+                &sample.system,
+                last.map(|(s, d)| (&s.system, d)),
+                carried_last("disk_stat").map(|(s, d)| (&s.system.disks, d)),
+            ),
             cgroup: CgroupModel::new(
                 "<root>".to_string(),
                 String::new(),
                 0,
                 &sample.cgroup,
                 last.map(|(s, d)| (&s.cgroup, d)),
+                carried_last("io_stat").map(|(_, d)| d),
+                sample.container_names.as_ref(),
             )
             .aggr_top_level_val(),
-            process: ProcessModel::new(&sample.processes, last.map(|(s, d)| (&s.processes, d))),
-            network: {
-                let sample = NetworkStats {
+            process: ProcessModel::new(
+                &sample.processes,
+                last.map(|(s, d)| (&s.processes, d)),
+                sample.container_names.as_ref(),
+            ),
+            network: NetworkModel::new(
+                &NetworkStats {
                     net: &sample.netstats,
                     ethtool: &sample.ethtool,
-                };
-                let network_stats: NetworkStats;
-
-                let last = if let Some((s, d)) = last {
-                    network_stats = NetworkStats {
-                        net: &s.netstats,
-                        ethtool: &s.ethtool,
-                    };
-                    Some((&network_stats, d))
-                } else {
-                    None
-                };
-
-                NetworkModel::new(&sample, last)
-            },
+                },
+                carried_last("netstat").map(|(s, d)| (&s.netstats, d)),
+                carried_last("ethtool").and_then(|(s, d)| s.ethtool.as_ref().map(|e| (e, d))),
+            ),
             gpu: sample.gpus.as_ref().map(|gpus| {
                 GpuModel::new(&gpus.gpu_map, {
                     if let Some((s, d)) = last {
@@ -548,11 +561,7 @@ impl Model {
             resctrl: sample.resctrl.as_ref().map(|r| {
                 ResctrlModel::new(
                     r,
-                    if let Some((s, d)) = last {
-                        s.resctrl.as_ref().map(|r| (r, d))
-                    } else {
-                        None
-                    },
+                    carried_last("resctrl").and_then(|(s, d)| s.resctrl.as_ref().map(|r| (r, d))),
                 )
             }),
             tc: sample.tc.as_ref().map(|tc| {
@@ -565,10 +574,36 @@ impl Model {
                     },
                 )
             }),
+            collector: sample
+                .collector_health
+                .as_ref()
+                .map(CollectorHealthModel::new),
         }
     }
 }
 
+/// Duration over which the counters of collector changed between last and
+/// sample, which were duration apart. None if collector is stale in sample, as
+/// its counters are carried from an earlier one and did not change. If it is
+/// stale in last, they changed since last's carried values were collected.
+fn carried_duration(
+    sample: &Sample,
+    last: &Sample,
+    duration: Duration,
+    collector: &str,
+) -> Option<Duration> {
+    let stale_age = |s: &Sample| {
+        s.collector_health
+            .as_ref()
+            .and_then(|health| health.stale_age_usec.as_ref())
+            .and_then(|ages| ages.get(collector).copied())
+    };
+    if stale_age(sample).is_some() {
+        return None;
+    }
+    Some(duration + Duration::from_micros(stale_age(last).unwrap_or_default()))
+}
+
 /// Get a sample `Model`. There are no guarantees internal consistency of the
 /// model, neither are values in the model supposed to be realistic.
 pub fn get_sample_model() -> Model {
@@ -694,6 +729,7 @@ mod tests {
                 "process.processes.1.comm",
                 Some(Field::Str("systemd".to_owned())),
             ),
+            ("collector.collect_latency_usec", Some(Field::U64(12500))),
         ] {
             assert_eq!(
                 &model.query(
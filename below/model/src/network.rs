@@ -35,7 +35,13 @@ pub struct NetworkModel {
 }
 
 impl NetworkModel {
-    pub fn new(sample: &NetworkStats, last: Option<(&NetworkStats, Duration)>) -> Self {
+    /// The last netstat and ethtool stats come with their own durations, as
+    /// either may be carried forward from an earlier sample.
+    pub fn new(
+        sample: &NetworkStats,
+        last_net: Option<(&procfs::NetStat, Duration)>,
+        last_ethtool: Option<(&ethtool::EthtoolStats, Duration)>,
+    ) -> Self {
         let mut interfaces: BTreeMap<String, SingleNetModel> = BTreeMap::new();
 
         let net_stats = sample.net;
@@ -67,25 +73,15 @@ impl NetworkModel {
                 nic: ethtool_stat,
             };
 
-            let mut l_network_stat = SingleNetworkStat {
-                iface: None,
-                nic: None,
-            };
-            let l_iface = last.map(|(l, d)| {
-                let l_iface_stat = l
-                    .net
-                    .interfaces
+            let l_iface = last_net.and_then(|(l, d)| {
+                l.interfaces
                     .as_ref()
-                    .and_then(|ifaces| ifaces.get(&interface));
-                let l_ethtool_stat = l.ethtool.as_ref().and_then(|stat| stat.nic.get(&interface));
-                l_network_stat = SingleNetworkStat {
-                    iface: l_iface_stat,
-                    nic: l_ethtool_stat,
-                };
-                (&l_network_stat, d)
+                    .and_then(|ifaces| ifaces.get(&interface))
+                    .map(|l| (l, d))
             });
+            let l_nic = last_ethtool.and_then(|(l, d)| l.nic.get(&interface).map(|l| (l, d)));
 
-            let net_model = SingleNetModel::new(&interface, &s_iface, l_iface);
+            let net_model = SingleNetModel::new(&interface, &s_iface, l_iface, l_nic);
             interfaces.insert(interface, net_model);
         }
 
@@ -93,57 +89,33 @@ impl NetworkModel {
             interfaces,
             tcp: TcpModel::new(
                 sample.net.tcp.as_ref().unwrap_or(&Default::default()),
-                last.and_then(|(l, d)| {
-                    let n = l.net;
-                    n.tcp.as_ref().map(|n| (n, d))
-                }),
+                last_net.and_then(|(n, d)| n.tcp.as_ref().map(|n| (n, d))),
             ),
             ip: IpModel::new(
                 sample.net.ip.as_ref().unwrap_or(&Default::default()),
-                last.and_then(|(l, d)| {
-                    let n = l.net;
-                    n.ip.as_ref().map(|n| (n, d))
-                }),
+                last_net.and_then(|(n, d)| n.ip.as_ref().map(|n| (n, d))),
                 sample.net.ip_ext.as_ref().unwrap_or(&Default::default()),
-                last.and_then(|(l, d)| {
-                    let n = l.net;
-                    n.ip_ext.as_ref().map(|n| (n, d))
-                }),
+                last_net.and_then(|(n, d)| n.ip_ext.as_ref().map(|n| (n, d))),
             ),
             ip6: Ip6Model::new(
                 sample.net.ip6.as_ref().unwrap_or(&Default::default()),
-                last.and_then(|(l, d)| {
-                    let n = l.net;
-                    n.ip6.as_ref().map(|n| (n, d))
-                }),
+                last_net.and_then(|(n, d)| n.ip6.as_ref().map(|n| (n, d))),
             ),
             icmp: IcmpModel::new(
                 sample.net.icmp.as_ref().unwrap_or(&Default::default()),
-                last.and_then(|(l, d)| {
-                    let n = l.net;
-                    n.icmp.as_ref().map(|n| (n, d))
-                }),
+                last_net.and_then(|(n, d)| n.icmp.as_ref().map(|n| (n, d))),
             ),
             icmp6: Icmp6Model::new(
                 sample.net.icmp6.as_ref().unwrap_or(&Default::default()),
-                last.and_then(|(l, d)| {
-                    let n = l.net;
-                    n.icmp6.as_ref().map(|n| (n, d))
-                }),
+                last_net.and_then(|(n, d)| n.icmp6.as_ref().map(|n| (n, d))),
             ),
             udp: UdpModel::new(
                 sample.net.udp.as_ref().unwrap_or(&Default::default()),
-                last.and_then(|(l, d)| {
-                    let n = l.net;
-                    n.udp.as_ref().map(|n| (n, d))
-                }),
+                last_net.and_then(|(n, d)| n.udp.as_ref().map(|n| (n, d))),
             ),
             udp6: Udp6Model::new(
                 sample.net.udp6.as_ref().unwrap_or(&Default::default()),
-                last.and_then(|(l, d)| {
-                    let n = l.net;
-                    n.udp6.as_ref().map(|n| (n, d))
-                }),
+                last_net.and_then(|(n, d)| n.udp6.as_ref().map(|n| (n, d))),
             ),
         }
     }
@@ -521,7 +493,8 @@ impl SingleNetModel {
     fn new(
         interface: &str,
         sample: &SingleNetworkStat,
-        last: Option<(&SingleNetworkStat, Duration)>,
+        last_iface: Option<(&procfs::InterfaceStat, Duration)>,
+        last_nic: Option<(&ethtool::NicStats, Duration)>,
     ) -> SingleNetModel {
         let iface_stat = sample.iface;
         let ethtool_stat = sample.nic;
@@ -533,19 +506,12 @@ impl SingleNetModel {
 
         // set procfs iface stats
         if let Some(iface_stat) = iface_stat {
-            Self::add_iface_stats(
-                &mut net_model,
-                iface_stat,
-                last.and_then(|(l, d)| l.iface.map(|l| (l, d))),
-            );
+            Self::add_iface_stats(&mut net_model, iface_stat, last_iface);
         }
 
         // set ethtool stats
         if let Some(nic_stat) = ethtool_stat {
-            let sample = nic_stat;
-            let last = last.and_then(|(l, d)| l.nic.map(|l| (l, d)));
-
-            Self::add_ethtool_stats(&mut net_model, sample, last);
+            Self::add_ethtool_stats(&mut net_model, nic_stat, last_nic);
         }
 
         net_model
@@ -764,17 +730,16 @@ mod test {
             )]),
         };
 
-        let prev_sample = NetworkStats {
-            net: &l_net_stats,
-            ethtool: &Some(l_ethtool_stats),
-        };
         let sample = NetworkStats {
             net: &s_net_stats,
             ethtool: &Some(s_ethtool_stats),
         };
-        let last = Some((&prev_sample, Duration::from_secs(1)));
 
-        let model = NetworkModel::new(&sample, last);
+        let model = NetworkModel::new(
+            &sample,
+            Some((&l_net_stats, Duration::from_secs(1))),
+            Some((&l_ethtool_stats, Duration::from_secs(1))),
+        );
 
         let iface_model = model.interfaces.get("eth0").unwrap();
         assert_eq!(iface_model.tx_timeout_per_sec, Some(10));
@@ -23,4 +23,35 @@
 ///
 /// This list also servers as documentation for available field ids that could
 /// be used in other below crates. A test ensures that this list is up-to-date.
-pub const MODEL_FIELD_IDS: &[&str] = &crate::common_field_ids::COMMON_MODEL_FIELD_IDS;
+pub const MODEL_FIELD_IDS: &[&str] = &{
+    const COMMON: &[&str] = &crate::common_field_ids::COMMON_MODEL_FIELD_IDS;
+    let mut ids = [""; COMMON.len() + GPU_MODEL_FIELD_IDS.len()];
+    let mut i = 0;
+    while i < COMMON.len() {
+        ids[i] = COMMON[i];
+        i += 1;
+    }
+    while i < ids.len() {
+        ids[i] = GPU_MODEL_FIELD_IDS[i - COMMON.len()];
+        i += 1;
+    }
+    ids
+};
+
+/// Field ids of the open source `GpuModel`.
+const GPU_MODEL_FIELD_IDS: [&str; 14] = [
+    "gpu.devices.<key>.name",
+    "gpu.devices.<key>.driver",
+    "gpu.devices.<key>.pci_slot",
+    "gpu.devices.<key>.busy_pct",
+    "gpu.devices.<key>.vram_used_bytes",
+    "gpu.devices.<key>.vram_total_bytes",
+    "gpu.devices.<key>.vram_used_pct",
+    "gpu.devices.<key>.temp_celsius",
+    "gpu.devices.<key>.power_watts",
+    "gpu.processes.<key>.pid",
+    "gpu.processes.<key>.comm",
+    "gpu.processes.<key>.busy_pct",
+    "gpu.processes.<key>.memory_bytes",
+    "gpu.processes.<key>.vram_bytes",
+];
@@ -13,17 +13,178 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use serde::Deserialize;
-use serde::Serialize;
-
 use super::*;
 
-#[derive(Default, Clone, Serialize, Deserialize, below_derive::Queriable)]
-pub struct GpuModel {}
+/// GPU usage read from the DRM sysfs files of each card and the DRM clients of
+/// each process.
+#[::below_derive::queriable_derives]
+pub struct GpuModel {
+    #[queriable(subquery)]
+    pub devices: BTreeMap<String, SingleGpuModel>,
+    #[queriable(subquery)]
+    pub processes: BTreeMap<i32, GpuProcessModel>,
+}
 
 impl GpuModel {
-    pub fn new(_sample: &gpu_stats::GpuMap, _last: Option<(&gpu_stats::GpuMap, Duration)>) -> Self {
-        // Open source GPU Model not implemented yet
-        Self {}
+    pub fn new(sample: &gpu_stats::GpuMap, last: Option<(&gpu_stats::GpuMap, Duration)>) -> Self {
+        GpuModel {
+            devices: sample
+                .devices
+                .iter()
+                .map(|(name, stats)| (name.clone(), SingleGpuModel::new(name, stats)))
+                .collect(),
+            processes: sample
+                .processes
+                .iter()
+                .map(|(pid, stats)| {
+                    let last = last.and_then(|(l, d)| l.processes.get(pid).map(|l| (l, d)));
+                    (*pid, GpuProcessModel::new(*pid, stats, last))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[::below_derive::queriable_derives]
+pub struct SingleGpuModel {
+    pub name: String,
+    pub driver: Option<String>,
+    pub pci_slot: Option<String>,
+    pub busy_pct: Option<f64>,
+    pub vram_used_bytes: Option<u64>,
+    pub vram_total_bytes: Option<u64>,
+    pub vram_used_pct: Option<f64>,
+    pub temp_celsius: Option<f64>,
+    pub power_watts: Option<f64>,
+}
+
+impl SingleGpuModel {
+    fn new(name: &str, stats: &gpu_stats::GpuDeviceStats) -> Self {
+        SingleGpuModel {
+            name: name.to_owned(),
+            driver: stats.driver.clone(),
+            pci_slot: stats.pci_slot.clone(),
+            busy_pct: stats.busy_pct.map(|pct| pct as f64),
+            vram_used_bytes: stats.vram_used_bytes,
+            vram_total_bytes: stats.vram_total_bytes,
+            vram_used_pct: match (stats.vram_used_bytes, stats.vram_total_bytes) {
+                (Some(used), Some(total)) if total > 0 => Some(used as f64 * 100.0 / total as f64),
+                _ => None,
+            },
+            temp_celsius: stats.temp_millicelsius.map(|temp| temp as f64 / 1000.0),
+            power_watts: stats
+                .power_microwatts
+                .map(|power| power as f64 / 1_000_000.0),
+        }
+    }
+}
+
+#[::below_derive::queriable_derives]
+pub struct GpuProcessModel {
+    pub pid: Option<i32>,
+    pub comm: Option<String>,
+    /// Busy time of the busiest engine over the interval
+    pub busy_pct: Option<f64>,
+    /// Sum of the memory of all regions
+    pub memory_bytes: Option<u64>,
+    pub vram_bytes: Option<u64>,
+}
+
+impl GpuProcessModel {
+    fn new(
+        pid: i32,
+        stats: &gpu_stats::GpuProcessStats,
+        last: Option<(&gpu_stats::GpuProcessStats, Duration)>,
+    ) -> Self {
+        GpuProcessModel {
+            pid: Some(pid),
+            comm: stats.comm.clone(),
+            busy_pct: last.and_then(|(l, d)| {
+                stats
+                    .engine_ns
+                    .iter()
+                    .filter_map(|(engine, ns)| {
+                        let last_ns = l.engine_ns.get(engine)?;
+                        let delta = ns.checked_sub(*last_ns)?;
+                        Some(delta as f64 * 100.0 / d.as_nanos() as f64)
+                    })
+                    .reduce(f64::max)
+            }),
+            memory_bytes: if stats.memory_bytes.is_empty() {
+                None
+            } else {
+                Some(stats.memory_bytes.values().sum())
+            },
+            vram_bytes: stats.memory_bytes.get("vram").copied(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gpu_model() {
+        let mut last = gpu_stats::GpuMap::default();
+        last.processes.insert(
+            100,
+            gpu_stats::GpuProcessStats {
+                comm: Some("game".to_owned()),
+                engine_ns: BTreeMap::from([
+                    ("gfx".to_owned(), 1_000_000_000),
+                    ("compute".to_owned(), 0),
+                ]),
+                memory_bytes: BTreeMap::new(),
+            },
+        );
+        let mut sample = gpu_stats::GpuMap::default();
+        sample.devices.insert(
+            "card0".to_owned(),
+            gpu_stats::GpuDeviceStats {
+                driver: Some("amdgpu".to_owned()),
+                pci_slot: Some("0000:03:00.0".to_owned()),
+                busy_pct: Some(42),
+                vram_used_bytes: Some(1 << 30),
+                vram_total_bytes: Some(4 << 30),
+                temp_millicelsius: Some(55000),
+                power_microwatts: Some(31_500_000),
+            },
+        );
+        sample.processes.insert(
+            100,
+            gpu_stats::GpuProcessStats {
+                comm: Some("game".to_owned()),
+                engine_ns: BTreeMap::from([
+                    ("gfx".to_owned(), 1_500_000_000),
+                    ("compute".to_owned(), 250_000_000),
+                ]),
+                memory_bytes: BTreeMap::from([
+                    ("vram".to_owned(), 1 << 20),
+                    ("gtt".to_owned(), 1 << 10),
+                ]),
+            },
+        );
+        sample.processes.insert(200, Default::default());
+
+        let model = GpuModel::new(&sample, Some((&last, Duration::from_secs(1))));
+        let card = &model.devices["card0"];
+        assert_eq!(card.name, "card0");
+        assert_eq!(card.driver.as_deref(), Some("amdgpu"));
+        assert_eq!(card.busy_pct, Some(42.0));
+        assert_eq!(card.vram_used_pct, Some(25.0));
+        assert_eq!(card.temp_celsius, Some(55.0));
+        assert_eq!(card.power_watts, Some(31.5));
+
+        let process = &model.processes[&100];
+        assert_eq!(process.pid, Some(100));
+        assert_eq!(process.busy_pct, Some(50.0));
+        assert_eq!(process.memory_bytes, Some((1 << 20) + (1 << 10)));
+        assert_eq!(process.vram_bytes, Some(1 << 20));
+
+        // No usage without a previous sample of the process
+        let process = &model.processes[&200];
+        assert_eq!(process.busy_pct, None);
+        assert_eq!(process.memory_bytes, None);
     }
 }
@@ -12,18 +12,40 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::path::PathBuf;
+
 use anyhow::Result;
 use async_trait::async_trait;
+use slog::error;
 
 use crate::collector_plugin::AsyncCollectorPlugin;
 
 pub type SampleType = gpu_stats::GpuSample;
 
-pub struct GpuStatsCollectorPlugin {}
+pub struct GpuStatsCollectorPlugin {
+    logger: slog::Logger,
+    reader: gpu_stats::GpuReader,
+}
 
 impl GpuStatsCollectorPlugin {
-    pub fn new(_logger: slog::Logger) -> Result<Self> {
-        Ok(Self {})
+    pub fn new(logger: slog::Logger) -> Result<Self> {
+        Ok(Self {
+            logger,
+            reader: gpu_stats::GpuReader::root(),
+        })
+    }
+
+    /// Read cards from sysfs_root and DRM clients from proc_root instead of
+    /// /sys and /proc.
+    pub fn new_with_roots(
+        logger: slog::Logger,
+        sysfs_root: PathBuf,
+        proc_root: PathBuf,
+    ) -> Result<Self> {
+        Ok(Self {
+            logger,
+            reader: gpu_stats::GpuReader::new(sysfs_root, proc_root),
+        })
     }
 }
 
@@ -33,6 +55,14 @@ impl AsyncCollectorPlugin for GpuStatsCollectorPlugin {
     type T = SampleType;
 
     async fn try_collect(&mut self) -> Result<Option<SampleType>> {
-        Ok(None)
+        let sample = match self.reader.read_sample() {
+            Ok(sample) => Some(sample),
+            Err(e) => {
+                error!(self.logger, "{:#}", e);
+                Default::default()
+            }
+        };
+
+        Ok(sample)
     }
 }
@@ -37,7 +37,11 @@ pub struct ProcessModel {
 }
 
 impl ProcessModel {
-    pub fn new(sample: &procfs::PidMap, last: Option<(&procfs::PidMap, Duration)>) -> ProcessModel {
+    pub fn new(
+        sample: &procfs::PidMap,
+        last: Option<(&procfs::PidMap, Duration)>,
+        container_names: Option<&BTreeMap<String, String>>,
+    ) -> ProcessModel {
         let mut processes: BTreeMap<i32, SingleProcessModel> = BTreeMap::new();
 
         for (pid, pidinfo) in sample.iter() {
@@ -46,14 +50,41 @@ impl ProcessModel {
                 SingleProcessModel::new(
                     pidinfo,
                     last.and_then(|(p, d)| p.get(pid).map(|p| (p, d))),
+                    container_names,
                 ),
             );
         }
+        fill_subtrees(&mut processes);
 
         ProcessModel { processes }
     }
 }
 
+/// Sets subtree of every process to the totals of the process and its
+/// descendants, found by following the ppid of each process up to a process
+/// that is not in processes.
+fn fill_subtrees(processes: &mut BTreeMap<i32, SingleProcessModel>) {
+    let mut subtrees: BTreeMap<i32, ProcessSubtreeModel> = BTreeMap::new();
+    for (pid, spm) in processes.iter() {
+        let own = ProcessSubtreeModel::new(spm);
+        let mut pid = *pid;
+        // Bounded in case a reused pid makes a cycle of ppids
+        for _ in 0..processes.len() {
+            let subtree = subtrees.entry(pid).or_default();
+            *subtree = ProcessSubtreeModel::fold(subtree, &own);
+            match processes[&pid].ppid {
+                Some(ppid) if processes.contains_key(&ppid) => pid = ppid,
+                _ => break,
+            }
+        }
+    }
+    for (pid, subtree) in subtrees {
+        if let Some(spm) = processes.get_mut(&pid) {
+            spm.subtree = Some(subtree);
+        }
+    }
+}
+
 impl Nameable for ProcessModel {
     fn name() -> &'static str {
         "process"
@@ -70,6 +101,8 @@ pub struct SingleProcessModel {
     pub uptime_secs: Option<u64>,
     pub cgroup: Option<String>,
     #[queriable(subquery)]
+    pub container: Option<ContainerModel>,
+    #[queriable(subquery)]
     pub io: Option<ProcessIoModel>,
     #[queriable(subquery)]
     pub mem: Option<ProcessMemoryModel>,
@@ -77,12 +110,15 @@ pub struct SingleProcessModel {
     pub cpu: Option<ProcessCpuModel>,
     pub cmdline: Option<String>,
     pub exe_path: Option<String>,
+    #[queriable(subquery)]
+    pub subtree: Option<ProcessSubtreeModel>,
 }
 
 impl SingleProcessModel {
     fn new(
         sample: &procfs::PidInfo,
         last: Option<(&procfs::PidInfo, Duration)>,
+        container_names: Option<&BTreeMap<String, String>>,
     ) -> SingleProcessModel {
         SingleProcessModel {
             pid: sample.stat.pid,
@@ -97,6 +133,7 @@ impl SingleProcessModel {
             state: sample.stat.state.clone(),
             uptime_secs: sample.stat.running_secs,
             cgroup: Some(sample.cgroup.clone()),
+            container: ContainerModel::new(&sample.cgroup, container_names),
             io: last.map(|(l, d)| ProcessIoModel::new(&l.io, &sample.io, d)),
             mem: last.map(|(l, d)| ProcessMemoryModel::new(l, sample, d)),
             cpu: last.map(|(l, d)| ProcessCpuModel::new(&l.stat, &sample.stat, d)),
@@ -106,6 +143,7 @@ impl SingleProcessModel {
                 Some("?".into())
             },
             exe_path: sample.exe_path.clone(),
+            subtree: None, // Filled in by fill_subtrees
         }
     }
 
@@ -121,11 +159,14 @@ impl SingleProcessModel {
             // 80% sure it should be None here. Don't know what someone can infer from summed uptime
             uptime_secs: None,
             cgroup: None,
+            container: None,
             io: fold_optionals!(&left.io, &right.io, ProcessIoModel::fold),
             mem: fold_optionals!(&left.mem, &right.mem, ProcessMemoryModel::fold),
             cpu: fold_optionals!(&left.cpu, &right.cpu, ProcessCpuModel::fold),
             cmdline: None,
             exe_path: None,
+            // Summing subtrees would count nested processes more than once
+            subtree: None,
         }
     }
 }
@@ -136,6 +177,37 @@ impl Nameable for SingleProcessModel {
     }
 }
 
+/// Totals of a process and all its descendants
+#[::below_derive::queriable_derives]
+pub struct ProcessSubtreeModel {
+    /// Number of processes in the subtree, including the process itself
+    pub num_procs: Option<u64>,
+    pub cpu_usage_pct: Option<f64>,
+    pub rss_bytes: Option<u64>,
+    pub rwbytes_per_sec: Option<f64>,
+}
+
+impl ProcessSubtreeModel {
+    fn new(spm: &SingleProcessModel) -> ProcessSubtreeModel {
+        ProcessSubtreeModel {
+            num_procs: Some(1),
+            cpu_usage_pct: spm.cpu.as_ref().and_then(|cpu| cpu.usage_pct),
+            rss_bytes: spm.mem.as_ref().and_then(|mem| mem.rss_bytes),
+            rwbytes_per_sec: spm.io.as_ref().and_then(|io| io.rwbytes_per_sec),
+        }
+    }
+
+    /// See `SingleProcessModel::fold`
+    pub fn fold(left: &ProcessSubtreeModel, right: &ProcessSubtreeModel) -> ProcessSubtreeModel {
+        ProcessSubtreeModel {
+            num_procs: fold_optionals!(left.num_procs, right.num_procs),
+            cpu_usage_pct: fold_optionals!(left.cpu_usage_pct, right.cpu_usage_pct),
+            rss_bytes: fold_optionals!(left.rss_bytes, right.rss_bytes),
+            rwbytes_per_sec: fold_optionals!(left.rwbytes_per_sec, right.rwbytes_per_sec),
+        }
+    }
+}
+
 #[::below_derive::queriable_derives]
 pub struct ProcessIoModel {
     pub rbytes_per_sec: Option<f64>,
@@ -279,4 +351,41 @@ mod test {
             Some(Field::Str("systemd".to_owned()))
         );
     }
+
+    #[test]
+    fn test_fill_subtrees() {
+        let process = |pid: i32, ppid: i32, usage_pct: f64| SingleProcessModel {
+            pid: Some(pid),
+            ppid: Some(ppid),
+            cpu: Some(ProcessCpuModel {
+                usage_pct: Some(usage_pct),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        // 1 -> 2 -> {3, 4}, 5 has an unknown parent, 6 and 7 are a cycle
+        let mut processes: BTreeMap<i32, SingleProcessModel> = [
+            process(1, 0, 1.0),
+            process(2, 1, 2.0),
+            process(3, 2, 3.0),
+            process(4, 2, 4.0),
+            process(5, 100, 5.0),
+            process(6, 7, 6.0),
+            process(7, 6, 7.0),
+        ]
+        .into_iter()
+        .map(|spm| (spm.pid.unwrap(), spm))
+        .collect();
+        fill_subtrees(&mut processes);
+        let subtree = |pid: i32| {
+            let subtree = processes[&pid].subtree.clone().unwrap();
+            (subtree.num_procs, subtree.cpu_usage_pct)
+        };
+        assert_eq!(subtree(1), (Some(4), Some(10.0)));
+        assert_eq!(subtree(2), (Some(3), Some(9.0)));
+        assert_eq!(subtree(3), (Some(1), Some(3.0)));
+        assert_eq!(subtree(5), (Some(1), Some(5.0)));
+        // Counted more than once but terminates
+        assert!(subtree(6).0.is_some());
+    }
 }
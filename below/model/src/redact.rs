@@ -0,0 +1,139 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Redaction of process cmdlines, which often carry tokens, passwords or
+//! customer identifiers. Applied to samples before they are stored, and to
+//! existing samples when taking a redacted snapshot.
+
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use regex::Regex;
+
+use super::*;
+
+pub struct Redactor {
+    /// Applied to each cmdline argument in order
+    rules: Vec<(Regex, String)>,
+    /// comm or exe file names whose cmdline is dropped entirely
+    drop_cmdline: BTreeSet<String>,
+}
+
+impl Redactor {
+    /// rules are pairs of a regex and its replacement, which may refer to
+    /// capture groups as in `Regex::replace_all`, e.g. `${1}`.
+    pub fn new<'a>(
+        rules: impl IntoIterator<Item = (&'a str, &'a str)>,
+        drop_cmdline: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self> {
+        let rules = rules
+            .into_iter()
+            .map(|(pattern, replacement)| {
+                Regex::new(pattern)
+                    .with_context(|| format!("Invalid redaction pattern {}", pattern))
+                    .map(|re| (re, replacement.to_owned()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            rules,
+            drop_cmdline: drop_cmdline.into_iter().map(str::to_owned).collect(),
+        })
+    }
+
+    pub fn redact_pid(&self, pid_info: &mut procfs::PidInfo) {
+        let exe_name = pid_info
+            .exe_path
+            .as_deref()
+            .and_then(|exe_path| Path::new(exe_path).file_name())
+            .and_then(|name| name.to_str());
+        let dropped = [pid_info.stat.comm.as_deref(), exe_name]
+            .into_iter()
+            .flatten()
+            .any(|name| self.drop_cmdline.contains(name));
+        if dropped {
+            pid_info.cmdline_vec = None;
+            return;
+        }
+        for arg in pid_info.cmdline_vec.iter_mut().flatten() {
+            for (re, replacement) in &self.rules {
+                let redacted = match re.replace_all(arg, replacement.as_str()) {
+                    Cow::Borrowed(_) => continue,
+                    Cow::Owned(redacted) => redacted,
+                };
+                *arg = redacted;
+            }
+        }
+    }
+
+    pub fn redact_sample(&self, sample: &mut Sample) {
+        for pid_info in sample.processes.values_mut() {
+            self.redact_pid(pid_info);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pid_info(comm: &str, exe_path: &str, cmdline: &[&str]) -> procfs::PidInfo {
+        procfs::PidInfo {
+            stat: procfs::PidStat {
+                comm: Some(comm.to_owned()),
+                ..Default::default()
+            },
+            exe_path: Some(exe_path.to_owned()),
+            cmdline_vec: Some(cmdline.iter().map(|arg| arg.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_redact_pid() {
+        let redactor = Redactor::new(
+            [
+                (r"^(--password=)\S+", "${1}<redacted>"),
+                (r"cust-[0-9]+", "cust-<redacted>"),
+            ],
+            ["vault", "mysql"],
+        )
+        .unwrap();
+
+        let mut info = pid_info(
+            "server",
+            "/usr/bin/server",
+            &["server", "--password=hunter2", "--for=cust-42,cust-43"],
+        );
+        redactor.redact_pid(&mut info);
+        assert_eq!(
+            info.cmdline_vec.unwrap(),
+            vec![
+                "server",
+                "--password=<redacted>",
+                "--for=cust-<redacted>,cust-<redacted>"
+            ]
+        );
+
+        // Dropped by comm or by exe file name
+        let mut info = pid_info("vault", "/usr/bin/vault", &["vault", "token"]);
+        redactor.redact_pid(&mut info);
+        assert_eq!(info.cmdline_vec, None);
+        let mut info = pid_info("mysqld-wrapper", "/usr/sbin/mysql", &["mysql", "-pfoo"]);
+        redactor.redact_pid(&mut info);
+        assert_eq!(info.cmdline_vec, None);
+
+        assert!(Redactor::new([("(", "")], ["vault"]).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Run queue latency histograms, i.e. how long tasks waited for a CPU after
+//! becoming runnable. Collected per cgroup by a BPF program hooking the
+//! scheduler tracepoints, see `enable_run_delay_stats` in below.conf.
+
+use super::*;
+
+/// Number of log2 buckets, the last one holds everything above 2^26 usec.
+pub const NR_BUCKETS: usize = 27;
+
+/// Cumulative histograms keyed by cgroup id, i.e. the inode number of the
+/// cgroup2 directory.
+pub type SampleType = BTreeMap<u64, RunDelayHistogram>;
+
+/// Cumulative log2 histogram of run queue latencies. Bucket i counts the
+/// latencies in [2^i, 2^(i+1)) usec, bucket 0 also counts those below 1 usec.
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RunDelayHistogram {
+    pub buckets: Vec<u64>,
+}
+
+impl RunDelayHistogram {
+    pub fn add(&mut self, other: &RunDelayHistogram) {
+        if self.buckets.len() < other.buckets.len() {
+            self.buckets.resize(other.buckets.len(), 0);
+        }
+        for (bucket, count) in self.buckets.iter_mut().zip(&other.buckets) {
+            *bucket += count;
+        }
+    }
+
+    /// Latencies counted since begin. Buckets that went down, e.g. because a
+    /// descendant cgroup was removed, count as zero.
+    pub fn delta(&self, begin: &RunDelayHistogram) -> RunDelayHistogram {
+        RunDelayHistogram {
+            buckets: self
+                .buckets
+                .iter()
+                .enumerate()
+                .map(|(idx, count)| {
+                    count.saturating_sub(begin.buckets.get(idx).copied().unwrap_or_default())
+                })
+                .collect(),
+        }
+    }
+
+    /// Upper bound in usec of the bucket holding the pct percentile, or None
+    /// if the histogram is empty.
+    pub fn percentile(&self, pct: f64) -> Option<u64> {
+        let total: u64 = self.buckets.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let target = ((total as f64 * pct / 100.0).ceil() as u64).max(1);
+        let mut seen = 0;
+        self.buckets
+            .iter()
+            .position(|count| {
+                seen += count;
+                seen >= target
+            })
+            .map(|idx| 1 << (idx + 1))
+    }
+}
+
+/// Sets run_delay of every cgroup in the tree to the sum of the histograms
+/// of the cgroup and its descendants. Returns the histogram of cgroup.
+pub fn fill_cgroup_run_delay(cgroup: &mut CgroupSample, hists: &SampleType) -> RunDelayHistogram {
+    let mut hist = cgroup
+        .inode_number
+        .and_then(|inode_number| hists.get(&(inode_number as u64)))
+        .cloned()
+        .unwrap_or_default();
+    for child in cgroup.children.iter_mut().flat_map(BTreeMap::values_mut) {
+        hist.add(&fill_cgroup_run_delay(child, hists));
+    }
+    cgroup.run_delay = Some(hist.clone());
+    hist
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hist(buckets: &[u64]) -> RunDelayHistogram {
+        RunDelayHistogram {
+            buckets: buckets.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(hist(&[]).percentile(50.0), None);
+        assert_eq!(hist(&[0, 0]).percentile(50.0), None);
+        // 50 under 2us, 49 in [4, 8)us, 1 in [16, 32)us
+        let h = hist(&[50, 0, 49, 0, 1]);
+        assert_eq!(h.percentile(50.0), Some(2));
+        assert_eq!(h.percentile(51.0), Some(8));
+        assert_eq!(h.percentile(99.0), Some(8));
+        assert_eq!(h.percentile(100.0), Some(32));
+        assert_eq!(h.percentile(0.0), Some(2));
+    }
+
+    #[test]
+    fn test_delta_and_fill() {
+        assert_eq!(hist(&[5, 3, 1]).delta(&hist(&[2, 4])), hist(&[3, 0, 1]));
+
+        let mut root = CgroupSample {
+            inode_number: Some(1),
+            children: Some(BTreeMap::from([
+                (
+                    "a".to_owned(),
+                    CgroupSample {
+                        inode_number: Some(2),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "b".to_owned(),
+                    CgroupSample {
+                        inode_number: Some(3),
+                        ..Default::default()
+                    },
+                ),
+            ])),
+            ..Default::default()
+        };
+        let hists = SampleType::from([(1, hist(&[1])), (2, hist(&[0, 2])), (4, hist(&[7]))]);
+        assert_eq!(fill_cgroup_run_delay(&mut root, &hists), hist(&[1, 2]));
+        let children = root.children.as_ref().unwrap();
+        assert_eq!(children["a"].run_delay, Some(hist(&[0, 2])));
+        assert_eq!(children["b"].run_delay, Some(hist(&[])));
+    }
+}
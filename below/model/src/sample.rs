@@ -24,6 +24,12 @@ pub struct Sample {
     pub ethtool: Option<ethtool::EthtoolStats>,
     pub resctrl: Option<resctrlfs::ResctrlSample>,
     pub tc: Option<tc::TcStats>,
+    /// Friendly names of containers and pods keyed by container id or pod
+    /// uid, see `ContainerNameLookup`
+    #[serde(default)]
+    pub container_names: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    pub collector_health: Option<CollectorHealth>,
 }
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -57,6 +63,12 @@ pub struct CgroupSample {
     pub cpu_max: Option<cgroupfs::CpuMax>,
     pub cgroup_controllers: Option<BTreeSet<String>>,
     pub cgroup_subtree_control: Option<BTreeSet<String>>,
+    /// Cumulative number of execs in the cgroup and its descendants since
+    /// below started, if exec tracking is enabled
+    pub exec_count: Option<u64>,
+    /// Run queue latencies of the cgroup and its descendants since below
+    /// started, if run delay stats are enabled
+    pub run_delay: Option<crate::run_delay::RunDelayHistogram>,
 }
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
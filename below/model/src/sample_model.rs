@@ -995,6 +995,20 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
                 }
             }
         ]
+    },
+    "collector": {
+        "collect_latency_usec": 12500,
+        "cgroup_latency_usec": 8000,
+        "procfs_latency_usec": 3000,
+        "netstat_latency_usec": 800,
+        "ethtool_latency_usec": null,
+        "btrfs_latency_usec": null,
+        "tc_latency_usec": 50,
+        "gpu_latency_usec": null,
+        "errors": 0,
+        "exitstat_lost_events": 0,
+        "store_write_latency_usec": 1500,
+        "stale_collectors": ["btrfs"]
     }
 }
 "#;
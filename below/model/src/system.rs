@@ -41,7 +41,13 @@ pub struct SystemModel {
 }
 
 impl SystemModel {
-    pub fn new(sample: &SystemSample, last: Option<(&SystemSample, Duration)>) -> SystemModel {
+    /// The last disk stats come with their own duration, as they may be
+    /// carried forward from an earlier sample.
+    pub fn new(
+        sample: &SystemSample,
+        last: Option<(&SystemSample, Duration)>,
+        last_disks: Option<(&procfs::DiskMap, Duration)>,
+    ) -> SystemModel {
         let stat = ProcStatModel::new(&sample.stat);
         let total_cpu = match (
             last.and_then(|(last, _)| last.stat.total_cpu.as_ref()),
@@ -105,10 +111,10 @@ impl SystemModel {
         sample.disks.iter().for_each(|(disk_name, end_disk_stat)| {
             disks.insert(
                 disk_name.clone(),
-                match last {
-                    Some((last_sample, duration)) if last_sample.disks.contains_key(disk_name) => {
+                match last_disks {
+                    Some((last_disks, duration)) if last_disks.contains_key(disk_name) => {
                         SingleDiskModel::new(
-                            last_sample.disks.get(disk_name).unwrap(),
+                            last_disks.get(disk_name).unwrap(),
                             end_disk_stat,
                             duration,
                         )
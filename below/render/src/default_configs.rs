@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use model::ProcessModelFieldId;
+use model::QueriableContainer;
 use model::SingleCgroupModelFieldId;
 use model::SingleProcessModelFieldId;
 use RenderFormat::Duration;
@@ -41,6 +42,51 @@ impl HasRenderConfig for model::Model {
             Gpu(_) => rc,
             Resctrl(_) => rc,
             Tc(_) => rc,
+            Collector(field_id) => model::CollectorHealthModel::get_render_config_builder(field_id),
+        }
+    }
+}
+
+impl HasRenderConfigForDump for model::Model {
+    fn get_render_config_for_dump(field_id: &Self::FieldId) -> RenderConfig {
+        use model::ModelFieldId::*;
+        match field_id {
+            System(field_id) => model::SystemModel::get_render_config_for_dump(field_id),
+            Cgroup(field_id) => {
+                model::SingleCgroupModel::get_render_config_for_dump(&field_id.subquery_id.0)
+            }
+            Process(ProcessModelFieldId::Processes(field_id)) => {
+                model::SingleProcessModel::get_render_config_for_dump(&field_id.subquery_id.0)
+            }
+            Network(field_id) => model::NetworkModel::get_render_config_for_dump(field_id),
+            _ => Self::get_render_config(field_id),
+        }
+    }
+
+    fn get_openmetrics_config_for_dump(
+        &self,
+        field_id: &Self::FieldId,
+    ) -> Option<RenderOpenMetricsConfigBuilder> {
+        use model::ModelFieldId::*;
+        match field_id {
+            System(field_id) => self.system.get_openmetrics_config_for_dump(field_id),
+            Cgroup(field_id) => self
+                .cgroup
+                .get_item(field_id.idx.as_ref()?)?
+                .get_openmetrics_config_for_dump(&field_id.subquery_id.0),
+            Process(ProcessModelFieldId::Processes(field_id)) => self
+                .process
+                .processes
+                .get(field_id.idx.as_ref()?)?
+                .get_openmetrics_config_for_dump(&field_id.subquery_id.0),
+            Network(field_id) => self.network.get_openmetrics_config_for_dump(field_id),
+            Gpu(_) => None,
+            Resctrl(_) => None,
+            Tc(_) => None,
+            Collector(field_id) => self
+                .collector
+                .as_ref()
+                .and_then(|collector| collector.get_openmetrics_config_for_dump(field_id)),
         }
     }
 }
@@ -53,6 +99,7 @@ impl HasRenderConfig for model::SingleCgroupModel {
             Name => rc.title("Name").width(50),
             FullPath => rc.title("Full Path").width(50),
             InodeNumber => rc.title("Inode Number"),
+            Container(field_id) => model::ContainerModel::get_render_config_builder(field_id),
             Cpu(field_id) => model::CgroupCpuModel::get_render_config_builder(field_id),
             Io(field_id) => model::CgroupIoModel::get_render_config_builder(field_id),
             IoDetails(field_id) => {
@@ -190,6 +237,8 @@ impl HasRenderConfigForDump for model::SingleCgroupModel {
             FullPath => None,
             // Not sure what to do with static fields like inode number so leave out for now
             InodeNumber => None,
+            // OpenMetrics does not support strings
+            Container(_) => None,
             Cpu(field_id) => match field_id {
                 UsagePct => Some(gauge.unit("percent")),
                 UserPct => Some(gauge.unit("percent")),
@@ -197,9 +246,12 @@ impl HasRenderConfigForDump for model::SingleCgroupModel {
                 NrPeriodsPerSec => Some(gauge),
                 NrThrottledPerSec => Some(gauge),
                 ThrottledPct => Some(gauge.unit("percent")),
+                RunDelayUsecP50 => Some(gauge.unit("microseconds")),
+                RunDelayUsecP99 => Some(gauge.unit("microseconds")),
             },
             Pids(field_id) => match field_id {
                 TidsCurrent => Some(counter.unit("count")),
+                ExecPerSec => Some(gauge),
             },
             Io(field_id) => match field_id {
                 RbytesPerSec => Some(gauge.unit("bytes_per_second")),
@@ -303,6 +355,8 @@ impl HasRenderConfig for model::CgroupCpuModel {
             NrPeriodsPerSec => rc.title("Nr Period").suffix("/s").format(Precision(2)),
             NrThrottledPerSec => rc.title("Nr Throttled").suffix("/s").format(Precision(2)),
             ThrottledPct => rc.title("Throttled").suffix("%").format(Precision(2)),
+            RunDelayUsecP50 => rc.title("Run Delay P50").format(Duration),
+            RunDelayUsecP99 => rc.title("Run Delay P99").format(Duration),
         }
     }
 }
@@ -313,6 +367,7 @@ impl HasRenderConfig for model::CgroupPidsModel {
         let rc = RenderConfigBuilder::new();
         match field_id {
             TidsCurrent => rc.title("Tids Current").format(Precision(1)),
+            ExecPerSec => rc.title("Exec/s").format(Precision(1)),
         }
     }
 }
@@ -919,11 +974,13 @@ impl HasRenderConfig for model::SingleProcessModel {
             State => rc.title("State"),
             UptimeSecs => rc.title("Uptime(sec)"),
             Cgroup => rc.title("Cgroup").width(50).fold(FoldOption::Name),
+            Container(field_id) => model::ContainerModel::get_render_config_builder(field_id),
             Io(field_id) => model::ProcessIoModel::get_render_config_builder(field_id),
             Mem(field_id) => model::ProcessMemoryModel::get_render_config_builder(field_id),
             Cpu(field_id) => model::ProcessCpuModel::get_render_config_builder(field_id),
             Cmdline => rc.title("Cmdline").width(50),
             ExePath => rc.title("Exe Path"),
+            Subtree(field_id) => model::ProcessSubtreeModel::get_render_config_builder(field_id),
         }
     }
 }
@@ -978,6 +1035,8 @@ impl HasRenderConfigForDump for model::SingleProcessModel {
             UptimeSecs => Some(counter),
             // OpenMetrics does not support strings
             Cgroup => None,
+            // OpenMetrics does not support strings
+            Container(_) => None,
             Io(field_id) => match field_id {
                 RbytesPerSec => Some(gauge),
                 WbytesPerSec => Some(gauge),
@@ -1007,6 +1066,25 @@ impl HasRenderConfigForDump for model::SingleProcessModel {
             Cmdline => None,
             // OpenMetrics does not support strings
             ExePath => None,
+            Subtree(field_id) => match field_id {
+                model::ProcessSubtreeModelFieldId::NumProcs => Some(gauge),
+                model::ProcessSubtreeModelFieldId::CpuUsagePct => Some(gauge.unit("percent")),
+                model::ProcessSubtreeModelFieldId::RssBytes => Some(gauge.unit("bytes")),
+                model::ProcessSubtreeModelFieldId::RwbytesPerSec => Some(gauge),
+            },
+        }
+    }
+}
+
+impl HasRenderConfig for model::ProcessSubtreeModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::ProcessSubtreeModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            NumProcs => rc.title("Tree Procs"),
+            CpuUsagePct => rc.title("Tree CPU").format(Precision(2)).suffix("%"),
+            RssBytes => rc.title("Tree RSS").format(ReadableSize),
+            RwbytesPerSec => rc.title("Tree RW").suffix("/s").format(ReadableSize),
         }
     }
 }
@@ -1657,6 +1735,20 @@ impl HasRenderConfig for model::CgroupProperties {
     }
 }
 
+impl HasRenderConfig for model::ContainerModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::ContainerModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            Runtime => rc.title("Runtime").width(12),
+            Id => rc.title("Container Id").width(66),
+            PodUid => rc.title("Pod UID").width(38),
+            Unit => rc.title("Unit").width(40),
+            Name => rc.title("Container").width(30),
+        }
+    }
+}
+
 impl HasRenderConfig for model::SingleTcModel {
     fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
         use model::SingleTcModelFieldId::*;
@@ -1836,3 +1928,48 @@ impl HasRenderConfigForDump for model::FqCodelXStatsModel {
         }
     }
 }
+
+impl HasRenderConfig for model::CollectorHealthModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::CollectorHealthModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            CollectLatencyUsec => rc.title("Collect").format(Duration),
+            CgroupLatencyUsec => rc.title("Cgroup").format(Duration),
+            ProcfsLatencyUsec => rc.title("Procfs").format(Duration),
+            NetstatLatencyUsec => rc.title("Netstat").format(Duration),
+            EthtoolLatencyUsec => rc.title("Ethtool").format(Duration),
+            BtrfsLatencyUsec => rc.title("Btrfs").format(Duration),
+            TcLatencyUsec => rc.title("Tc").format(Duration),
+            GpuLatencyUsec => rc.title("Gpu").format(Duration),
+            Errors => rc.title("Errors"),
+            ExitstatLostEvents => rc.title("Exitstat Lost"),
+            StoreWriteLatencyUsec => rc.title("Store Write").format(Duration),
+            StaleCollectors => rc.title("Stale").width(30),
+        }
+    }
+}
+
+impl HasRenderConfigForDump for model::CollectorHealthModel {
+    fn get_openmetrics_config_for_dump(
+        &self,
+        field_id: &Self::FieldId,
+    ) -> Option<RenderOpenMetricsConfigBuilder> {
+        use model::CollectorHealthModelFieldId::*;
+        let gauge = gauge();
+        match field_id {
+            CollectLatencyUsec => Some(gauge.unit("microseconds")),
+            CgroupLatencyUsec => Some(gauge.unit("microseconds")),
+            ProcfsLatencyUsec => Some(gauge.unit("microseconds")),
+            NetstatLatencyUsec => Some(gauge.unit("microseconds")),
+            EthtoolLatencyUsec => Some(gauge.unit("microseconds")),
+            BtrfsLatencyUsec => Some(gauge.unit("microseconds")),
+            TcLatencyUsec => Some(gauge.unit("microseconds")),
+            GpuLatencyUsec => Some(gauge.unit("microseconds")),
+            Errors => Some(gauge),
+            ExitstatLostEvents => Some(gauge),
+            StoreWriteLatencyUsec => Some(gauge.unit("microseconds")),
+            StaleCollectors => None,
+        }
+    }
+}
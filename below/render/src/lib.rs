@@ -18,6 +18,7 @@ use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Write;
+use std::str::FromStr;
 
 mod default_configs;
 
@@ -58,6 +59,31 @@ pub enum RenderFormat {
     MaxOrDuration,
 }
 
+impl FromStr for RenderFormat {
+    type Err = String;
+
+    /// Parses the snake_case name of a format, e.g. `readable_size`, or
+    /// `precision:N` for N digits after the decimal point.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(precision) = s.strip_prefix("precision:") {
+            return precision
+                .parse()
+                .map(RenderFormat::Precision)
+                .map_err(|_| format!("Invalid precision in render format: {}", s));
+        }
+        match s {
+            "readable_size" => Ok(RenderFormat::ReadableSize),
+            "page_readable_size" => Ok(RenderFormat::PageReadableSize),
+            "sector_readable_size" => Ok(RenderFormat::SectorReadableSize),
+            "max_or_readable_size" => Ok(RenderFormat::MaxOrReadableSize),
+            "readable_frequency" => Ok(RenderFormat::ReadableFrequency),
+            "duration" => Ok(RenderFormat::Duration),
+            "max_or_duration" => Ok(RenderFormat::MaxOrDuration),
+            _ => Err(format!("Unknown render format: {}", s)),
+        }
+    }
+}
+
 /// Specifies how a long string is folded to fit into a shorter width.
 #[derive(Clone)]
 pub enum FoldOption {
@@ -426,6 +452,25 @@ impl RenderConfig {
     pub fn render(&self, field: Option<Field>, fixed_width: bool) -> String {
         self.render_indented(field, fixed_width, 0)
     }
+
+    /// Renders a signed change of a Field, e.g. "+1.5 GB" or "-3". The
+    /// magnitude is rendered as the same kind of Field as `like` so that the
+    /// format of the original Field still applies.
+    pub fn render_delta(&self, delta: f64, like: Option<&Field>, fixed_width: bool) -> String {
+        let magnitude = match like {
+            Some(Field::U32(_) | Field::U64(_)) => Field::U64(delta.abs().round() as u64),
+            Some(Field::I32(_) | Field::I64(_)) => Field::I64(delta.abs().round() as i64),
+            Some(Field::F32(_)) => Field::F32(delta.abs() as f32),
+            _ => Field::F64(delta.abs()),
+        };
+        let sign = if delta < 0.0 { "-" } else { "+" };
+        let rendered = format!("{}{}", sign, self.render(Some(magnitude), false));
+        if fixed_width {
+            get_fixed_width(&rendered, self.get_width())
+        } else {
+            rendered
+        }
+    }
 }
 
 /// Provide default RenderConfig for each Field in a Model
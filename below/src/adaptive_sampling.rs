@@ -0,0 +1,167 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use config::AdaptiveSamplingConfig;
+use model::Model;
+use model::ModelFieldId;
+use model::Queriable;
+use model::Sample;
+use slog::info;
+
+/// Picks the interval of `record` to the next sample. Normally that is the
+/// configured interval, but once the pressure of the root cgroup or of a
+/// top-level cgroup, or one of the configured trigger fields, crosses its
+/// threshold, samples are taken at the fast interval for a bounded window.
+/// A new window only starts once the thresholds were cleared in between, so
+/// that sustained pressure does not keep the fast interval forever.
+pub struct AdaptiveSampler {
+    logger: slog::Logger,
+    interval: Duration,
+    fast_interval: Duration,
+    window: Duration,
+    pressure_threshold_pct: f64,
+    triggers: Vec<(ModelFieldId, f64)>,
+    // Only kept when there are triggers, which need a model
+    last_sample: Option<(SystemTime, Sample)>,
+    fast_until: Option<SystemTime>,
+    crossed: bool,
+}
+
+impl AdaptiveSampler {
+    pub fn new(
+        logger: slog::Logger,
+        interval: Duration,
+        config: &AdaptiveSamplingConfig,
+    ) -> Result<Self> {
+        if config.fast_interval_s == 0 {
+            bail!("adaptive_sampling.fast_interval_s must be at least 1");
+        }
+        let triggers = config
+            .triggers
+            .iter()
+            .map(|(field_path, threshold)| {
+                ModelFieldId::from_str(field_path)
+                    .with_context(|| {
+                        format!("Invalid adaptive_sampling.triggers field {}", field_path)
+                    })
+                    .map(|field_id| (field_id, *threshold))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            logger,
+            interval,
+            fast_interval: Duration::from_secs(config.fast_interval_s),
+            window: Duration::from_secs(config.window_s),
+            pressure_threshold_pct: config.pressure_threshold_pct,
+            triggers,
+            last_sample: None,
+            fast_until: None,
+            crossed: false,
+        })
+    }
+
+    /// Name of the first pressure of the root or a top-level cgroup above the
+    /// threshold, if any.
+    fn high_pressure(&self, sample: &Sample) -> Option<String> {
+        let root = std::iter::once(("<root>", &sample.cgroup));
+        let top_level = sample
+            .cgroup
+            .children
+            .iter()
+            .flatten()
+            .map(|(name, cgroup)| (name.as_str(), cgroup));
+        for (name, cgroup) in root.chain(top_level) {
+            let pressure = match cgroup.pressure.as_ref() {
+                Some(pressure) => pressure,
+                None => continue,
+            };
+            for (resource, avg10) in [
+                ("cpu some", pressure.cpu.some.avg10),
+                ("io full", pressure.io.full.avg10),
+                ("memory full", pressure.memory.full.avg10),
+            ] {
+                if avg10.map_or(false, |avg10| avg10 > self.pressure_threshold_pct) {
+                    return Some(format!("{} pressure of {}", resource, name));
+                }
+            }
+        }
+        None
+    }
+
+    /// First trigger field above its threshold, if any.
+    fn triggered_field(&mut self, timestamp: SystemTime, sample: &Sample) -> Option<String> {
+        if self.triggers.is_empty() {
+            return None;
+        }
+        let last = self
+            .last_sample
+            .as_ref()
+            .and_then(|(last_timestamp, last)| {
+                Some((last, timestamp.duration_since(*last_timestamp).ok()?))
+            });
+        let model = Model::new(timestamp, sample, last);
+        self.last_sample = Some((timestamp, sample.clone()));
+        self.triggers
+            .iter()
+            .find(|(field_id, threshold)| {
+                model::diff::numeric(&model.query(field_id)).map_or(false, |v| v > *threshold)
+            })
+            .map(|(field_id, _)| field_id.to_string())
+    }
+
+    /// Update with the sample collected at timestamp.
+    pub fn observe(&mut self, timestamp: SystemTime, sample: &Sample) {
+        // Always evaluated so that the model rates are over a single interval
+        let triggered_field = self.triggered_field(timestamp, sample);
+        let reason = self.high_pressure(sample).or(triggered_field);
+        let crossed = reason.is_some();
+        if let (Some(reason), false) = (reason, self.crossed) {
+            if self.fast_until.map_or(true, |until| until <= timestamp) {
+                info!(
+                    self.logger,
+                    "Adaptive sampling: {} crossed its threshold, sampling every {}s for {}s",
+                    reason,
+                    self.fast_interval.as_secs(),
+                    self.window.as_secs()
+                );
+                self.fast_until = Some(timestamp + self.window);
+            }
+        }
+        self.crossed = crossed;
+    }
+
+    /// Interval from timestamp to the next sample.
+    pub fn interval(&mut self, timestamp: SystemTime) -> Duration {
+        match self.fast_until {
+            Some(until) if timestamp < until => self.fast_interval.min(self.interval),
+            Some(_) => {
+                info!(
+                    self.logger,
+                    "Adaptive sampling: window ended, sampling every {}s",
+                    self.interval.as_secs()
+                );
+                self.fast_until = None;
+                self.interval
+            }
+            None => self.interval,
+        }
+    }
+}
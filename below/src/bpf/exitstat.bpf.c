@@ -23,6 +23,8 @@
 #include <bpf/bpf_tracing.h>
 
 #define TASK_COMM_LEN 16
+#define EXEC_FILENAME_LEN 256
+#define EXEC_ARGS_LEN 256 // must be a power of 2, see the read of argv
 
 struct {
   __uint(type, BPF_MAP_TYPE_PERF_EVENT_ARRAY);
@@ -37,6 +39,7 @@ struct metadata {
   uint32_t sid; // session ID
   uint32_t cpu; // CPU task is running on
   char comm[TASK_COMM_LEN]; // process name
+  uint64_t cgroup_id; // cgroup2 id, i.e. inode number of the cgroup directory
 };
 
 struct exitstats {
@@ -56,6 +59,28 @@ struct event {
   struct exitstats stats;
 };
 
+struct exec_event {
+  pid_t pid; // process ID, exec always leaves the caller as thread group leader
+  uint64_t cgroup_id; // cgroup2 id, i.e. inode number of the cgroup directory
+  uint32_t args_len; // length of argv in args, at most EXEC_ARGS_LEN
+  char filename[EXEC_FILENAME_LEN]; // path of the executed binary
+  char args[EXEC_ARGS_LEN]; // nul separated argv, truncated
+};
+
+struct {
+  __uint(type, BPF_MAP_TYPE_PERF_EVENT_ARRAY);
+  __uint(key_size, sizeof(u32));
+  __uint(value_size, sizeof(u32));
+} exec_events SEC(".maps");
+
+// struct exec_event does not fit on the 512 byte BPF stack
+struct {
+  __uint(type, BPF_MAP_TYPE_PERCPU_ARRAY);
+  __uint(max_entries, 1);
+  __type(key, u32);
+  __type(value, struct exec_event);
+} exec_event_heap SEC(".maps");
+
 struct task_struct___pre516 {
   unsigned int cpu;
 } __attribute__((preserve_access_index));
@@ -118,6 +143,7 @@ int tracepoint__sched__sched_process_exit(
   data.meta.sid = BPF_CORE_READ(task, sessionid);
   data.meta.cpu = task_cpu(task);
   bpf_get_current_comm(&data.meta.comm, sizeof(data.meta.comm));
+  data.meta.cgroup_id = bpf_get_current_cgroup_id();
 
   /* read/calculate exitstats */
   data.stats.min_flt = BPF_CORE_READ(task, min_flt);
@@ -160,4 +186,54 @@ int tracepoint__sched__sched_process_exit(
   return 1;
 }
 
+// sched:sched_process_exec is triggered after a successful exec, when the new
+// mm is already set up. Only loaded if exec tracking is enabled. Records what
+// was executed so that processes that exit between two samples still have a
+// cmdline.
+SEC("tracepoint/sched/sched_process_exec")
+int tracepoint__sched__sched_process_exec(
+    struct trace_event_raw_sched_process_exec* args
+) {
+  u32 zero = 0;
+  struct exec_event* data = bpf_map_lookup_elem(&exec_event_heap, &zero);
+  if (!data) {
+    return 0;
+  }
+
+  struct task_struct* task = (struct task_struct*)bpf_get_current_task();
+  data->pid = bpf_get_current_pid_tgid() >> 32;
+  data->cgroup_id = bpf_get_current_cgroup_id();
+
+  // filename is a __data_loc field: the low 16 bits are its offset in args
+  unsigned int filename_off = BPF_CORE_READ(args, __data_loc_filename) & 0xFFFF;
+  bpf_probe_read_kernel_str(
+      data->filename, sizeof(data->filename), (void*)args + filename_off);
+
+  // argv lives on the user stack of the new image. Only read up to its end,
+  // it may be close to the end of the stack mapping.
+  u64 arg_start = BPF_CORE_READ(task, mm, arg_start);
+  u64 arg_end = BPF_CORE_READ(task, mm, arg_end);
+  u64 args_len = arg_end - arg_start;
+  u32 read_len = args_len < EXEC_ARGS_LEN ? args_len : EXEC_ARGS_LEN;
+  data->args_len = 0;
+  if (read_len > 0) {
+    // Keeps read_len within 1..EXEC_ARGS_LEN for the verifier
+    read_len = ((read_len - 1) & (EXEC_ARGS_LEN - 1)) + 1;
+    if (!bpf_probe_read_user(data->args, read_len, (void*)arg_start)) {
+      data->args_len = read_len;
+    } else {
+      // Keep at least argv[0] if the rest cannot be read
+      long len = bpf_probe_read_user_str(
+          data->args, sizeof(data->args), (void*)arg_start);
+      if (len > 0) {
+        data->args_len = len;
+      }
+    }
+  }
+
+  bpf_perf_event_output(
+      args, &exec_events, BPF_F_CURRENT_CPU, data, sizeof(struct exec_event));
+  return 1;
+}
+
 char _license[] SEC("license") = "GPL";
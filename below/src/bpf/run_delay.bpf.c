@@ -0,0 +1,141 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#ifdef FBCODE_BUILD
+#include <bpf/vmlinux/vmlinux.h>
+#else
+#include "../open_source/vmlinux/vmlinux.h"
+#endif // FBCODE_BUILD
+
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_tracing.h>
+
+#define NR_BUCKETS 27 // must match run_delay::NR_BUCKETS
+#define MAX_ENTRIES 10240
+#define MAX_CGROUPS 16384 // must match run_delay::MAX_CGROUPS
+#define TASK_RUNNING 0
+
+struct hist {
+  u64 buckets[NR_BUCKETS];
+};
+
+// Time a task became runnable, by pid
+struct {
+  __uint(type, BPF_MAP_TYPE_HASH);
+  __uint(max_entries, MAX_ENTRIES);
+  __type(key, u32);
+  __type(value, u64);
+} start SEC(".maps");
+
+// Cumulative log2 histogram of run queue latencies in usec, by cgroup id
+struct {
+  __uint(type, BPF_MAP_TYPE_HASH);
+  __uint(max_entries, MAX_CGROUPS);
+  __type(key, u64);
+  __type(value, struct hist);
+} hists SEC(".maps");
+
+struct task_struct___pre514 {
+  volatile long state;
+} __attribute__((preserve_access_index));
+
+struct task_struct___post514 {
+  unsigned int __state;
+} __attribute__((preserve_access_index));
+
+static __always_inline long task_state(void* arg) {
+  if (bpf_core_field_exists(struct task_struct___post514, __state)) {
+    struct task_struct___post514* task = arg;
+    return BPF_CORE_READ(task, __state);
+  } else {
+    struct task_struct___pre514* task = arg;
+    return BPF_CORE_READ(task, state);
+  }
+}
+
+static __always_inline u32 log2_u64(u64 v) {
+  u32 r = 0;
+  // Unrolled binary search, loops over v upset older verifiers
+  if (v >> 32) { v >>= 32; r += 32; }
+  if (v >> 16) { v >>= 16; r += 16; }
+  if (v >> 8) { v >>= 8; r += 8; }
+  if (v >> 4) { v >>= 4; r += 4; }
+  if (v >> 2) { v >>= 2; r += 2; }
+  if (v >> 1) { r += 1; }
+  return r;
+}
+
+static __always_inline int trace_enqueue(u32 pid) {
+  if (!pid) {
+    return 0;
+  }
+  u64 ts = bpf_ktime_get_ns();
+  bpf_map_update_elem(&start, &pid, &ts, BPF_ANY);
+  return 0;
+}
+
+SEC("tp_btf/sched_wakeup")
+int BPF_PROG(sched_wakeup, struct task_struct* p) {
+  return trace_enqueue(BPF_CORE_READ(p, pid));
+}
+
+SEC("tp_btf/sched_wakeup_new")
+int BPF_PROG(sched_wakeup_new, struct task_struct* p) {
+  return trace_enqueue(BPF_CORE_READ(p, pid));
+}
+
+// The run queue latency of next ends when it is switched in. A preempted prev
+// is still runnable, so its latency starts now.
+SEC("tp_btf/sched_switch")
+int BPF_PROG(
+    sched_switch,
+    bool preempt,
+    struct task_struct* prev,
+    struct task_struct* next) {
+  if (task_state(prev) == TASK_RUNNING) {
+    trace_enqueue(BPF_CORE_READ(prev, pid));
+  }
+
+  u32 pid = BPF_CORE_READ(next, pid);
+  u64* tsp = bpf_map_lookup_elem(&start, &pid);
+  if (!tsp) {
+    return 0;
+  }
+  s64 delta_us = (s64)(bpf_ktime_get_ns() - *tsp) / 1000;
+  bpf_map_delete_elem(&start, &pid);
+  if (delta_us < 0) {
+    return 0;
+  }
+
+  // Current is still prev, so take the cgroup of next from its css_set
+  u64 cgroup_id = BPF_CORE_READ(next, cgroups, dfl_cgrp, kn, id);
+  struct hist* hist = bpf_map_lookup_elem(&hists, &cgroup_id);
+  if (!hist) {
+    struct hist zero = {};
+    bpf_map_update_elem(&hists, &cgroup_id, &zero, BPF_NOEXIST);
+    hist = bpf_map_lookup_elem(&hists, &cgroup_id);
+    if (!hist) {
+      return 0;
+    }
+  }
+  u32 bucket = log2_u64(delta_us);
+  if (bucket >= NR_BUCKETS) {
+    bucket = NR_BUCKETS - 1;
+  }
+  __sync_fetch_and_add(&hist->buckets[bucket], 1);
+  return 0;
+}
+
+char _license[] SEC("license") = "GPL";
@@ -0,0 +1,220 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `below config check` and `below config show`. Unlike the other commands
+//! they run before below.conf is loaded, so that a broken one can be checked.
+
+use std::path::Path;
+use std::time::Duration;
+
+use common::util::get_belowrc_cmd_section_key;
+use common::util::get_belowrc_dump_section_key;
+use common::util::get_belowrc_view_section_key;
+use common::util::ConfigIssue;
+use config::BelowConfig;
+use regex::Regex;
+
+use crate::adaptive_sampling::AdaptiveSampler;
+
+/// Check below.conf and belowrc, printing their issues. Returns the exit code,
+/// which is 1 if there are errors.
+pub fn check(config_path: &Path, belowrc_path: &Path) -> i32 {
+    let mut nr_errors = 0;
+    match std::fs::read_to_string(config_path) {
+        Ok(source) => nr_errors += report(config_path, &source, &check_below_config(&source)),
+        Err(e)
+            if e.kind() == std::io::ErrorKind::NotFound
+                && config_path == Path::new(config::BELOW_DEFAULT_CONF) =>
+        {
+            println!("{}: not found, using defaults", config_path.display());
+        }
+        Err(e) => {
+            println!("{}: error: {}", config_path.display(), e);
+            nr_errors += 1;
+        }
+    }
+    match std::fs::read_to_string(belowrc_path) {
+        Ok(source) => nr_errors += report(belowrc_path, &source, &check_belowrc(&source)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            println!("{}: not found, using defaults", belowrc_path.display());
+        }
+        Err(e) => {
+            println!("{}: error: {}", belowrc_path.display(), e);
+            nr_errors += 1;
+        }
+    }
+    if nr_errors > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Print below.conf as it is used, with defaults filled in.
+pub fn show(config_path: &Path) -> i32 {
+    let mut below_config = match BelowConfig::load(config_path) {
+        Ok(below_config) => below_config,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            return 1;
+        }
+    };
+    // Defaults of record without its flags
+    let collectors = &mut below_config.collectors;
+    for (collector, default) in [
+        (&mut collectors.io_stat, false),
+        (&mut collectors.disk_stat, true),
+        (&mut collectors.slabinfo, true),
+        (&mut collectors.netstat, true),
+        (&mut collectors.btrfs, below_config.enable_btrfs_stats),
+        (&mut collectors.ethtool, below_config.enable_ethtool_stats),
+        (&mut collectors.ksm, below_config.enable_ksm_stats),
+        (&mut collectors.resctrl, below_config.enable_resctrl_stats),
+        (&mut collectors.tc, below_config.enable_tc_stats),
+        (&mut collectors.gpu, below_config.enable_gpu_stats),
+        (&mut collectors.exitstats, true),
+    ] {
+        collector.enabled.get_or_insert(default);
+    }
+    match toml::to_string(&below_config) {
+        Ok(below_config_str) => {
+            print!("{}", below_config_str);
+            0
+        }
+        Err(e) => {
+            eprintln!("Failed to serialize below.conf: {}", e);
+            1
+        }
+    }
+}
+
+pub fn check_below_config(source: &str) -> Vec<ConfigIssue> {
+    let (below_config, unknown_keys) = match BelowConfig::parse_checked(source) {
+        Ok(parsed) => parsed,
+        Err(e) => return vec![ConfigIssue::error("", format!("{:#}", e))],
+    };
+    let mut issues: Vec<ConfigIssue> = unknown_keys
+        .into_iter()
+        .map(|key| ConfigIssue::warning(key, "Unknown key, ignored"))
+        .collect();
+    if !below_config.cgroup_filter_out.is_empty() {
+        if let Err(e) = Regex::new(&below_config.cgroup_filter_out) {
+            issues.push(ConfigIssue::error("cgroup_filter_out", e.to_string()));
+        }
+    }
+    if let Some(path) = below_config.container_names_file.as_ref() {
+        if let Err(e) = model::MappingFile::load(path) {
+            issues.push(ConfigIssue::error(
+                "container_names_file",
+                format!("{:#}", e),
+            ));
+        }
+    }
+    for rule in &below_config.redaction.cmdline_rules {
+        if let Err(e) = Regex::new(&rule.pattern) {
+            issues.push(ConfigIssue::error("redaction.cmdline_rules", e.to_string()));
+        }
+    }
+    if let Some(adaptive_sampling) = below_config.adaptive_sampling.as_ref() {
+        let logger = slog::Logger::root(slog::Discard, slog::o!());
+        // The interval only matters once sampling
+        if let Err(e) = AdaptiveSampler::new(logger, Duration::from_secs(5), adaptive_sampling) {
+            issues.push(ConfigIssue::error("adaptive_sampling", format!("{:#}", e)));
+        }
+    }
+    issues
+}
+
+pub fn check_belowrc(source: &str) -> Vec<ConfigIssue> {
+    let belowrc = match source.parse::<toml::Table>() {
+        Ok(belowrc) => belowrc,
+        Err(e) => return vec![ConfigIssue::error("", e.to_string())],
+    };
+    let mut issues = Vec::new();
+    for (section, value) in &belowrc {
+        if section == get_belowrc_view_section_key() {
+            issues.extend(view::viewrc::ViewRc::check(value));
+        } else if section == get_belowrc_cmd_section_key() {
+            issues.extend(view::controllers::check_cmdrc(value));
+        } else if section == get_belowrc_dump_section_key() {
+            issues.extend(dump::check_patterns(value));
+        } else {
+            issues.push(ConfigIssue::warning(section, "Unknown section, ignored"));
+        }
+    }
+    issues
+}
+
+/// Print issues found in the file at path, returning the number of errors.
+fn report(path: &Path, source: &str, issues: &[ConfigIssue]) -> usize {
+    if issues.is_empty() {
+        println!("{}: OK", path.display());
+    }
+    for issue in issues {
+        let location = match find_key_line(source, &issue.key) {
+            Some(line) => format!("{}:{}", path.display(), line),
+            None => path.display().to_string(),
+        };
+        let key = if issue.key.is_empty() {
+            String::new()
+        } else {
+            format!("{}: ", issue.key)
+        };
+        let severity = if issue.is_error { "error" } else { "warning" };
+        println!("{}: {}: {}{}", location, severity, key, issue.message);
+    }
+    issues.iter().filter(|issue| issue.is_error).count()
+}
+
+/// Best effort line number of a dotted key in a toml file, falling back to
+/// the line of the closest enclosing table.
+pub fn find_key_line(source: &str, key: &str) -> Option<usize> {
+    if key.is_empty() {
+        return None;
+    }
+    let mut table = String::new();
+    let mut table_line = None;
+    for (idx, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let full_key = if let Some(header) = line.strip_prefix('[') {
+            table = header
+                .trim_start_matches('[')
+                .split(']')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_owned();
+            table.clone()
+        } else if let Some((line_key, _)) = line.split_once('=') {
+            let line_key = line_key.trim().trim_matches(|c| c == '"' || c == '\'');
+            if table.is_empty() {
+                line_key.to_owned()
+            } else {
+                format!("{}.{}", table, line_key)
+            }
+        } else {
+            continue;
+        };
+        if full_key == key {
+            return Some(idx + 1);
+        }
+        if key.starts_with(&format!("{}.", full_key)) {
+            table_line = Some(idx + 1);
+        }
+    }
+    table_line
+}
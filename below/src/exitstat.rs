@@ -13,7 +13,11 @@
 // limitations under the License.
 
 use core::time::Duration;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::ffi::CStr;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -30,6 +34,10 @@ use crate::ExitstatSkelBuilder;
 
 static PAGE_SIZE: Lazy<u64> = Lazy::new(page_size);
 
+/// Upper bound of exec records waiting for the exit of their process. Exits
+/// can be lost, so the oldest records are dropped once this is reached.
+pub(crate) const MAX_PENDING_EXECS: usize = 65536;
+
 #[repr(C)]
 #[derive(Default)]
 pub struct Metadata {
@@ -39,6 +47,7 @@ pub struct Metadata {
     pub sid: i32,
     pub cpu: i32,
     pub comm: [u8; 16],
+    pub cgroup_id: u64,
 }
 
 // See bpf prog for comments on what each field is
@@ -65,6 +74,99 @@ pub struct Event {
 
 unsafe impl Plain for Event {}
 
+// See bpf prog for comments on what each field is
+#[repr(C)]
+pub struct ExecEvent {
+    pub pid: i32,
+    pub cgroup_id: u64,
+    pub args_len: u32,
+    pub filename: [u8; 256],
+    pub args: [u8; 256],
+}
+
+impl Default for ExecEvent {
+    fn default() -> Self {
+        Self {
+            pid: 0,
+            cgroup_id: 0,
+            args_len: 0,
+            filename: [0; 256],
+            args: [0; 256],
+        }
+    }
+}
+
+unsafe impl Plain for ExecEvent {}
+
+/// What a process executed, kept until the process exits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecInfo {
+    pub cmdline_vec: Vec<String>,
+    pub exe_path: String,
+}
+
+impl ExecInfo {
+    fn new(event: &ExecEvent) -> Self {
+        let filename = event.filename.split(|b| *b == 0).next().unwrap_or_default();
+        let args_len = (event.args_len as usize).min(event.args.len());
+        // argv is nul separated and nul terminated, unless it was truncated
+        let args = &event.args[..args_len];
+        let args = args.strip_suffix(&[0]).unwrap_or(args);
+        Self {
+            cmdline_vec: if args.is_empty() {
+                Vec::new()
+            } else {
+                args.split(|b| *b == 0)
+                    .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                    .collect()
+            },
+            exe_path: String::from_utf8_lossy(filename).into_owned(),
+        }
+    }
+}
+
+/// Exec records by pid, waiting for the exit of the process. Holds at most
+/// MAX_PENDING_EXECS records, evicting the oldest first.
+#[derive(Default)]
+pub(crate) struct PendingExecs {
+    /// Pid to the sequence number of its record and the record
+    execs: HashMap<i32, (u64, ExecInfo)>,
+    /// Sequence number to pid, oldest first
+    order: BTreeMap<u64, i32>,
+    next_seq: u64,
+}
+
+impl PendingExecs {
+    pub(crate) fn insert(&mut self, pid: i32, exec: ExecInfo) {
+        self.remove(pid);
+        while self.execs.len() >= MAX_PENDING_EXECS {
+            match self.order.pop_first() {
+                Some((_, oldest)) => self.execs.remove(&oldest),
+                None => break,
+            };
+        }
+        self.execs.insert(pid, (self.next_seq, exec));
+        self.order.insert(self.next_seq, pid);
+        self.next_seq += 1;
+    }
+
+    pub(crate) fn remove(&mut self, pid: i32) -> Option<ExecInfo> {
+        let (seq, exec) = self.execs.remove(&pid)?;
+        self.order.remove(&seq);
+        Some(exec)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.execs.len()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.execs.is_empty()
+    }
+}
+
 fn page_size() -> u64 {
     match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
         -1 => panic!("Failed to query page size"),
@@ -76,14 +178,30 @@ pub struct ExitstatDriver {
     logger: slog::Logger,
     debug: bool,
     buffer: Arc<Mutex<procfs::PidMap>>,
+    cgroup_id_map: Arc<Mutex<model::CgroupIdMap>>,
+    /// Exec records by pid, waiting for the exit of the process
+    execs: Arc<Mutex<PendingExecs>>,
+    /// Some if exec tracking is enabled
+    exec_counter: Option<Arc<Mutex<model::ExecCounter>>>,
+    /// Events lost since the collector last took the count
+    lost_events: Arc<AtomicU64>,
 }
 
 impl ExitstatDriver {
-    pub fn new(logger: slog::Logger, debug: bool) -> Self {
+    pub fn new(
+        logger: slog::Logger,
+        debug: bool,
+        cgroup_id_map: Arc<Mutex<model::CgroupIdMap>>,
+        exec_counter: Option<Arc<Mutex<model::ExecCounter>>>,
+    ) -> Self {
         Self {
             logger,
             debug,
             buffer: Arc::new(Mutex::new(procfs::PidMap::default())),
+            cgroup_id_map,
+            execs: Default::default(),
+            exec_counter,
+            lost_events: Default::default(),
         }
     }
 
@@ -91,7 +209,16 @@ impl ExitstatDriver {
         self.buffer.clone()
     }
 
-    fn handle_event(handle: &Arc<Mutex<procfs::PidMap>>, data: &[u8]) {
+    pub fn get_lost_events(&self) -> Arc<AtomicU64> {
+        self.lost_events.clone()
+    }
+
+    pub(crate) fn handle_event(
+        handle: &Arc<Mutex<procfs::PidMap>>,
+        cgroup_id_map: &Arc<Mutex<model::CgroupIdMap>>,
+        execs: &Arc<Mutex<PendingExecs>>,
+        data: &[u8],
+    ) {
         let mut event = Event::default();
         plain::copy_from_bytes(&mut event, data).expect("Data buffer was too short");
 
@@ -106,6 +233,10 @@ impl ExitstatDriver {
         }
         comm_no_interior_nul.push(0);
 
+        // Exec records are keyed by pid, so only the exit of the thread group
+        // leader picks up the record.
+        let exec = execs.lock().unwrap().remove(event.meta.tid);
+
         let pidinfo = procfs::PidInfo {
             stat: procfs::PidStat {
                 pid: Some(event.meta.tid), // event.meta.pid is actually tgid
@@ -130,12 +261,20 @@ impl ExitstatDriver {
                 rbytes: Some(event.stats.io_read_bytes),
                 wbytes: Some(event.stats.io_write_bytes),
             },
-            // It seems to be somewhat tricky to get a cgroup name using bpf. It might be possible
-            // with the bpf_get_current_cgroup_id() helper, but that returns what looks like an
-            // inode number. I'm not sure if it's easy/possible to translate an inode # to a path.
-            cgroup: "?".to_string(),
+            // The cgroup id is the inode number of the cgroup directory, which the collector
+            // maps to a path on every cgroup walk. Cgroups created since the last walk are
+            // unknown.
+            cgroup: cgroup_id_map
+                .lock()
+                .unwrap()
+                .resolve(event.meta.cgroup_id)
+                .unwrap_or("?")
+                .to_string(),
             // We can't access cmdline b/c it requires taking mmap_sem and a
-            // bunch of memory management helpers.
+            // bunch of memory management helpers. Take it from the exec record
+            // instead if exec tracking is enabled.
+            cmdline_vec: exec.as_ref().map(|exec| exec.cmdline_vec.clone()),
+            exe_path: exec.map(|exec| exec.exe_path),
             ..Default::default()
         };
 
@@ -144,31 +283,109 @@ impl ExitstatDriver {
         handle.lock().unwrap().insert(event.meta.tid, pidinfo);
     }
 
-    fn handle_lost_events(logger: &slog::Logger, cpu: i32, count: u64) {
+    pub(crate) fn handle_exec_event(
+        handle: &Arc<Mutex<procfs::PidMap>>,
+        cgroup_id_map: &Arc<Mutex<model::CgroupIdMap>>,
+        execs: &Arc<Mutex<PendingExecs>>,
+        exec_counter: &Arc<Mutex<model::ExecCounter>>,
+        data: &[u8],
+    ) {
+        let mut event = ExecEvent::default();
+        plain::copy_from_bytes(&mut event, data).expect("Data buffer was too short");
+
+        if let Some(path) = cgroup_id_map.lock().unwrap().resolve(event.cgroup_id) {
+            exec_counter.lock().unwrap().record(path);
+        }
+
+        let exec = ExecInfo::new(&event);
+        // Exec and exit events are read from different perf buffers, so the
+        // exit may have been handled first.
+        if let Some(pidinfo) = handle.lock().unwrap().get_mut(&event.pid) {
+            if pidinfo.cmdline_vec.is_none() {
+                pidinfo.cmdline_vec = Some(exec.cmdline_vec);
+                pidinfo.exe_path = Some(exec.exe_path);
+                return;
+            }
+        }
+
+        execs.lock().unwrap().insert(event.pid, exec);
+    }
+
+    fn handle_lost_events(
+        logger: &slog::Logger,
+        lost_events: &Arc<AtomicU64>,
+        cpu: i32,
+        count: u64,
+    ) {
         warn!(logger, "Lost {} events on CPU {}", count, cpu);
+        lost_events.fetch_add(count, Ordering::Relaxed);
     }
 
     /// Loops forever unless an error is hit
     pub fn drive(&mut self) -> Result<()> {
         let mut skel_builder = ExitstatSkelBuilder::default();
         skel_builder.obj_builder.debug(self.debug);
-        let mut skel = skel_builder
-            .open()
-            .context("Failed to open BPF program")?
-            .load()
-            .context("Failed to load BPF program")?;
+        let mut open_skel = skel_builder.open().context("Failed to open BPF program")?;
+        if self.exec_counter.is_none() {
+            open_skel
+                .progs_mut()
+                .tracepoint__sched__sched_process_exec()
+                .set_autoload(false)
+                .context("Failed to disable exec tracking")?;
+        }
+        let mut skel = open_skel.load().context("Failed to load BPF program")?;
         skel.attach().context("Failed to attach BPF program?")?;
 
         // Set up perf ring buffer
         let buffer = self.get_buffer();
+        let cgroup_id_map = self.cgroup_id_map.clone();
+        let execs = self.execs.clone();
         let logger_clone = self.logger.clone();
+        let lost_events = self.get_lost_events();
         let perf = libbpf_rs::PerfBufferBuilder::new(skel.maps().events())
-            .sample_cb(move |_, data: &[u8]| Self::handle_event(&buffer, data))
-            .lost_cb(move |cpu, count| Self::handle_lost_events(&logger_clone, cpu, count))
+            .sample_cb(move |_, data: &[u8]| {
+                Self::handle_event(&buffer, &cgroup_id_map, &execs, data)
+            })
+            .lost_cb(move |cpu, count| {
+                Self::handle_lost_events(&logger_clone, &lost_events, cpu, count)
+            })
             .build()?;
 
-        // Poll events
+        let exec_perf = match self.exec_counter.clone() {
+            Some(exec_counter) => {
+                let buffer = self.get_buffer();
+                let cgroup_id_map = self.cgroup_id_map.clone();
+                let execs = self.execs.clone();
+                let logger_clone = self.logger.clone();
+                let lost_events = self.get_lost_events();
+                Some(
+                    libbpf_rs::PerfBufferBuilder::new(skel.maps().exec_events())
+                        .sample_cb(move |_, data: &[u8]| {
+                            Self::handle_exec_event(
+                                &buffer,
+                                &cgroup_id_map,
+                                &execs,
+                                &exec_counter,
+                                data,
+                            )
+                        })
+                        .lost_cb(move |cpu, count| {
+                            Self::handle_lost_events(&logger_clone, &lost_events, cpu, count)
+                        })
+                        .build()?,
+                )
+            }
+            None => None,
+        };
+
+        // Poll events. Execs first so that they are usually cached before the
+        // exit of the process is handled.
         loop {
+            if let Some(exec_perf) = exec_perf.as_ref() {
+                exec_perf
+                    .poll(Duration::ZERO)
+                    .context("Error polling exec perf buffer")?;
+            }
             perf.poll(Duration::from_millis(100))
                 .context("Error polling perf buffer")?;
         }
@@ -20,9 +20,13 @@ use std::cell::RefCell;
 use std::fs;
 use std::io;
 use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
 use std::path::PathBuf;
 use std::process::exit;
 use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::mpsc::channel;
 use std::sync::mpsc::Receiver;
 use std::sync::mpsc::RecvTimeoutError;
@@ -49,15 +53,19 @@ use regex::Regex;
 use signal_hook::iterator::Signals;
 use slog::debug;
 use slog::error;
+use slog::info;
 use slog::warn;
 use tar::Archive;
-use tar::Builder as TarBuilder;
 use tempfile::TempDir;
 use tokio::runtime::Builder as TB;
 use uzers::get_current_uid;
 use uzers::get_user_by_uid;
 
+mod adaptive_sampling;
+mod config_check;
 mod exitstat;
+mod run_delay;
+mod snapshot;
 #[cfg(test)]
 mod test;
 
@@ -65,6 +73,9 @@ use common::cliutil;
 use common::logutil;
 use common::open_source_shim;
 use config::BelowConfig;
+use dump::diff::DiffCommand;
+use dump::diff::DiffOpts;
+use dump::query::QueryOpts;
 use dump::DumpCommand;
 use store::advance::new_advance_local;
 use store::advance::new_advance_remote;
@@ -163,7 +174,7 @@ enum Command {
         #[clap(long, requires("host"))]
         port: Option<u16>,
     },
-    /// Record local system data (daemon mode)
+    /// Record local system data (daemon mode). SIGHUP reloads below.conf.
     Record {
         #[clap(short, long, default_value = "5")]
         interval_s: u64,
@@ -254,11 +265,45 @@ enum Command {
         #[clap(subcommand)]
         cmd: DumpCommand,
     },
-    /// Create a historical snapshot file for a given time range
+    #[clap(about = dump::diff::DIFF_ABOUT, long_about = dump::diff::DIFF_LONG_ABOUT.as_str())]
+    Diff {
+        /// Supply hostname to compare samples of a remote host
+        #[clap(short = 's', long)]
+        host: Option<String>,
+        /// Override default port to connect to the remote host
+        #[clap(long, requires("host"))]
+        port: Option<u16>,
+        /// Compare samples from a snapshot file generated by the snapshot
+        /// command instead of from the store directory.
+        #[clap(long, conflicts_with("host"))]
+        snapshot: Option<String>,
+        #[clap(flatten)]
+        opts: DiffOpts,
+        #[clap(subcommand)]
+        cmd: DiffCommand,
+    },
+    #[clap(about = dump::query::QUERY_ABOUT, long_about = dump::query::QUERY_LONG_ABOUT.as_str())]
+    Query {
+        /// Supply hostname to query a remote host
+        #[clap(short = 's', long)]
+        host: Option<String>,
+        /// Override default port to connect to the remote host
+        #[clap(long, requires("host"))]
+        port: Option<u16>,
+        /// Query a snapshot file generated by the snapshot command
+        /// instead of the store directory.
+        #[clap(long, conflicts_with("host"))]
+        snapshot: Option<String>,
+        #[clap(flatten)]
+        opts: QueryOpts,
+    },
+    /// Create a historical snapshot file for a given time range, or inspect
+    /// and merge snapshot files
+    #[clap(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
     Snapshot {
         /// Begin time, same format as replay
-        #[clap(short, long, verbatim_doc_comment)]
-        begin: String,
+        #[clap(short, long, verbatim_doc_comment, required = true)]
+        begin: Option<String>,
         /// End time, same format as replay
         #[clap(short, long, verbatim_doc_comment, group = "time")]
         end: Option<String>,
@@ -278,6 +323,26 @@ enum Command {
         /// Override default port to connect to remote
         #[clap(long, requires("host"))]
         port: Option<u16>,
+        /// Apply the [redaction] rules of below.conf to the cmdlines of the
+        /// snapshot, e.g. for data recorded before the rules were added
+        #[clap(long)]
+        redact: bool,
+        /// Only keep the cgroups whose full path, e.g.
+        /// /system.slice/foo.service, matches this regex, along with their
+        /// ancestors and descendants and the processes in them. btrfs,
+        /// resctrl and tc stats cannot be filtered by cgroup and are dropped.
+        #[clap(long)]
+        cgroup_filter: Option<String>,
+        /// Comma separated parts of the samples to leave out of the snapshot
+        #[clap(long, value_enum, value_delimiter = ',')]
+        drop: Vec<snapshot::SnapshotPart>,
+        #[clap(subcommand)]
+        cmd: Option<SnapshotCommand>,
+    },
+    /// Validate the configuration or print the effective one
+    Config {
+        #[clap(subcommand)]
+        cmd: ConfigCommand,
     },
     /// Generate a shell completions file
     #[clap(hide = true)]
@@ -324,6 +389,38 @@ enum DebugCommand {
     },
 }
 
+#[derive(Debug, Parser)]
+enum SnapshotCommand {
+    /// Print the manifest of a snapshot file
+    Info {
+        #[clap(value_parser)]
+        snapshot: PathBuf,
+    },
+    /// Merge snapshot files of the same host, which may overlap, into one
+    Merge {
+        #[clap(value_parser, required = true, num_args = 2..)]
+        snapshots: Vec<PathBuf>,
+        /// Output file path
+        #[clap(short, long, value_parser)]
+        output: PathBuf,
+    },
+}
+
+#[derive(Debug, Parser)]
+enum ConfigCommand {
+    /// Validate below.conf and belowrc, exiting with 1 on errors
+    Check {
+        /// below.conf to check instead of --config
+        #[clap(value_parser)]
+        path: Option<PathBuf>,
+        /// belowrc to check instead of $HOME/.config/below/belowrc
+        #[clap(long, value_parser)]
+        belowrc: Option<PathBuf>,
+    },
+    /// Print below.conf as used, with defaults filled in
+    Show,
+}
+
 // Whether or not to start a service to respond to network request
 // (e.g. for stats collection or otherwise)
 pub enum Service {
@@ -398,9 +495,16 @@ fn create_log_dir(path: &PathBuf) -> Result<()> {
 fn start_exitstat(
     logger: slog::Logger,
     debug: bool,
-) -> (Arc<Mutex<procfs::PidMap>>, Option<Receiver<Error>>) {
-    let mut exit_driver = exitstat::ExitstatDriver::new(logger, debug);
+    cgroup_id_map: Arc<Mutex<model::CgroupIdMap>>,
+    exec_counter: Option<Arc<Mutex<model::ExecCounter>>>,
+) -> (
+    Arc<Mutex<procfs::PidMap>>,
+    Option<Arc<AtomicU64>>,
+    Option<Receiver<Error>>,
+) {
+    let mut exit_driver = exitstat::ExitstatDriver::new(logger, debug, cgroup_id_map, exec_counter);
     let exit_buffer = exit_driver.get_buffer();
+    let lost_events = exit_driver.get_lost_events();
     let (bpf_err_send, bpf_err_recv) = channel();
     thread::Builder::new()
         .name("exit_driver".to_owned())
@@ -412,7 +516,7 @@ fn start_exitstat(
         })
         .expect("Failed to spawn thread");
 
-    (exit_buffer, Some(bpf_err_recv))
+    (exit_buffer, Some(lost_events), Some(bpf_err_recv))
 }
 
 pub fn start_gpu_stats_thread_and_get_stats_receiver(
@@ -541,6 +645,274 @@ fn start_tc_stats_thread_and_get_stats_receiver(
     Ok(receiver)
 }
 
+// Run delay stats come from a bpf prog that is loaded on the collector thread,
+// which owns the skeleton. Returns an error if the prog could not be loaded,
+// e.g. because BPF is not permitted.
+fn start_run_delay_thread_and_get_stats_receiver(
+    logger: slog::Logger,
+    interval: Duration,
+    debug: bool,
+    cgroup_id_map: Arc<Mutex<model::CgroupIdMap>>,
+) -> Result<model::collector_plugin::Consumer<model::run_delay::SampleType>> {
+    let (init_send, init_recv) = channel();
+    let target_interval = interval;
+    thread::Builder::new()
+        .name("run_delay_collector".to_owned())
+        .spawn(move || {
+            let skel = match run_delay::load(debug) {
+                Ok(skel) => skel,
+                Err(e) => {
+                    init_send.send(Err(e)).unwrap();
+                    return;
+                }
+            };
+            let run_delay_collector =
+                match run_delay::RunDelayCollectorPlugin::new(logger.clone(), &skel, cgroup_id_map)
+                {
+                    Ok(run_delay_collector) => run_delay_collector,
+                    Err(e) => {
+                        init_send.send(Err(e)).unwrap();
+                        return;
+                    }
+                };
+            let (mut collector, receiver) =
+                model::collector_plugin::collector_consumer(run_delay_collector);
+            init_send.send(Ok(receiver)).unwrap();
+
+            // Exponential backoff on unrecoverable errors
+            const EXP_BACKOFF_FACTOR: u32 = 2;
+            const MAX_BACKOFF_SECS: u64 = 900;
+            let max_backoff = Duration::from_secs(MAX_BACKOFF_SECS);
+            let mut interval = target_interval;
+            loop {
+                let collect_instant = Instant::now();
+                let rt = TB::new_current_thread()
+                    .thread_name("run_delay")
+                    .build()
+                    .expect("Failed to build tokio runtime.");
+                match rt.block_on(collector.collect_and_update()) {
+                    Ok(_) => {
+                        interval = target_interval;
+                    }
+                    Err(e) => {
+                        interval = std::cmp::min(
+                            interval.saturating_mul(EXP_BACKOFF_FACTOR),
+                            max_backoff,
+                        );
+                        error!(
+                            logger,
+                            "Run delay collection backing off {:?} because of unrecoverable error: {:?}",
+                            interval,
+                            e
+                        );
+                    }
+                }
+                let collect_duration = Instant::now().duration_since(collect_instant);
+                if interval > collect_duration {
+                    std::thread::sleep(interval - collect_duration);
+                }
+            }
+        })
+        .expect("Failed to spawn thread");
+
+    init_recv
+        .recv()
+        .context("Run delay collector exited during initialization")?
+        .context("Failed to initialize run delay collector")
+}
+
+/// Starts the run delay collector if enabled in below.conf. Failing to load
+/// its bpf prog only disables the run delay fields.
+fn start_run_delay_stats(
+    below_config: &BelowConfig,
+    logger: &slog::Logger,
+    interval: Duration,
+    debug: bool,
+    cgroup_id_map: Arc<Mutex<model::CgroupIdMap>>,
+) -> Option<model::collector_plugin::Consumer<model::run_delay::SampleType>> {
+    if !below_config.enable_run_delay_stats {
+        return None;
+    }
+    match start_run_delay_thread_and_get_stats_receiver(
+        logger.clone(),
+        interval,
+        debug,
+        cgroup_id_map,
+    ) {
+        Ok(receiver) => Some(receiver),
+        Err(e) => {
+            warn!(logger, "{:#}. Run delay stats will not be collected.", e);
+            None
+        }
+    }
+}
+
+/// Loads the container names file from below.conf, if any
+fn load_container_name_lookup(
+    below_config: &BelowConfig,
+) -> Result<Option<Arc<dyn model::ContainerNameLookup>>> {
+    match below_config.container_names_file.as_ref() {
+        Some(path) => Ok(Some(Arc::new(model::MappingFile::load(path)?))),
+        None => Ok(None),
+    }
+}
+
+/// Loads the [redaction] rules from below.conf, if any
+fn load_redactor(below_config: &BelowConfig) -> Result<Option<model::Redactor>> {
+    let redaction = &below_config.redaction;
+    if redaction.is_empty() {
+        return Ok(None);
+    }
+    let redactor = model::Redactor::new(
+        redaction
+            .cmdline_rules
+            .iter()
+            .map(|rule| (rule.pattern.as_str(), rule.replacement.as_str())),
+        redaction.drop_cmdline.iter().map(String::as_str),
+    )
+    .context("Failed to load [redaction] of below.conf")?;
+    Ok(Some(redactor))
+}
+
+/// Interval multiples of the [collectors.<name>] sections of below.conf
+fn interval_multiples(below_config: &BelowConfig) -> model::IntervalMultiples {
+    let collectors = &below_config.collectors;
+    model::IntervalMultiples {
+        io_stat: collectors.io_stat.interval_multiple,
+        disk_stat: collectors.disk_stat.interval_multiple,
+        slabinfo: collectors.slabinfo.interval_multiple,
+        netstat: collectors.netstat.interval_multiple,
+        btrfs: collectors.btrfs.interval_multiple,
+        ethtool: collectors.ethtool.interval_multiple,
+        ksm: collectors.ksm.interval_multiple,
+        resctrl: collectors.resctrl.interval_multiple,
+    }
+}
+
+/// Keys of below.conf that record only reads on startup
+const RESTART_ONLY_KEYS: &[&str] = &[
+    "log_dir",
+    "store_dir",
+    "cgroup_root",
+    "enable_gpu_stats",
+    "use_rgpu_for_gpu_stats",
+    "gpu_sysfs_root",
+    "gpu_proc_root",
+    "enable_tc_stats",
+    "enable_exec_tracking",
+    "enable_run_delay_stats",
+    "collectors.exitstats",
+];
+
+/// Fold the flags of record into below_config, where they take precedence
+/// over the [collectors.<name>] sections
+fn apply_record_flags(
+    below_config: &mut BelowConfig,
+    collect_io_stat: bool,
+    disable_disk_stat: bool,
+    disable_exitstats: bool,
+) {
+    let collectors = &mut below_config.collectors;
+    if collect_io_stat {
+        collectors.io_stat.enabled = Some(true);
+    }
+    if disable_disk_stat {
+        collectors.disk_stat.enabled = Some(false);
+    }
+    if disable_exitstats {
+        collectors.exitstats.enabled = Some(false);
+    }
+}
+
+/// Set the collector options of record that come from below.conf and may
+/// change on reload. Nothing is set if any of them fails to load.
+fn set_collector_options(
+    options: &mut model::CollectorOptions,
+    below_config: &BelowConfig,
+) -> Result<()> {
+    let cgroup_re = if !below_config.cgroup_filter_out.is_empty() {
+        Some(
+            Regex::new(&below_config.cgroup_filter_out)
+                .context("Failed to generate regex from cgroup_filter_out in below.conf")?,
+        )
+    } else {
+        None
+    };
+    let container_name_lookup = load_container_name_lookup(below_config)?;
+    let redactor = load_redactor(below_config)?;
+    let collectors = &below_config.collectors;
+    options.collect_io_stat = collectors.io_stat.is_enabled(false);
+    options.disable_disk_stat = !collectors.disk_stat.is_enabled(true);
+    options.disable_slabinfo = !collectors.slabinfo.is_enabled(true);
+    options.disable_netstat = !collectors.netstat.is_enabled(true);
+    options.enable_btrfs_stats = below_config.enable_btrfs_stats;
+    options.enable_ethtool_stats = below_config.enable_ethtool_stats;
+    options.enable_ksm_stats = below_config.enable_ksm_stats;
+    options.enable_resctrl_stats = below_config.enable_resctrl_stats;
+    options.btrfs_samples = below_config.btrfs_samples;
+    options.btrfs_min_pct = below_config.btrfs_min_pct;
+    options.cgroup_re = cgroup_re;
+    options.container_name_lookup = container_name_lookup;
+    options.interval_multiples = interval_multiples(below_config);
+    options.redactor = redactor;
+    Ok(())
+}
+
+/// Keys changed from current to new, failing if any of them is only read
+/// on startup
+fn check_reload(current: &BelowConfig, new: &BelowConfig) -> Result<Vec<String>> {
+    let changed_keys = current.changed_keys(new)?;
+    let restart_only: Vec<&str> = changed_keys
+        .iter()
+        .map(String::as_str)
+        .filter(|key| {
+            RESTART_ONLY_KEYS.iter().any(|restart_only| {
+                key == restart_only || key.starts_with(&format!("{}.", restart_only))
+            })
+        })
+        .collect();
+    if !restart_only.is_empty() {
+        bail!(
+            "{} cannot change without restarting below record",
+            restart_only.join(", ")
+        );
+    }
+    Ok(changed_keys)
+}
+
+/// Apply a reloaded below.conf to a running record, returning the changed
+/// keys. Nothing changes if it fails.
+fn reload_record_config(
+    new_config: BelowConfig,
+    below_config: &mut BelowConfig,
+    collector: &mut model::Collector,
+    adaptive_sampler: &mut Option<adaptive_sampling::AdaptiveSampler>,
+    logger: &slog::Logger,
+    interval: Duration,
+) -> Result<Vec<String>> {
+    let changed_keys = check_reload(below_config, &new_config)?;
+    // Rebuilt only if changed, as that ends the current fast window
+    let new_adaptive_sampler = if new_config.adaptive_sampling != below_config.adaptive_sampling {
+        Some(
+            new_config
+                .adaptive_sampling
+                .as_ref()
+                .map(|config| {
+                    adaptive_sampling::AdaptiveSampler::new(logger.clone(), interval, config)
+                })
+                .transpose()?,
+        )
+    } else {
+        None
+    };
+    set_collector_options(collector.collector_options_mut(), &new_config)?;
+    if let Some(new_adaptive_sampler) = new_adaptive_sampler {
+        *adaptive_sampler = new_adaptive_sampler;
+    }
+    *below_config = new_config;
+    Ok(changed_keys)
+}
+
 /// Returns true if other end disconnected, false otherwise
 fn check_for_exitstat_errors(logger: &slog::Logger, receiver: &Receiver<Error>) -> bool {
     // Print an error but don't exit on bpf issues. Do this b/c we can't always
@@ -698,6 +1070,18 @@ fn main() {
 fn real_main(init: init::InitToken) {
     let opts = Opt::parse();
     let debug = opts.debug;
+    // Handled before loading below.conf, which may be what is broken
+    if let Some(Command::Config { cmd }) = opts.cmd.as_ref() {
+        exit(match cmd {
+            ConfigCommand::Check { path, belowrc } => config_check::check(
+                path.as_ref().unwrap_or(&opts.config),
+                &belowrc
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(common::util::get_belowrc_filename())),
+            ),
+            ConfigCommand::Show => config_check::show(&opts.config),
+        });
+    }
     config::BELOW_CONFIG
         .set(match BelowConfig::load(&opts.config) {
             Ok(c) => c,
@@ -771,6 +1155,7 @@ fn real_main(init: init::InitToken) {
                         errs,
                         Duration::from_secs(*interval_s),
                         below_config,
+                        &opts.config,
                         retain_for_s.map(Duration::from_secs),
                         *store_size_limit,
                         *collect_io_stat,
@@ -815,6 +1200,21 @@ fn real_main(init: init::InitToken) {
                 },
             )
         }
+        Command::Snapshot {
+            cmd: Some(ref cmd), ..
+        } => run(
+            init,
+            debug,
+            below_config,
+            Service::Off,
+            RedirectLogOnFail::Off,
+            |_, _below_config, logger, _errs| match cmd {
+                SnapshotCommand::Info { snapshot: path } => snapshot::info(logger, path),
+                SnapshotCommand::Merge { snapshots, output } => {
+                    snapshot::merge(logger, snapshots, output)
+                }
+            },
+        ),
         Command::Snapshot {
             ref begin,
             ref end,
@@ -822,13 +1222,22 @@ fn real_main(init: init::InitToken) {
             ref output,
             ref host,
             ref port,
+            ref redact,
+            ref cgroup_filter,
+            drop: ref drop_parts,
+            cmd: None,
         } => {
-            let begin = begin.clone();
+            let begin = begin
+                .clone()
+                .expect("--begin is required to take a snapshot");
             let end = end.clone();
             let duration = duration.clone();
             let output = output.clone();
             let host = host.clone();
             let port = *port;
+            let redact = *redact;
+            let cgroup_filter = cgroup_filter.clone();
+            let drop_parts = drop_parts.clone();
             run(
                 init,
                 debug,
@@ -845,6 +1254,9 @@ fn real_main(init: init::InitToken) {
                         output,
                         host,
                         port,
+                        redact,
+                        cgroup_filter,
+                        drop_parts,
                     )
                 },
             )
@@ -897,7 +1309,9 @@ fn real_main(init: init::InitToken) {
                             host,
                             port,
                             compress_opts,
+                            &Default::default(),
                         )
+                        .map(|_| ())
                     },
                 )
             }
@@ -924,6 +1338,53 @@ fn real_main(init: init::InitToken) {
                 },
             )
         }
+        Command::Diff {
+            ref host,
+            ref port,
+            ref snapshot,
+            ref opts,
+            ref cmd,
+        } => {
+            let store_dir = below_config.store_dir.clone();
+            let host = host.clone();
+            let port = *port;
+            let snapshot = snapshot.clone();
+            let opts = opts.clone();
+            let cmd = cmd.clone();
+            run(
+                init,
+                debug,
+                below_config,
+                Service::Off,
+                RedirectLogOnFail::Off,
+                |_, _below_config, logger, _errs| {
+                    dump::diff::run(logger, store_dir, host, port, snapshot, opts, cmd)
+                },
+            )
+        }
+        Command::Query {
+            ref host,
+            ref port,
+            ref snapshot,
+            ref opts,
+        } => {
+            let store_dir = below_config.store_dir.clone();
+            let host = host.clone();
+            let port = *port;
+            let snapshot = snapshot.clone();
+            let opts = opts.clone();
+            run(
+                init,
+                debug,
+                below_config,
+                Service::Off,
+                RedirectLogOnFail::Off,
+                |_, _below_config, logger, errs| {
+                    dump::query::run(logger, errs, store_dir, host, port, snapshot, opts)
+                },
+            )
+        }
+        Command::Config { .. } => unreachable!("Handled before loading below.conf"),
         Command::GenerateCompletions {
             ref shell,
             ref output,
@@ -1018,6 +1479,7 @@ fn record(
     errs: Receiver<Error>,
     interval: Duration,
     below_config: &BelowConfig,
+    config_path: &Path,
     retention: Option<Duration>,
     store_size_limit: Option<u64>,
     collect_io_stat: bool,
@@ -1029,6 +1491,22 @@ fn record(
 ) -> Result<()> {
     debug!(logger, "Starting up!");
 
+    // Owned as it is replaced on reload
+    let mut below_config = below_config.clone();
+    let apply_flags = move |below_config: &mut BelowConfig| {
+        apply_record_flags(
+            below_config,
+            collect_io_stat,
+            disable_disk_stat,
+            disable_exitstats,
+        )
+    };
+    apply_flags(&mut below_config);
+    let disable_exitstats = !below_config.collectors.exitstats.is_enabled(true);
+    let reload_requested = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGHUP, reload_requested.clone())
+        .context("Failed to register SIGHUP handler")?;
+
     if !disable_exitstats {
         bump_memlock_rlimit()?;
     }
@@ -1041,22 +1519,20 @@ fn record(
     )?;
     let mut stats = statistics::Statistics::new(init);
 
-    let (exit_buffer, bpf_errs) = if disable_exitstats {
-        (Arc::new(Mutex::new(procfs::PidMap::new())), None)
+    let cgroup_id_map = Arc::new(Mutex::new(model::CgroupIdMap::default()));
+    let exec_counter = (below_config.enable_exec_tracking && !disable_exitstats)
+        .then(|| Arc::new(Mutex::new(model::ExecCounter::default())));
+    let (exit_buffer, exitstat_lost_events, bpf_errs) = if disable_exitstats {
+        (Arc::new(Mutex::new(procfs::PidMap::new())), None, None)
     } else {
-        start_exitstat(logger.clone(), debug)
-    };
-    let mut bpf_err_warned = false;
-
-    // Handle cgroup filter from conf and generate Regex
-    let cgroup_re = if !below_config.cgroup_filter_out.is_empty() {
-        Some(
-            Regex::new(&below_config.cgroup_filter_out)
-                .expect("Failed to generate regex from cgroup_filter_out in below.conf"),
+        start_exitstat(
+            logger.clone(),
+            debug,
+            cgroup_id_map.clone(),
+            exec_counter.clone(),
         )
-    } else {
-        None
     };
+    let mut bpf_err_warned = false;
 
     let gpu_stats_receiver = if below_config.enable_gpu_stats {
         Some(start_gpu_stats_thread_and_get_stats_receiver(
@@ -1077,27 +1553,72 @@ fn record(
         None
     };
 
-    let mut collector = model::Collector::new(
-        logger.clone(),
-        model::CollectorOptions {
-            cgroup_root: below_config.cgroup_root.clone(),
-            exit_data: exit_buffer,
-            collect_io_stat,
-            disable_disk_stat,
-            enable_btrfs_stats: below_config.enable_btrfs_stats,
-            enable_ethtool_stats: below_config.enable_ethtool_stats,
-            enable_ksm_stats: below_config.enable_ksm_stats,
-            enable_resctrl_stats: below_config.enable_resctrl_stats,
-            enable_tc_stats: below_config.enable_tc_stats,
-            btrfs_samples: below_config.btrfs_samples,
-            btrfs_min_pct: below_config.btrfs_min_pct,
-            cgroup_re,
-            gpu_stats_receiver,
-            tc_stats_receiver,
-        },
-    );
+    let mut collector_options = model::CollectorOptions {
+        cgroup_root: below_config.cgroup_root.clone(),
+        exit_data: exit_buffer,
+        cgroup_id_map: cgroup_id_map.clone(),
+        exec_counter,
+        enable_tc_stats: below_config.enable_tc_stats,
+        gpu_stats_receiver,
+        tc_stats_receiver,
+        run_delay_receiver: start_run_delay_stats(
+            &below_config,
+            &logger,
+            interval,
+            debug,
+            cgroup_id_map,
+        ),
+        exitstat_lost_events,
+        ..Default::default()
+    };
+    set_collector_options(&mut collector_options, &below_config)?;
+    let mut collector = model::Collector::new(logger.clone(), collector_options);
+
+    let mut adaptive_sampler = below_config
+        .adaptive_sampling
+        .as_ref()
+        .map(|config| adaptive_sampling::AdaptiveSampler::new(logger.clone(), interval, config))
+        .transpose()?;
+
+    // Only known once the sample is written, so recorded with the next one
+    let mut store_write_latency_usec = None;
 
     loop {
+        if reload_requested.swap(false, Ordering::Relaxed) {
+            let reloaded = BelowConfig::load(config_path).and_then(|mut new_config| {
+                apply_flags(&mut new_config);
+                reload_record_config(
+                    new_config,
+                    &mut below_config,
+                    &mut collector,
+                    &mut adaptive_sampler,
+                    &logger,
+                    interval,
+                )
+            });
+            match reloaded {
+                Ok(changed_keys) if changed_keys.is_empty() => {
+                    info!(
+                        logger,
+                        "Reloaded {}, nothing changed",
+                        config_path.display()
+                    )
+                }
+                Ok(changed_keys) => info!(
+                    logger,
+                    "Reloaded {}, changed: {}",
+                    config_path.display(),
+                    changed_keys.join(", ")
+                ),
+                Err(e) => error!(
+                    logger,
+                    "Failed to reload {}, keeping the current config: {:#}",
+                    config_path.display(),
+                    e
+                ),
+            }
+        }
+
         if !disable_exitstats {
             // Anything that comes over the error channel is an error
             match errs.try_recv() {
@@ -1136,11 +1657,20 @@ fn record(
 
         match collected_sample {
             Ok(s) => {
-                let frame = DataFrame { sample: s };
-                match store.put(post_collect_sys_time, &frame) {
-                    Ok(/* new shard */ true) => {
-                        cleanup_store(&store, &logger, store_size_limit, /* retention */ None)?
-                    }
+                let (frame, put_result) = store_sample(
+                    &mut store,
+                    adaptive_sampler.as_mut(),
+                    post_collect_sys_time,
+                    s,
+                    &mut store_write_latency_usec,
+                );
+                match put_result {
+                    Ok(/* new shard */ true) => cleanup_store(
+                        &store,
+                        &logger,
+                        store_size_limit.or(below_config.store_size_limit),
+                        /* retention */ None,
+                    )?,
                     Ok(/* new shard */ false) => {}
                     Err(e) => error!(logger, "{:#}", e),
                 }
@@ -1150,8 +1680,12 @@ fn record(
             }
             Err(e) => {
                 // Handle cgroupfs errors
-                if let Some(cgroupfs::Error::NotCgroup2(_)) = e.downcast_ref::<cgroupfs::Error>() {
-                    // Unrecoverable error -- below only supports cgroup2
+                if let Some(
+                    cgroupfs::Error::NotCgroup2(_) | cgroupfs::Error::NoCgroupV1Controllers(_),
+                ) = e.downcast_ref::<cgroupfs::Error>()
+                {
+                    // Unrecoverable error -- neither cgroup2 nor any cgroup
+                    // v1 controller is available
                     bail!(e)
                 }
                 error!(logger, "{:#}", e);
@@ -1160,10 +1694,19 @@ fn record(
 
         // Only check against retention and not size limit. Size limit is only
         // checked on creation of successful write to a new shard.
-        cleanup_store(&store, &logger, /* store_size_limit */ None, retention)?;
+        cleanup_store(
+            &store,
+            &logger,
+            /* store_size_limit */ None,
+            retention.or(below_config.retain_for_s.map(Duration::from_secs)),
+        )?;
 
         stats.report_store_size(below_config.store_dir.as_path());
 
+        let interval = match adaptive_sampler.as_mut() {
+            Some(adaptive_sampler) => adaptive_sampler.interval(post_collect_sys_time),
+            None => interval,
+        };
         let collect_duration = Instant::now().duration_since(collect_instant);
         // Sleep for at least 1s to avoid sample collision
         let sleep_duration = if interval > collect_duration {
@@ -1175,6 +1718,29 @@ fn record(
     }
 }
 
+/// Writes the sample collected at timestamp to store, after updating the
+/// adaptive sampler with it. Returns the frame and whether writing it started
+/// a new shard.
+fn store_sample(
+    store: &mut store::StoreWriter,
+    adaptive_sampler: Option<&mut adaptive_sampling::AdaptiveSampler>,
+    timestamp: SystemTime,
+    mut sample: model::Sample,
+    store_write_latency_usec: &mut Option<u64>,
+) -> (DataFrame, Result<bool>) {
+    if let Some(adaptive_sampler) = adaptive_sampler {
+        adaptive_sampler.observe(timestamp, &sample);
+    }
+    if let Some(collector_health) = sample.collector_health.as_mut() {
+        collector_health.store_write_latency_usec = *store_write_latency_usec;
+    }
+    let frame = DataFrame { sample };
+    let put_instant = Instant::now();
+    let put_result = store.put(timestamp, &frame);
+    *store_write_latency_usec = Some(put_instant.elapsed().as_micros() as u64);
+    (frame, put_result)
+}
+
 fn live_local(
     init: init::InitToken,
     logger: slog::Logger,
@@ -1193,7 +1759,16 @@ fn live_local(
         );
     };
 
-    let (exit_buffer, bpf_errs) = start_exitstat(logger.clone(), debug);
+    let cgroup_id_map = Arc::new(Mutex::new(model::CgroupIdMap::default()));
+    let exec_counter = below_config
+        .enable_exec_tracking
+        .then(|| Arc::new(Mutex::new(model::ExecCounter::default())));
+    let (exit_buffer, exitstat_lost_events, bpf_errs) = start_exitstat(
+        logger.clone(),
+        debug,
+        cgroup_id_map.clone(),
+        exec_counter.clone(),
+    );
     let mut bpf_err_warned = false;
 
     let gpu_stats_receiver = if below_config.enable_gpu_stats {
@@ -1211,6 +1786,8 @@ fn live_local(
         model::CollectorOptions {
             cgroup_root: below_config.cgroup_root.clone(),
             exit_data: exit_buffer,
+            cgroup_id_map: cgroup_id_map.clone(),
+            exec_counter,
             enable_btrfs_stats: below_config.enable_btrfs_stats,
             enable_ethtool_stats: below_config.enable_ethtool_stats,
             enable_ksm_stats: below_config.enable_ksm_stats,
@@ -1218,6 +1795,18 @@ fn live_local(
             btrfs_samples: below_config.btrfs_samples,
             btrfs_min_pct: below_config.btrfs_min_pct,
             gpu_stats_receiver,
+            run_delay_receiver: start_run_delay_stats(
+                below_config,
+                &logger,
+                interval,
+                debug,
+                cgroup_id_map,
+            ),
+            container_name_lookup: load_container_name_lookup(below_config)?,
+            exitstat_lost_events,
+            disable_slabinfo: !below_config.collectors.slabinfo.is_enabled(true),
+            disable_netstat: !below_config.collectors.netstat.is_enabled(true),
+            interval_multiples: interval_multiples(below_config),
             ..Default::default()
         },
     );
@@ -1233,6 +1822,7 @@ fn live_local(
         collector.collect_and_update_model()?,
         view::ViewMode::Live(Rc::new(RefCell::new(adv))),
     );
+    view.enable_actions(logger.clone(), below_config.cgroup_root.clone());
 
     let sink = view.cb_sink().clone();
 
@@ -1427,7 +2017,8 @@ fn convert_store(
     host: Option<String>,
     port: Option<u16>,
     compress_opts: &CompressOpts,
-) -> Result<()> {
+    filter: &snapshot::SnapshotFilter,
+) -> Result<snapshot::SnapshotManifest> {
     let (time_begin, time_end) = cliutil::system_time_range_from_date_and_adjuster(
         begin.as_str(),
         end.as_deref(),
@@ -1464,38 +2055,20 @@ fn convert_store(
         }
     };
 
-    let mut dest_store = store::StoreWriter::new(
-        logger.clone(),
-        &to_store_dir,
-        compress_opts.to_compression_mode()?,
-        store::Format::Cbor,
-    )?;
+    let mut writer = snapshot::FrameWriter::new(logger, &to_store_dir, compress_opts, filter)?;
 
     pb.set_message(format!("Writing to local store at {:?}", to_store_dir));
 
-    let mut nr_samples = 0;
-    let mut cur_time = time_begin;
-    while cur_time < time_end {
-        match store.get_sample_at_timestamp(cur_time, store::Direction::Forward)? {
-            Some((frame_time, frame)) => {
-                cur_time = frame_time;
-                pb.set_message(format!("Storing frame at t = {:?}", frame_time));
-                dest_store.put(frame_time, &frame)?;
-                nr_samples += 1;
-            }
-            None => {
-                pb.set_message(format!(
-                    "Error: Breaking early. Couldn't find any frames after t = {:?}",
-                    cur_time
-                ));
-                break;
-            }
-        }
-        pb.set_position(common::util::get_unix_timestamp(cur_time) - timestamp_begin);
-        cur_time += Duration::from_secs(1); // To actually move forward
-    }
-    pb.set_message(format!("Done. Logged {} samples.", nr_samples));
-    Ok(())
+    snapshot::for_each_frame(
+        store.as_mut(),
+        time_begin,
+        time_end,
+        &pb,
+        |frame_time, frame| writer.put(frame_time, frame),
+    )?;
+    let manifest = writer.finish();
+    pb.set_message(format!("Done. Logged {} samples.", manifest.nr_frames));
+    Ok(manifest)
 }
 
 fn snapshot(
@@ -1507,7 +2080,24 @@ fn snapshot(
     output: Option<PathBuf>,
     host: Option<String>,
     port: Option<u16>,
+    redact: bool,
+    cgroup_filter: Option<String>,
+    drop_parts: Vec<snapshot::SnapshotPart>,
 ) -> Result<()> {
+    let filter = snapshot::SnapshotFilter {
+        redactor: if redact {
+            match load_redactor(below_config)? {
+                Some(redactor) => Some(redactor),
+                None => bail!("--redact requires a [redaction] section in below.conf"),
+            }
+        } else {
+            None
+        },
+        cgroup_re: cgroup_filter
+            .map(|cgroup_filter| Regex::new(&cgroup_filter).context("Invalid --cgroup-filter"))
+            .transpose()?,
+        drop: drop_parts.into_iter().collect(),
+    };
     let (time_begin, time_end) = cliutil::system_time_range_from_date_and_adjuster(
         begin.as_str(),
         end.as_deref(),
@@ -1528,12 +2118,7 @@ fn snapshot(
     .context("Failed to create temporary folder for snapshot")?;
     let snapshot_store_path = temp_folder.into_path();
 
-    // Build compression options to ensure snapshot is compressed before tarball
-    let compress_opts = CompressOpts {
-        compress: true,
-        dict_compress_chunk_size: Some(16),
-    };
-    convert_store(
+    let manifest = convert_store(
         logger,
         below_config,
         begin,
@@ -1543,7 +2128,8 @@ fn snapshot(
         snapshot_store_path.clone(),
         host,
         port,
-        &compress_opts,
+        &snapshot::SNAPSHOT_COMPRESS_OPTS,
+        &filter,
     )
     .context("Failed to convert store for snapshot")?;
 
@@ -1558,14 +2144,7 @@ fn snapshot(
             .with_context(|| "path has no filename")?
             .into()
     };
-    let file = fs::File::create(&tarball)
-        .with_context(|| format!("Failed to create snapshot file {}", tarball.display()))?;
-    // Create a new tarball with the snapshot dir name
-    let mut tar = TarBuilder::new(file);
-    tar.append_dir_all("store", snapshot_store_path.as_path())
-        .context("Failed to add snapshot store to tar builder")?;
-    tar.finish()
-        .context("Failed to build compressed snapshot file.")?;
+    snapshot::write(&snapshot_store_path, &manifest, &tarball)?;
 
     println!("Snapshot has been created at {}", tarball.display());
     Ok(())
@@ -20,5 +20,14 @@ pub fn get_gpu_stats_collector_plugin(
     _init: InitToken,
     logger: slog::Logger,
 ) -> Result<model::gpu_stats_collector_plugin::GpuStatsCollectorPlugin> {
-    model::gpu_stats_collector_plugin::GpuStatsCollectorPlugin::new(logger)
+    match config::BELOW_CONFIG.get() {
+        Some(below_config) => {
+            model::gpu_stats_collector_plugin::GpuStatsCollectorPlugin::new_with_roots(
+                logger,
+                below_config.gpu_sysfs_root.clone(),
+                below_config.gpu_proc_root.clone(),
+            )
+        }
+        None => model::gpu_stats_collector_plugin::GpuStatsCollectorPlugin::new(logger),
+    }
 }
@@ -15,6 +15,9 @@
 mod bpf {
     include!(concat!(env!("OUT_DIR"), "/exitstat.skel.rs"));
 }
+mod run_delay_bpf {
+    include!(concat!(env!("OUT_DIR"), "/run_delay.skel.rs"));
+}
 pub mod commands;
 pub mod gpu_stats;
 pub mod init;
@@ -22,3 +25,5 @@ pub mod logging;
 pub mod statistics;
 
 pub use bpf::ExitstatSkelBuilder;
+pub use run_delay_bpf::RunDelaySkel;
+pub use run_delay_bpf::RunDelaySkelBuilder;
@@ -0,0 +1,160 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
+use libbpf_rs::skel::OpenSkel as _;
+use libbpf_rs::skel::Skel as _;
+use libbpf_rs::skel::SkelBuilder as _;
+use libbpf_rs::MapCore as _;
+use libbpf_rs::MapFlags;
+use model::collector_plugin::AsyncCollectorPlugin;
+use model::run_delay::RunDelayHistogram;
+use model::run_delay::SampleType;
+use model::run_delay::NR_BUCKETS;
+use slog::error;
+
+use crate::RunDelaySkel;
+use crate::RunDelaySkelBuilder;
+
+/// Size of the histogram map, see MAX_CGROUPS in the bpf prog. Histograms of
+/// removed cgroups are deleted once half of it is used, so that they do not
+/// fill it up.
+const MAX_CGROUPS: usize = 16384;
+
+/// Loads and attaches the bpf prog. The programs are detached when the
+/// returned skeleton is dropped.
+pub fn load(debug: bool) -> Result<RunDelaySkel<'static>> {
+    let mut skel_builder = RunDelaySkelBuilder::default();
+    skel_builder.obj_builder.debug(debug);
+    let mut skel = skel_builder
+        .open()
+        .context("Failed to open BPF program")?
+        .load()
+        .context("Failed to load BPF program")?;
+    skel.attach().context("Failed to attach BPF program")?;
+    Ok(skel)
+}
+
+pub(crate) fn parse_entry(key: &[u8], value: &[u8]) -> Option<(u64, RunDelayHistogram)> {
+    let cgroup_id = u64::from_ne_bytes(key.try_into().ok()?);
+    if value.len() != NR_BUCKETS * 8 {
+        return None;
+    }
+    let buckets = value
+        .chunks_exact(8)
+        .map(|bucket| u64::from_ne_bytes(bucket.try_into().unwrap()))
+        .collect();
+    Some((cgroup_id, RunDelayHistogram { buckets }))
+}
+
+/// Cgroups of hists that were removed. A cgroup is removed if the last
+/// cgroup walk did not find it. As that walk may be older than a new cgroup,
+/// the histogram must also not have changed since previous.
+pub(crate) fn removed_cgroups(
+    hists: &SampleType,
+    previous: &SampleType,
+    cgroup_id_map: &model::CgroupIdMap,
+) -> Vec<u64> {
+    hists
+        .iter()
+        .filter(|(cgroup_id, hist)| {
+            previous.get(*cgroup_id) == Some(*hist) && cgroup_id_map.resolve(**cgroup_id).is_none()
+        })
+        .map(|(cgroup_id, _)| *cgroup_id)
+        .collect()
+}
+
+/// Reads the run queue latency histograms of the bpf prog
+pub struct RunDelayCollectorPlugin {
+    logger: slog::Logger,
+    hists: libbpf_rs::MapHandle,
+    previous: SampleType,
+    /// Cgroups found by the collector, to tell which ones were removed
+    cgroup_id_map: Arc<Mutex<model::CgroupIdMap>>,
+}
+
+impl RunDelayCollectorPlugin {
+    pub fn new(
+        logger: slog::Logger,
+        skel: &RunDelaySkel,
+        cgroup_id_map: Arc<Mutex<model::CgroupIdMap>>,
+    ) -> Result<Self> {
+        Ok(Self {
+            logger,
+            hists: libbpf_rs::MapHandle::try_from(skel.maps().hists())
+                .context("Failed to get histogram map")?,
+            previous: SampleType::new(),
+            cgroup_id_map,
+        })
+    }
+
+    fn read_hists(&self) -> Result<SampleType> {
+        let mut hists = SampleType::new();
+        for key in self.hists.keys() {
+            // The entry may have been deleted since the key was read
+            if let Some(value) = self.hists.lookup(&key, MapFlags::ANY)? {
+                if let Some((cgroup_id, hist)) = parse_entry(&key, &value) {
+                    hists.insert(cgroup_id, hist);
+                }
+            }
+        }
+        Ok(hists)
+    }
+
+    /// Delete the histograms of removed cgroups. Those of idle cgroups are
+    /// kept, as they are cumulative.
+    fn prune(&self, hists: &mut SampleType) -> Result<()> {
+        let removed = removed_cgroups(
+            hists,
+            &self.previous,
+            &self
+                .cgroup_id_map
+                .lock()
+                .expect("tried to acquire poisoned lock"),
+        );
+        for cgroup_id in removed {
+            self.hists.delete(&cgroup_id.to_ne_bytes())?;
+            hists.remove(&cgroup_id);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncCollectorPlugin for RunDelayCollectorPlugin {
+    type T = SampleType;
+
+    async fn try_collect(&mut self) -> Result<Option<SampleType>> {
+        let mut hists = match self.read_hists() {
+            Ok(hists) => hists,
+            Err(e) => {
+                error!(self.logger, "Failed to read run delay histograms: {:#}", e);
+                return Ok(None);
+            }
+        };
+        let sample = hists.clone();
+        if hists.len() > MAX_CGROUPS / 2 {
+            if let Err(e) = self.prune(&mut hists) {
+                error!(self.logger, "Failed to prune run delay histograms: {:#}", e);
+            }
+        }
+        self.previous = hists;
+        Ok(Some(sample))
+    }
+}
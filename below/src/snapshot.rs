@@ -0,0 +1,557 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Snapshot files are tarballs of a store directory named "store". The
+//! manifest describing them is a file in that directory, so that snapshots
+//! still unpack to a single directory for older versions of below.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use clap::ValueEnum;
+use common::util::get_system_time;
+use common::util::get_unix_timestamp;
+use common::util::systemtime_to_datetime;
+use indicatif::ProgressBar;
+use model::CgroupSample;
+use model::Sample;
+use regex::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+use store::DataFrame;
+use store::Store;
+use tar::Archive;
+use tar::Builder as TarBuilder;
+use tempfile::TempDir;
+
+use crate::CompressOpts;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Compression of the stores of snapshots, so they are small before being
+/// put into the tarball
+pub const SNAPSHOT_COMPRESS_OPTS: CompressOpts = CompressOpts {
+    compress: true,
+    dict_compress_chunk_size: Some(16),
+};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct SnapshotManifest {
+    /// Version of below that wrote the snapshot
+    pub below_version: String,
+    /// Hostname of the first frame, empty if there are none
+    pub hostname: String,
+    /// Unix timestamps of the first and last frame
+    pub begin: u64,
+    pub end: u64,
+    pub nr_frames: u64,
+    /// Collectors with data in any of the frames, named as the
+    /// [collectors.<name>] sections of below.conf
+    pub collectors: BTreeSet<String>,
+    /// Whether the [redaction] rules of below.conf were applied
+    pub redacted: bool,
+    /// Regex of the cgroups kept, if filtered
+    #[serde(default)]
+    pub cgroup_filter: Option<String>,
+    /// Parts of the samples that were dropped
+    #[serde(default)]
+    pub dropped: BTreeSet<String>,
+}
+
+impl SnapshotManifest {
+    pub fn new() -> Self {
+        Self {
+            below_version: env!("CARGO_PKG_VERSION").to_owned(),
+            ..Default::default()
+        }
+    }
+
+    pub fn add_frame(&mut self, timestamp: SystemTime, frame: &DataFrame) {
+        let timestamp = get_unix_timestamp(timestamp);
+        if self.nr_frames == 0 {
+            self.begin = timestamp;
+            self.hostname = frame.sample.system.hostname.clone();
+        }
+        self.end = timestamp;
+        self.nr_frames += 1;
+        self.collectors
+            .extend(collectors_present(&frame.sample).map(str::to_owned));
+    }
+}
+
+/// Parts of the samples that can be dropped from a snapshot
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum SnapshotPart {
+    Processes,
+    Slab,
+    Netstat,
+    Disks,
+    Btrfs,
+    Ethtool,
+    Ksm,
+    Resctrl,
+    Tc,
+    Gpu,
+}
+
+impl SnapshotPart {
+    /// Parts with data of entities that cannot be told apart by cgroup, e.g.
+    /// the qdiscs of tc, so they are dropped when filtering by cgroup
+    const NOT_BY_CGROUP: [SnapshotPart; 3] =
+        [SnapshotPart::Btrfs, SnapshotPart::Resctrl, SnapshotPart::Tc];
+}
+
+/// Applied to every frame of a snapshot
+#[derive(Default)]
+pub struct SnapshotFilter {
+    pub redactor: Option<model::Redactor>,
+    /// Only the cgroups whose path matches, their ancestors and descendants,
+    /// and the processes in them are kept. Parts that cannot be filtered by
+    /// cgroup are dropped.
+    pub cgroup_re: Option<Regex>,
+    pub drop: BTreeSet<SnapshotPart>,
+}
+
+impl SnapshotFilter {
+    pub fn apply(&self, sample: &mut Sample) {
+        if let Some(redactor) = self.redactor.as_ref() {
+            redactor.redact_sample(sample);
+        }
+        if let Some(cgroup_re) = self.cgroup_re.as_ref() {
+            let mut kept = Vec::new();
+            prune_cgroups("", &mut sample.cgroup, cgroup_re, &mut kept);
+            sample.processes.retain(|_, pid_info| {
+                kept.iter().any(|path| {
+                    path == "/"
+                        || pid_info.cgroup == *path
+                        || pid_info.cgroup.starts_with(&format!("{}/", path))
+                })
+            });
+            if let Some(gpus) = sample.gpus.as_mut() {
+                gpus.gpu_map
+                    .processes
+                    .retain(|pid, _| sample.processes.contains_key(pid));
+            }
+            // Names of the containers that were pruned would remain otherwise
+            sample.container_names = sample
+                .container_names
+                .take()
+                .map(|names| model::lookup_container_names(&sample.cgroup, &NameMap(&names)));
+        }
+        for part in self.dropped() {
+            match part {
+                SnapshotPart::Processes => {
+                    sample.processes = Default::default();
+                    if let Some(gpus) = sample.gpus.as_mut() {
+                        gpus.gpu_map.processes = Default::default();
+                    }
+                }
+                SnapshotPart::Slab => sample.system.slabinfo = Default::default(),
+                SnapshotPart::Netstat => sample.netstats = Default::default(),
+                SnapshotPart::Disks => sample.system.disks = Default::default(),
+                SnapshotPart::Btrfs => sample.system.btrfs = None,
+                SnapshotPart::Ethtool => sample.ethtool = None,
+                SnapshotPart::Ksm => sample.system.ksm = None,
+                SnapshotPart::Resctrl => sample.resctrl = None,
+                SnapshotPart::Tc => sample.tc = None,
+                SnapshotPart::Gpu => sample.gpus = None,
+            }
+        }
+    }
+
+    /// Records what the filter does in manifest
+    pub fn describe(&self, manifest: &mut SnapshotManifest) {
+        manifest.redacted = self.redactor.is_some();
+        manifest.cgroup_filter = self.cgroup_re.as_ref().map(|re| re.as_str().to_owned());
+        manifest.dropped = self
+            .dropped()
+            .iter()
+            .filter_map(|part| part.to_possible_value())
+            .map(|value| value.get_name().to_owned())
+            .collect();
+    }
+
+    fn dropped(&self) -> BTreeSet<SnapshotPart> {
+        let mut dropped = self.drop.clone();
+        if self.cgroup_re.is_some() {
+            dropped.extend(SnapshotPart::NOT_BY_CGROUP);
+        }
+        dropped
+    }
+}
+
+/// Removes the descendants of cgroup that neither match re nor have a
+/// matching descendant, appending the paths of the matching ones to kept.
+/// Returns whether cgroup matches or has a matching descendant.
+fn prune_cgroups(
+    path: &str,
+    cgroup: &mut CgroupSample,
+    re: &Regex,
+    kept: &mut Vec<String>,
+) -> bool {
+    let path_or_root = if path.is_empty() { "/" } else { path };
+    if re.is_match(path_or_root) {
+        kept.push(path_or_root.to_owned());
+        return true;
+    }
+    match cgroup.children.as_mut() {
+        Some(children) => {
+            children.retain(|name, child| {
+                prune_cgroups(&format!("{}/{}", path, name), child, re, kept)
+            });
+            !children.is_empty()
+        }
+        None => false,
+    }
+}
+
+/// Container names of a sample, to look up those of the cgroups kept
+struct NameMap<'a>(&'a BTreeMap<String, String>);
+
+impl model::ContainerNameLookup for NameMap<'_> {
+    fn lookup(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+}
+
+/// Collectors whose data is in sample. Disabled ones leave it empty.
+fn collectors_present(sample: &Sample) -> impl Iterator<Item = &'static str> {
+    let exitstats = sample
+        .collector_health
+        .as_ref()
+        .map_or(false, |health| health.exitstat_lost_events.is_some());
+    [
+        ("io_stat", sample.cgroup.io_stat.is_some()),
+        ("disk_stat", !sample.system.disks.is_empty()),
+        ("slabinfo", !sample.system.slabinfo.is_empty()),
+        ("netstat", sample.netstats != Default::default()),
+        ("btrfs", sample.system.btrfs.is_some()),
+        ("ethtool", sample.ethtool.is_some()),
+        ("ksm", sample.system.ksm.is_some()),
+        ("resctrl", sample.resctrl.is_some()),
+        ("tc", sample.tc.is_some()),
+        ("gpu", sample.gpus.is_some()),
+        ("exitstats", exitstats),
+    ]
+    .into_iter()
+    .filter_map(|(collector, present)| present.then_some(collector))
+}
+
+/// Calls f with the frames of store from time_begin until time_end, oldest
+/// first, showing the progress on pb.
+pub fn for_each_frame(
+    store: &mut dyn Store<SampleType = DataFrame>,
+    time_begin: SystemTime,
+    time_end: SystemTime,
+    pb: &ProgressBar,
+    mut f: impl FnMut(SystemTime, DataFrame) -> Result<()>,
+) -> Result<()> {
+    let timestamp_begin = get_unix_timestamp(time_begin);
+    let mut cur_time = time_begin;
+    while cur_time < time_end {
+        match store.get_sample_at_timestamp(cur_time, store::Direction::Forward)? {
+            Some((frame_time, frame)) => {
+                cur_time = frame_time;
+                pb.set_message(format!("Storing frame at t = {:?}", frame_time));
+                f(frame_time, frame)?;
+            }
+            None => {
+                pb.set_message(format!(
+                    "Error: Breaking early. Couldn't find any frames after t = {:?}",
+                    cur_time
+                ));
+                break;
+            }
+        }
+        pb.set_position(get_unix_timestamp(cur_time) - timestamp_begin);
+        cur_time += Duration::from_secs(1); // To actually move forward
+    }
+    Ok(())
+}
+
+/// Writes frames to a new store, applying filter to them first, and keeps the
+/// manifest of the frames written.
+pub struct FrameWriter<'a> {
+    store: store::StoreWriter,
+    filter: &'a SnapshotFilter,
+    pub manifest: SnapshotManifest,
+}
+
+impl<'a> FrameWriter<'a> {
+    pub fn new(
+        logger: slog::Logger,
+        store_dir: &Path,
+        compress_opts: &CompressOpts,
+        filter: &'a SnapshotFilter,
+    ) -> Result<Self> {
+        let store = store::StoreWriter::new(
+            logger,
+            store_dir,
+            compress_opts.to_compression_mode()?,
+            store::Format::Cbor,
+        )?;
+        let mut manifest = SnapshotManifest::new();
+        filter.describe(&mut manifest);
+        Ok(Self {
+            store,
+            filter,
+            manifest,
+        })
+    }
+
+    pub fn put(&mut self, timestamp: SystemTime, mut frame: DataFrame) -> Result<()> {
+        self.filter.apply(&mut frame.sample);
+        self.store.put(timestamp, &frame)?;
+        self.manifest.add_frame(timestamp, &frame);
+        Ok(())
+    }
+
+    /// Closes the store and returns the manifest of its frames
+    pub fn finish(self) -> SnapshotManifest {
+        self.manifest
+    }
+}
+
+/// Writes the store at store_dir and its manifest to the snapshot file at
+/// path.
+pub fn write(store_dir: &Path, manifest: &SnapshotManifest, path: &Path) -> Result<()> {
+    let manifest_str =
+        serde_json::to_string_pretty(manifest).context("Failed to serialize manifest")?;
+    fs::write(store_dir.join(MANIFEST_FILE), manifest_str)
+        .context("Failed to write snapshot manifest")?;
+    let file = fs::File::create(path)
+        .with_context(|| format!("Failed to create snapshot file {}", path.display()))?;
+    let mut tar = TarBuilder::new(file);
+    tar.append_dir_all("store", store_dir)
+        .context("Failed to add snapshot store to tar builder")?;
+    tar.finish()
+        .context("Failed to build compressed snapshot file.")?;
+    Ok(())
+}
+
+/// A snapshot file unpacked to a temporary directory, which is removed on
+/// drop.
+pub struct UnpackedSnapshot {
+    _dir: TempDir,
+    pub store_dir: PathBuf,
+    pub manifest: SnapshotManifest,
+    /// Snapshots of older versions have no manifest. It is then computed
+    /// from the frames.
+    pub has_manifest: bool,
+}
+
+impl UnpackedSnapshot {
+    pub fn open(logger: &slog::Logger, path: &Path) -> Result<Self> {
+        let dir = TempDir::with_prefix("snapshot_unpack.")?;
+        Archive::new(
+            fs::File::open(path)
+                .with_context(|| format!("Failed to open snapshot file {}", path.display()))?,
+        )
+        .unpack(dir.path())
+        .with_context(|| format!("Failed to unpack snapshot file {}", path.display()))?;
+        let store_dir = dir.path().join("store");
+        if !store_dir.is_dir() {
+            bail!("{} is not a snapshot file", path.display());
+        }
+        let manifest_path = store_dir.join(MANIFEST_FILE);
+        let (manifest, has_manifest) = if manifest_path.exists() {
+            let manifest = serde_json::from_str(&fs::read_to_string(&manifest_path)?)
+                .with_context(|| format!("Invalid manifest in {}", path.display()))?;
+            (manifest, true)
+        } else {
+            (scan(logger, &store_dir)?, false)
+        };
+        Ok(Self {
+            _dir: dir,
+            store_dir,
+            manifest,
+            has_manifest,
+        })
+    }
+}
+
+/// Computes the manifest of the store at store_dir from its frames
+fn scan(logger: &slog::Logger, store_dir: &Path) -> Result<SnapshotManifest> {
+    let mut store = store::LocalStore::new(logger.clone(), store_dir.to_path_buf());
+    let timestamps = store.get_sample_timestamps(None)?.unwrap_or_default();
+    let mut manifest = SnapshotManifest {
+        below_version: "unknown".to_owned(),
+        ..Default::default()
+    };
+    if let (Some(first), Some(last)) = (timestamps.first(), timestamps.last()) {
+        for_each_frame(
+            &mut store,
+            *first,
+            *last + Duration::from_secs(1),
+            &ProgressBar::hidden(),
+            |frame_time, frame| {
+                manifest.add_frame(frame_time, &frame);
+                Ok(())
+            },
+        )?;
+    }
+    Ok(manifest)
+}
+
+pub fn info(logger: slog::Logger, path: &Path) -> Result<()> {
+    let snapshot = UnpackedSnapshot::open(&logger, path)?;
+    let manifest = &snapshot.manifest;
+    if !snapshot.has_manifest {
+        println!("No manifest, computed from the frames");
+    }
+    println!("Hostname:      {}", manifest.hostname);
+    println!("Below version: {}", manifest.below_version);
+    println!(
+        "Begin:         {} ({})",
+        systemtime_to_datetime(get_system_time(manifest.begin)),
+        manifest.begin
+    );
+    println!(
+        "End:           {} ({})",
+        systemtime_to_datetime(get_system_time(manifest.end)),
+        manifest.end
+    );
+    println!("Frames:        {}", manifest.nr_frames);
+    println!(
+        "Collectors:    {}",
+        manifest
+            .collectors
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    println!("Redacted:      {}", manifest.redacted);
+    if let Some(cgroup_filter) = manifest.cgroup_filter.as_ref() {
+        println!("Cgroup filter: {}", cgroup_filter);
+    }
+    if !manifest.dropped.is_empty() {
+        println!(
+            "Dropped:       {}",
+            manifest
+                .dropped
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+    }
+    Ok(())
+}
+
+/// Merges snapshots of the same host into one at output. Frames found in
+/// more than one snapshot are only written once.
+pub fn merge(logger: slog::Logger, paths: &[PathBuf], output: &Path) -> Result<()> {
+    let mut snapshots = paths
+        .iter()
+        .map(|path| UnpackedSnapshot::open(&logger, path))
+        .collect::<Result<Vec<_>>>()?;
+    snapshots.retain(|snapshot| snapshot.manifest.nr_frames > 0);
+    if let Some((first, others)) = snapshots.split_first() {
+        if let Some(other) = others
+            .iter()
+            .find(|other| other.manifest.hostname != first.manifest.hostname)
+        {
+            bail!(
+                "Cannot merge snapshots of different hosts: {} and {}",
+                first.manifest.hostname,
+                other.manifest.hostname
+            );
+        }
+        if let Some(other) = others
+            .iter()
+            .find(|other| other.manifest.cgroup_filter != first.manifest.cgroup_filter)
+        {
+            bail!(
+                "Cannot merge snapshots with different cgroup filters: {:?} and {:?}",
+                first.manifest.cgroup_filter,
+                other.manifest.cgroup_filter
+            );
+        }
+    }
+    snapshots.sort_by_key(|snapshot| snapshot.manifest.begin);
+
+    // Filtering the frames again keeps them as they are, but the parts
+    // dropped from any of the snapshots are incomplete in the merged one, so
+    // they are dropped from all frames.
+    let mut drop = BTreeSet::new();
+    for snapshot in &snapshots {
+        for name in &snapshot.manifest.dropped {
+            drop.insert(
+                SnapshotPart::from_str(name, false)
+                    .map_err(|_| anyhow!("Unknown part {} dropped from snapshot", name))?,
+            );
+        }
+    }
+    let filter = SnapshotFilter {
+        redactor: None,
+        cgroup_re: snapshots
+            .first()
+            .and_then(|first| first.manifest.cgroup_filter.as_ref())
+            .map(|cgroup_filter| Regex::new(cgroup_filter))
+            .transpose()
+            .context("Invalid cgroup filter in snapshot manifest")?,
+        drop,
+    };
+
+    let merged_dir = TempDir::with_prefix("snapshot_merge.")?;
+    let mut writer = FrameWriter::new(
+        logger.clone(),
+        merged_dir.path(),
+        &SNAPSHOT_COMPRESS_OPTS,
+        &filter,
+    )?;
+    writer.manifest.redacted =
+        !snapshots.is_empty() && snapshots.iter().all(|snapshot| snapshot.manifest.redacted);
+    // Frames at the same timestamp are only written once, from the snapshot
+    // that begins first. Frames in between are all kept.
+    let mut stores = Vec::new();
+    let mut frame_stores: BTreeMap<SystemTime, usize> = BTreeMap::new();
+    for snapshot in &snapshots {
+        let mut store = store::LocalStore::new(logger.clone(), snapshot.store_dir.clone());
+        for timestamp in store.get_sample_timestamps(None)?.unwrap_or_default() {
+            frame_stores.entry(timestamp).or_insert(stores.len());
+        }
+        stores.push(store);
+    }
+    let pb = ProgressBar::new(frame_stores.len() as u64);
+    for (timestamp, idx) in frame_stores {
+        pb.set_message(format!("Storing frame at t = {:?}", timestamp));
+        match stores[idx].get_sample_at_timestamp(timestamp, store::Direction::Forward)? {
+            Some((frame_time, frame)) if frame_time == timestamp => {
+                writer.put(frame_time, frame)?;
+            }
+            _ => bail!("Failed to read frame at t = {:?}", timestamp),
+        }
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+    let merged = writer.finish();
+    write(merged_dir.path(), &merged, output)?;
+    println!(
+        "Merged {} frames into {}",
+        merged.nr_frames,
+        output.display()
+    );
+    Ok(())
+}
@@ -13,23 +13,41 @@
 // limitations under the License.
 
 use std::collections::BTreeMap;
+use std::mem::offset_of;
+use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
 use common::logutil::get_logger;
 use common::util::fold_string;
+use model::CgroupIdMap;
 use model::CgroupPressureModel;
+use model::CgroupSample;
 use model::Collector;
 use model::CollectorOptions;
+use model::ExecCounter;
 use model::Model;
 use model::Sample;
 use store::advance::new_advance_local;
 use store::ChunkSizePo2;
 use store::CompressionMode;
 use store::DataFrame;
+use store::Store;
 use tempfile::TempDir;
 
+use crate::adaptive_sampling::AdaptiveSampler;
+use crate::config_check;
+use crate::exitstat::Event;
+use crate::exitstat::ExecEvent;
+use crate::exitstat::ExecInfo;
+use crate::exitstat::ExitstatDriver;
+use crate::exitstat::PendingExecs;
+use crate::exitstat::MAX_PENDING_EXECS;
+use crate::snapshot;
+use crate::store_sample;
+
 #[cfg_attr(not(feature = "vmtest"), test)]
 fn record_replay_integration() {
     let logger = get_logger();
@@ -61,6 +79,12 @@ fn record_replay_integration() {
         .total
         .as_ref()
         .expect("missing memory.pressure.total");
+    sample
+        .collector_health
+        .as_ref()
+        .expect("missing collector_health")
+        .cgroup_latency_usec
+        .expect("missing cgroup latency");
     let nr_procs = sample.processes.len();
     let hostname = sample.system.hostname.clone();
     let proc0_cgroup = sample
@@ -183,6 +207,101 @@ fn advance_forward_and_reverse() {
     );
 }
 
+/// Clock advanced only by the test.
+struct FakeClock {
+    now: SystemTime,
+}
+
+impl FakeClock {
+    fn sleep(&mut self, duration: Duration) {
+        self.now += duration;
+    }
+}
+
+/// Collector whose root memory pressure is high within the given range.
+struct FakePressureCollector {
+    high_pressure: std::ops::Range<SystemTime>,
+}
+
+impl FakePressureCollector {
+    fn collect_sample(&self, now: SystemTime) -> Sample {
+        let avg10 = if self.high_pressure.contains(&now) {
+            80.0
+        } else {
+            5.0
+        };
+        let pressure = cgroupfs::PressureMetrics {
+            avg10: Some(avg10),
+            ..Default::default()
+        };
+        let mut sample: Sample = Default::default();
+        sample.cgroup.pressure = Some(cgroupfs::Pressure {
+            cpu: Default::default(),
+            io: Default::default(),
+            memory: cgroupfs::MemoryPressure {
+                some: pressure.clone(),
+                full: pressure,
+            },
+        });
+        sample
+    }
+}
+
+#[cfg_attr(not(feature = "vmtest"), test)]
+fn adaptive_sampling() {
+    let logger = get_logger();
+    let dir = TempDir::with_prefix("below_adaptive_sampling_test.").expect("tempdir failed");
+    let mut store = store::StoreWriter::new(
+        logger.clone(),
+        &dir,
+        CompressionMode::ZstdDictionary(ChunkSizePo2(2)),
+        store::Format::Cbor,
+    )
+    .expect("Failed to create store");
+
+    let start = UNIX_EPOCH + Duration::from_secs(554400);
+    let secs = |s| start + Duration::from_secs(s);
+    let mut clock = FakeClock { now: start };
+    // Pressure outlasts the window, which must not be extended
+    let collector = FakePressureCollector {
+        high_pressure: secs(20)..secs(40),
+    };
+    let config = config::AdaptiveSamplingConfig {
+        fast_interval_s: 1,
+        window_s: 10,
+        pressure_threshold_pct: 40.0,
+        ..Default::default()
+    };
+    let mut sampler = AdaptiveSampler::new(logger.clone(), Duration::from_secs(5), &config)
+        .expect("Failed to create sampler");
+    let mut store_write_latency_usec = None;
+    while clock.now <= secs(50) {
+        let sample = collector.collect_sample(clock.now);
+        let (_, put_result) = store_sample(
+            &mut store,
+            Some(&mut sampler),
+            clock.now,
+            sample,
+            &mut store_write_latency_usec,
+        );
+        put_result.expect("Failed to store sample");
+        let interval = sampler.interval(clock.now);
+        clock.sleep(interval);
+    }
+
+    let timestamps = store::LocalStore::new(logger, dir.as_ref().to_path_buf())
+        .get_sample_timestamps(None)
+        .expect("Failed to list samples")
+        .expect("Local store lists samples");
+    let expected: Vec<_> = [0, 5, 10, 15]
+        .into_iter()
+        .chain(20..30)
+        .chain([30, 35, 40, 45, 50])
+        .map(secs)
+        .collect();
+    assert_eq!(timestamps, expected);
+}
+
 #[cfg_attr(not(feature = "vmtest"), test)]
 fn disable_io_stat() {
     let logger = get_logger();
@@ -377,3 +496,450 @@ fn testing_fold_string() {
         "d/...aaaaa"
     );
 }
+
+/// Bytes of a zeroed T with fields written at their offsets, as the
+/// exitstat BPF program would send them.
+fn event_bytes<T>(fields: &[(usize, &[u8])]) -> Vec<u8> {
+    let mut data = vec![0; std::mem::size_of::<T>()];
+    for (offset, bytes) in fields {
+        data[*offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+    data
+}
+
+#[test]
+fn exitstat_cgroup_attribution() {
+    let service = CgroupSample {
+        inode_number: Some(43),
+        ..Default::default()
+    };
+    let slice = CgroupSample {
+        inode_number: Some(42),
+        children: Some(BTreeMap::from([("foo.service".to_owned(), service)])),
+        ..Default::default()
+    };
+    let root = CgroupSample {
+        inode_number: Some(1),
+        children: Some(BTreeMap::from([("system.slice".to_owned(), slice)])),
+        ..Default::default()
+    };
+    let cgroup_id_map = Arc::new(Mutex::new(CgroupIdMap::default()));
+    cgroup_id_map.lock().unwrap().update(&root);
+
+    let buffer = Arc::new(Mutex::new(procfs::PidMap::default()));
+    for (tid, cgroup_id) in [(100i32, 43u64), (101, 1), (102, 7)] {
+        let data = event_bytes::<Event>(&[
+            (offset_of!(Event, meta.tid), &tid.to_ne_bytes()),
+            (offset_of!(Event, meta.cgroup_id), &cgroup_id.to_ne_bytes()),
+            (offset_of!(Event, meta.comm), b"job\0"),
+        ]);
+        ExitstatDriver::handle_event(&buffer, &cgroup_id_map, &Default::default(), &data);
+    }
+
+    let buffer = buffer.lock().unwrap();
+    assert_eq!(buffer[&100].cgroup, "/system.slice/foo.service");
+    assert_eq!(buffer[&100].stat.comm.as_deref(), Some("job"));
+    assert_eq!(buffer[&101].cgroup, "/");
+    // Unknown cgroup, e.g. created since the last cgroup walk
+    assert_eq!(buffer[&102].cgroup, "?");
+}
+
+#[test]
+fn exitstat_exec_tracking() {
+    let service = CgroupSample {
+        inode_number: Some(43),
+        ..Default::default()
+    };
+    let mut root = CgroupSample {
+        inode_number: Some(1),
+        children: Some(BTreeMap::from([("foo.service".to_owned(), service)])),
+        ..Default::default()
+    };
+    let cgroup_id_map = Arc::new(Mutex::new(CgroupIdMap::default()));
+    cgroup_id_map.lock().unwrap().update(&root);
+    let exec_counter = Arc::new(Mutex::new(ExecCounter::default()));
+    let execs = Arc::new(Mutex::new(PendingExecs::default()));
+    let buffer = Arc::new(Mutex::new(procfs::PidMap::default()));
+
+    let exec = |pid: i32, filename: &[u8], args: &[u8]| {
+        let args_len = args.len() as u32;
+        let data = event_bytes::<ExecEvent>(&[
+            (offset_of!(ExecEvent, pid), &pid.to_ne_bytes()),
+            (offset_of!(ExecEvent, cgroup_id), &43u64.to_ne_bytes()),
+            (offset_of!(ExecEvent, args_len), &args_len.to_ne_bytes()),
+            (offset_of!(ExecEvent, filename), filename),
+            (offset_of!(ExecEvent, args), args),
+        ]);
+        ExitstatDriver::handle_exec_event(&buffer, &cgroup_id_map, &execs, &exec_counter, &data);
+    };
+    let exit = |tid: i32| {
+        let data = event_bytes::<Event>(&[
+            (offset_of!(Event, meta.tid), &tid.to_ne_bytes()),
+            (offset_of!(Event, meta.cgroup_id), &43u64.to_ne_bytes()),
+        ]);
+        ExitstatDriver::handle_event(&buffer, &cgroup_id_map, &execs, &data);
+    };
+
+    // Exec handled before the exit
+    exec(100, b"/bin/ls\0", b"ls\0-l\0/tmp\0");
+    exit(100);
+    // Exit handled before the exec, with argv truncated
+    exit(101);
+    exec(101, b"/bin/sh\0", b"sh\0-c\0slee");
+    // Exit of a process that was not exec'd since below started
+    exit(102);
+
+    {
+        let buffer = buffer.lock().unwrap();
+        assert_eq!(
+            buffer[&100].cmdline_vec,
+            Some(vec!["ls".to_owned(), "-l".to_owned(), "/tmp".to_owned()])
+        );
+        assert_eq!(buffer[&100].exe_path.as_deref(), Some("/bin/ls"));
+        assert_eq!(
+            buffer[&101].cmdline_vec,
+            Some(vec!["sh".to_owned(), "-c".to_owned(), "slee".to_owned()])
+        );
+        assert_eq!(buffer[&101].exe_path.as_deref(), Some("/bin/sh"));
+        assert_eq!(buffer[&102].cmdline_vec, None);
+    }
+    assert!(execs.lock().unwrap().is_empty());
+
+    // Execs are counted in the cgroup and its ancestors
+    exec_counter.lock().unwrap().fill(&mut root);
+    assert_eq!(root.exec_count, Some(2));
+    assert_eq!(
+        root.children.as_ref().unwrap()["foo.service"].exec_count,
+        Some(2)
+    );
+}
+
+#[test]
+fn exitstat_pending_execs_eviction() {
+    let exec = |exe_path: &str| ExecInfo {
+        cmdline_vec: Vec::new(),
+        exe_path: exe_path.to_owned(),
+    };
+    let mut execs = PendingExecs::default();
+    for pid in 0..MAX_PENDING_EXECS as i32 {
+        execs.insert(pid, exec("/bin/true"));
+    }
+    // Exec of a pid again replaces its record, which is then the newest
+    execs.insert(0, exec("/bin/sh"));
+    assert_eq!(execs.len(), MAX_PENDING_EXECS);
+
+    // Only the oldest records are evicted when full
+    let next = MAX_PENDING_EXECS as i32;
+    execs.insert(next, exec("/bin/ls"));
+    execs.insert(next + 1, exec("/bin/ls"));
+    assert_eq!(execs.len(), MAX_PENDING_EXECS);
+    assert_eq!(execs.remove(1), None);
+    assert_eq!(execs.remove(2), None);
+    assert_eq!(execs.remove(0), Some(exec("/bin/sh")));
+    assert_eq!(execs.remove(3), Some(exec("/bin/true")));
+    assert_eq!(execs.remove(next + 1), Some(exec("/bin/ls")));
+    assert_eq!(execs.len(), MAX_PENDING_EXECS - 3);
+}
+
+#[test]
+fn run_delay_parse_entry() {
+    let key = 42u64.to_ne_bytes();
+    let mut value = Vec::new();
+    for bucket in 0..model::run_delay::NR_BUCKETS as u64 {
+        value.extend_from_slice(&bucket.to_ne_bytes());
+    }
+    let (cgroup_id, hist) = crate::run_delay::parse_entry(&key, &value).unwrap();
+    assert_eq!(cgroup_id, 42);
+    assert_eq!(hist.buckets.len(), model::run_delay::NR_BUCKETS);
+    assert_eq!(hist.buckets[3], 3);
+    assert_eq!(crate::run_delay::parse_entry(&key, &value[8..]), None);
+}
+
+#[test]
+fn run_delay_removed_cgroups() {
+    use model::run_delay::RunDelayHistogram;
+    use model::run_delay::SampleType;
+
+    let hist = |count: u64| RunDelayHistogram {
+        buckets: vec![count; model::run_delay::NR_BUCKETS],
+    };
+    // Cgroup 1 is idle, 2 is busy, 3 was removed and 4 is too new to be
+    // found by the cgroup walk
+    let previous = SampleType::from([(1, hist(1)), (2, hist(1)), (3, hist(1))]);
+    let hists = SampleType::from([(1, hist(1)), (2, hist(2)), (3, hist(1)), (4, hist(1))]);
+    let mut cgroup_id_map = CgroupIdMap::default();
+    cgroup_id_map.update(&CgroupSample {
+        inode_number: Some(1),
+        children: Some(BTreeMap::from([(
+            "foo.service".to_owned(),
+            CgroupSample {
+                inode_number: Some(2),
+                ..Default::default()
+            },
+        )])),
+        ..Default::default()
+    });
+    assert_eq!(
+        crate::run_delay::removed_cgroups(&hists, &previous, &cgroup_id_map),
+        vec![3]
+    );
+}
+
+#[test]
+fn config_check() {
+    let issues = config_check::check_below_config("cgroup_filter_out = '('\nfoo = 1\n");
+    let issues: Vec<_> = issues
+        .iter()
+        .map(|issue| (issue.key.as_str(), issue.is_error))
+        .collect();
+    assert_eq!(issues, vec![("foo", false), ("cgroup_filter_out", true)]);
+
+    let belowrc = r#"
+[view]
+default_view = "cgroup"
+no_such_key = 1
+
+[cmd]
+quit = "ctrl-x-y"
+no_such_cmd = "z"
+
+[dump.system]
+mine = ["datetime", "no_such_field"]
+"#;
+    let issues = config_check::check_belowrc(belowrc);
+    let issues: Vec<_> = issues
+        .iter()
+        .map(|issue| (issue.key.as_str(), issue.is_error))
+        .collect();
+    assert_eq!(
+        issues,
+        vec![
+            ("cmd.no_such_cmd", false),
+            ("cmd.quit", true),
+            ("dump.system.mine", true),
+            ("view.no_such_key", false),
+        ]
+    );
+    assert_eq!(
+        config_check::find_key_line(belowrc, "view.no_such_key"),
+        Some(4)
+    );
+    assert_eq!(config_check::find_key_line(belowrc, "cmd.quit"), Some(7));
+    assert_eq!(
+        config_check::find_key_line(belowrc, "dump.system.mine"),
+        Some(11)
+    );
+    // Falls back to the enclosing table
+    assert_eq!(
+        config_check::find_key_line(belowrc, "dump.system.other"),
+        Some(10)
+    );
+    assert_eq!(
+        config_check::find_key_line(belowrc, "view.no_such_tab"),
+        Some(2)
+    );
+}
+
+#[test]
+fn record_reload() {
+    // The flags of record apply to reloaded configs too
+    let mut current = config::BelowConfig::default();
+    crate::apply_record_flags(&mut current, true, false, true);
+    assert!(current.collectors.io_stat.is_enabled(false));
+    assert!(!current.collectors.exitstats.is_enabled(true));
+
+    let mut new = config::BelowConfig {
+        cgroup_filter_out: "user.slice.*".to_owned(),
+        retain_for_s: Some(3600),
+        ..Default::default()
+    };
+    crate::apply_record_flags(&mut new, true, false, true);
+    assert_eq!(
+        crate::check_reload(&current, &new).unwrap(),
+        vec!["cgroup_filter_out", "retain_for_s"]
+    );
+
+    new.store_dir = "/data/below".into();
+    new.collectors.exitstats.enabled = Some(true);
+    assert_eq!(
+        crate::check_reload(&current, &new).unwrap_err().to_string(),
+        "collectors.exitstats.enabled, store_dir cannot change without restarting below record"
+    );
+}
+
+/// Writes a snapshot of frames of hostname at timestamps to path
+fn write_snapshot(
+    path: &std::path::Path,
+    hostname: &str,
+    timestamps: impl IntoIterator<Item = u64>,
+) {
+    let logger = get_logger();
+    let dir = TempDir::with_prefix("below_snapshot_test.").expect("tempdir failed");
+    let mut store = store::StoreWriter::new(
+        logger,
+        &dir,
+        CompressionMode::ZstdDictionary(ChunkSizePo2(2)),
+        store::Format::Cbor,
+    )
+    .expect("Failed to create store");
+    let mut manifest = snapshot::SnapshotManifest::new();
+    for timestamp in timestamps {
+        let mut frame = DataFrame::default();
+        frame.sample.system.hostname = hostname.to_owned();
+        frame.sample.system.btrfs = Some(Default::default());
+        let frame_time = UNIX_EPOCH + Duration::from_secs(timestamp);
+        store.put(frame_time, &frame).expect("Failed to store data");
+        manifest.add_frame(frame_time, &frame);
+    }
+    drop(store);
+    snapshot::write(dir.path(), &manifest, path).expect("Failed to write snapshot");
+}
+
+#[test]
+fn snapshot_merge() {
+    let logger = get_logger();
+    let dir = TempDir::with_prefix("below_snapshot_merge_test.").expect("tempdir failed");
+    let (a, b, c) = (
+        dir.path().join("a.tar"),
+        dir.path().join("b.tar"),
+        dir.path().join("c.tar"),
+    );
+    // Shards are a day long, so these are all in one. The frames of b
+    // interleave with those of a and share 864004 and 864006 with it.
+    write_snapshot(&a, "host", (864000..864008).step_by(2));
+    write_snapshot(&b, "host", 864003..864008);
+
+    let unpacked = snapshot::UnpackedSnapshot::open(&logger, &a).expect("Failed to open");
+    assert!(unpacked.has_manifest);
+    assert_eq!(unpacked.manifest.nr_frames, 4);
+
+    // Passed out of order on purpose
+    snapshot::merge(logger.clone(), &[b.clone(), a.clone()], &c).expect("Failed to merge");
+    let merged = snapshot::UnpackedSnapshot::open(&logger, &c).expect("Failed to open");
+    assert_eq!(
+        (
+            merged.manifest.hostname.as_str(),
+            merged.manifest.begin,
+            merged.manifest.end,
+            merged.manifest.nr_frames
+        ),
+        ("host", 864000, 864007, 7)
+    );
+    assert_eq!(
+        merged.manifest.collectors,
+        ["btrfs".to_owned()].into_iter().collect()
+    );
+    let timestamps = store::LocalStore::new(logger.clone(), merged.store_dir.clone())
+        .get_sample_timestamps(None)
+        .expect("Failed to read timestamps")
+        .expect("Local store has timestamps");
+    assert_eq!(
+        timestamps,
+        [864000, 864002, 864003, 864004, 864005, 864006, 864007]
+            .map(|timestamp| UNIX_EPOCH + Duration::from_secs(timestamp))
+    );
+
+    write_snapshot(&b, "other", 864003..864008);
+    let err = snapshot::merge(logger, &[a, b], &c).expect_err("Merged different hosts");
+    assert!(err.to_string().contains("different hosts"), "{}", err);
+}
+
+#[test]
+fn snapshot_filter() {
+    let cgroup = |children: &[(&str, CgroupSample)]| CgroupSample {
+        children: Some(
+            children
+                .iter()
+                .map(|(name, child)| (name.to_string(), child.clone()))
+                .collect(),
+        ),
+        ..Default::default()
+    };
+    let mut sample = Sample {
+        cgroup: cgroup(&[
+            (
+                "system.slice",
+                cgroup(&[
+                    ("foo.service", cgroup(&[("inner", cgroup(&[]))])),
+                    ("bar.service", cgroup(&[])),
+                ]),
+            ),
+            ("user.slice", cgroup(&[])),
+        ]),
+        ..Default::default()
+    };
+    sample.gpus = Some(Default::default());
+    for (pid, cgroup, comm) in [
+        (1, "/system.slice/foo.service/inner", "kept"),
+        (2, "/system.slice/bar.service", "pruned-bar"),
+        (3, "/system.slice/foo.servicex", "pruned-foox"),
+    ] {
+        sample.processes.insert(
+            pid,
+            procfs::PidInfo {
+                cgroup: cgroup.to_owned(),
+                stat: procfs::PidStat {
+                    comm: Some(comm.to_owned()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let gpu_processes = &mut sample.gpus.as_mut().unwrap().gpu_map.processes;
+        gpu_processes.entry(pid).or_default().comm = Some(comm.to_owned());
+    }
+    sample.tc = Some(Default::default());
+    sample
+        .system
+        .slabinfo
+        .insert("dentry".to_owned(), Default::default());
+
+    let filter = snapshot::SnapshotFilter {
+        cgroup_re: Some(regex::Regex::new("^/system.slice/foo.service$").unwrap()),
+        drop: [snapshot::SnapshotPart::Slab].into_iter().collect(),
+        ..Default::default()
+    };
+    filter.apply(&mut sample);
+
+    // Ancestors and descendants of matching cgroups are kept
+    let root_children = sample.cgroup.children.as_ref().unwrap();
+    assert_eq!(
+        root_children.keys().collect::<Vec<_>>(),
+        vec!["system.slice"]
+    );
+    let system_children = root_children["system.slice"].children.as_ref().unwrap();
+    assert_eq!(
+        system_children.keys().collect::<Vec<_>>(),
+        vec!["foo.service"]
+    );
+    assert!(
+        system_children["foo.service"].children.as_ref().unwrap()["inner"]
+            .children
+            .is_some()
+    );
+    assert_eq!(sample.processes.keys().collect::<Vec<_>>(), vec![&1]);
+    let gpu_pids = &sample.gpus.as_ref().unwrap().gpu_map.processes;
+    assert_eq!(gpu_pids.keys().collect::<Vec<_>>(), vec![&1]);
+    // Nothing of the processes pruned remains anywhere
+    let json = serde_json::to_string(&sample).unwrap();
+    assert!(json.contains("kept"));
+    assert!(!json.contains("pruned"), "{}", json);
+    assert!(sample.system.slabinfo.is_empty());
+    // tc cannot be filtered by cgroup
+    assert_eq!(sample.tc, None);
+
+    let mut manifest = snapshot::SnapshotManifest::new();
+    filter.describe(&mut manifest);
+    assert_eq!(
+        manifest.cgroup_filter.as_deref(),
+        Some("^/system.slice/foo.service$")
+    );
+    assert_eq!(
+        manifest.dropped,
+        ["btrfs", "resctrl", "slab", "tc"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect()
+    );
+    assert!(!manifest.redacted);
+}
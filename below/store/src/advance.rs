@@ -313,6 +313,90 @@ impl<FrameType, ModelType> Advance<FrameType, ModelType> {
         model
     }
 
+    /// Move forward by a whole step. Unlike advance(), the Model is built
+    /// from the currently displayed sample and the last sample within `step`
+    /// of it, so rates are averaged across the step instead of the last
+    /// sample interval. If there is no sample within the step (e.g. a gap in
+    /// recording), the next available sample is used instead.
+    pub fn advance_step(&mut self, step: Duration) -> Option<ModelType> {
+        // Re-anchor on the displayed sample unless it is already cached. When
+        // moving backward, the cached sample is the one before the displayed.
+        if self.current_direction != Direction::Forward || self.cached_sample.is_none() {
+            let timestamp = match self.cached_sample {
+                Some(_) => self.target_timestamp + Duration::from_secs(1),
+                None => self.target_timestamp,
+            };
+            let (timestamp, sample) =
+                self.store
+                    .extract_sample_and_log(timestamp, Direction::Forward, &self.logger)?;
+            self.cached_sample = Some(sample);
+            self.target_timestamp = timestamp;
+            self.current_direction = Direction::Forward;
+        }
+
+        let older_timestamp = self.target_timestamp;
+        let (newer_timestamp, newer_sample) = match self.store.extract_sample_and_log(
+            older_timestamp + step,
+            Direction::Reverse,
+            &self.logger,
+        ) {
+            Some((timestamp, sample)) if timestamp > older_timestamp => (timestamp, sample),
+            _ => self.store.extract_sample_and_log(
+                older_timestamp + Duration::from_secs(1),
+                Direction::Forward,
+                &self.logger,
+            )?,
+        };
+
+        let sample_package = SamplePackage::new(
+            self.cached_sample.take(),
+            older_timestamp,
+            newer_sample,
+            newer_timestamp,
+        );
+        let model = self.store.to_model(&sample_package);
+        self.cached_sample = Some(sample_package.newer_sample);
+        self.target_timestamp = newer_timestamp;
+
+        model
+    }
+
+    /// Up to `count` Models ending with the one of the sample at or before
+    /// `timestamp`, oldest first. Unlike advance(), the current position is
+    /// left unchanged.
+    pub fn history(&mut self, timestamp: SystemTime, count: usize) -> Vec<ModelType>
+    where
+        FrameType: Clone,
+    {
+        let mut models = Vec::new();
+        let mut newer =
+            self.store
+                .extract_sample_and_log(timestamp, Direction::Reverse, &self.logger);
+        while let Some((newer_timestamp, newer_sample)) = newer {
+            if models.len() == count {
+                break;
+            }
+            let older = self.store.extract_sample_and_log(
+                newer_timestamp - Duration::from_secs(1),
+                Direction::Reverse,
+                &self.logger,
+            );
+            let sample_package = match &older {
+                Some((older_timestamp, older_sample)) => SamplePackage::new(
+                    Some(older_sample.clone()),
+                    *older_timestamp,
+                    newer_sample,
+                    newer_timestamp,
+                ),
+                None => SamplePackage::new(None, newer_timestamp, newer_sample, newer_timestamp),
+            };
+            models.extend(self.store.to_model(&sample_package));
+            newer = older;
+        }
+        models.reverse();
+        models
+    }
+
     /// Syntactic sugar for getting lastest sample
     pub fn get_latest_sample(&mut self) -> Option<ModelType> {
         self.jump_sample_to(SystemTime::now())
@@ -333,6 +417,30 @@ impl<FrameType, ModelType> Advance<FrameType, ModelType> {
         self.jump_sample_to(self.target_timestamp - gap)
     }
 
+    /// Timestamps of the samples in the store newer than since, or of all
+    /// samples if since is None, oldest first. None if the store cannot list
+    /// them, e.g. a remote store.
+    pub fn get_sample_timestamps(&mut self, since: Option<SystemTime>) -> Option<Vec<SystemTime>> {
+        match self.store.get_sample_timestamps(since) {
+            Ok(timestamps) => timestamps,
+            Err(e) => {
+                error!(
+                    self.logger,
+                    "{:#}",
+                    e.context("Failed to list samples in store")
+                );
+                None
+            }
+        }
+    }
+
+    /// The sample at timestamp, or the first one after it, without moving
+    /// the current position.
+    pub fn peek_sample(&mut self, timestamp: SystemTime) -> Option<(SystemTime, FrameType)> {
+        self.store
+            .extract_sample_and_log(timestamp, Direction::Forward, &self.logger)
+    }
+
     // Convenience function will be used by dump and scuba dump
     pub fn get_next_ts(&self) -> SystemTime {
         // timestamp for initial advance if initialize didn't setup cached_sample
@@ -724,6 +832,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn advance_test_advance_step() {
+        // Samples: [3, 10, 20, 50]
+        let mut advance = get_advance_with_fake_store(3);
+        advance.initialize();
+
+        let step = Duration::from_secs(10);
+        // Last sample within the step
+        assert_eq!(advance.advance_step(step), Some("3_10_10_7".into()));
+        // Sample exactly at the end of the step
+        assert_eq!(advance.advance_step(step), Some("10_20_20_10".into()));
+        // No sample within the step, falls back to the next sample
+        assert_eq!(advance.advance_step(step), Some("20_50_50_30".into()));
+        // Reached the end, nothing changes
+        assert_eq!(advance.advance_step(step), None);
+        assert_eq!(advance.cached_sample, Some(50));
+        assert_eq!(advance.target_timestamp, util::get_system_time(50));
+
+        // A step covering multiple samples skips the ones in between
+        let mut advance = get_advance_with_fake_store(3);
+        advance.initialize();
+        assert_eq!(
+            advance.advance_step(Duration::from_secs(30)),
+            Some("3_20_20_17".into())
+        );
+
+        // After moving backward, steps start from the displayed sample (20)
+        // rather than the cached one (10)
+        let mut advance = get_advance_with_fake_store(3);
+        advance.initialize();
+        advance.jump_sample_to(util::get_system_time(50));
+        assert_eq!(
+            advance.advance(Direction::Reverse),
+            Some("10_20_20_10".into())
+        );
+        assert_eq!(advance.advance_step(step), Some("20_50_50_30".into()));
+    }
+
+    #[test]
+    fn advance_test_history() {
+        // Samples: [3, 10, 20, 50]
+        let mut advance = get_advance_with_fake_store(3);
+        advance.initialize();
+        advance.jump_sample_to(util::get_system_time(20));
+
+        assert_eq!(
+            advance.history(util::get_system_time(20), 2),
+            vec!["3_10_10_7".to_owned(), "10_20_20_10".to_owned()]
+        );
+        // Between samples, ends with the sample before
+        assert_eq!(
+            advance.history(util::get_system_time(49), 10),
+            vec![
+                "3_3".to_owned(),
+                "3_10_10_7".to_owned(),
+                "10_20_20_10".to_owned()
+            ]
+        );
+        assert_eq!(
+            advance.history(util::get_system_time(2), 10),
+            Vec::<String>::new()
+        );
+        // The position is unchanged
+        assert_eq!(
+            advance.advance(Direction::Forward),
+            Some("20_50_50_30".into())
+        );
+    }
+
+    #[test]
+    fn advance_test_peek_sample() {
+        // Samples: [3, 10, 20, 50]
+        let mut advance = get_advance_with_fake_store(3);
+        advance.initialize();
+        advance.jump_sample_to(util::get_system_time(20));
+
+        assert_eq!(
+            advance.peek_sample(util::get_system_time(10)),
+            Some((util::get_system_time(10), 10))
+        );
+        assert_eq!(
+            advance.peek_sample(util::get_system_time(30)),
+            Some((util::get_system_time(50), 50))
+        );
+        assert_eq!(advance.peek_sample(util::get_system_time(60)), None);
+        // The position is unchanged
+        assert_eq!(
+            advance.advance(Direction::Forward),
+            Some("20_50_50_30".into())
+        );
+    }
+
     #[test]
     fn advance_test_jump_sample_to() {
         // Samples: [3, 10, 20, 50]
@@ -516,6 +516,37 @@ impl StoreCursor {
     }
 }
 
+impl StoreCursor {
+    /// Timestamps of the samples in the store newer than since, oldest first.
+    /// Only the index files of the shards that may contain such samples are
+    /// read, so this is much cheaper than reading the samples. The position of
+    /// this cursor is unchanged.
+    pub fn get_timestamps(&self, since: Option<u64>) -> Result<Vec<u64>> {
+        let mut cursor = Self::new(self.logger.clone(), self.path.clone());
+        let mut timestamps = Vec::new();
+        for entry in get_index_files(&self.path)? {
+            let shard = match entry.split('_').nth(1).map(|v| v.parse::<u64>()) {
+                Some(Ok(shard)) => shard,
+                _ => continue,
+            };
+            if since.map_or(false, |since| shard + SHARD_TIME <= since) {
+                continue;
+            }
+            cursor.shard = Some(shard);
+            cursor.index_mmap = cursor.get_mmap(StoreFile::Index, shard)?;
+            let len = cursor.index_mmap.as_ref().map_or(0, |m| m.len());
+            timestamps.extend(
+                (0..len)
+                    .step_by(INDEX_ENTRY_SIZE)
+                    .filter_map(|offset| cursor.get_index_entry_at(offset))
+                    .map(|index_entry| index_entry.timestamp)
+                    .filter(|ts| since.map_or(true, |since| *ts > since)),
+            );
+        }
+        Ok(timestamps)
+    }
+}
+
 /// Offset of a StoreCursor.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct StoreOffset {
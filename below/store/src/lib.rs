@@ -754,6 +754,16 @@ pub trait Store {
         timestamp: SystemTime,
         direction: Direction,
     ) -> Result<Option<(SystemTime, Self::SampleType)>>;
+
+    /// Return the timestamps of the samples newer than since, or of all
+    /// samples if since is None, oldest first, without reading the samples.
+    /// Stores that cannot list them cheaply return None.
+    fn get_sample_timestamps(
+        &mut self,
+        _since: Option<SystemTime>,
+    ) -> Result<Option<Vec<SystemTime>>> {
+        Ok(None)
+    }
 }
 
 pub struct LocalStore {
@@ -791,6 +801,19 @@ impl Store for LocalStore {
         self.store_cursor
             .get_next(&get_unix_timestamp(timestamp), direction)
     }
+
+    fn get_sample_timestamps(
+        &mut self,
+        since: Option<SystemTime>,
+    ) -> Result<Option<Vec<SystemTime>>> {
+        Ok(Some(
+            self.store_cursor
+                .get_timestamps(since.map(get_unix_timestamp))?
+                .into_iter()
+                .map(|ts| std::time::UNIX_EPOCH + std::time::Duration::from_secs(ts))
+                .collect(),
+        ))
+    }
 }
 
 impl Store for RemoteStore {
@@ -1767,4 +1790,45 @@ mod tests {
         assert_ts!(frame.0, ts);
         assert_eq!(frame.1.sample.cgroup.memory_current, Some(333));
     }
+
+    store_test!(get_sample_timestamps, _get_sample_timestamps);
+    fn _get_sample_timestamps(compression_mode: CompressionMode, format: Format) {
+        let dir = TempDir::with_prefix("below_store_test.").expect("tempdir failed");
+        let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
+        // Samples across two shards, with a gap
+        let expected: Vec<SystemTime> = [0, 5, 10, 100, SHARD_TIME, SHARD_TIME + 5]
+            .iter()
+            .map(|offset| ts + Duration::from_secs(*offset))
+            .collect();
+        {
+            let mut writer = StoreWriter::new(get_logger(), &dir, compression_mode, format)
+                .expect("Failed to create store");
+            for timestamp in &expected {
+                writer
+                    .put(*timestamp, &DataFrame::default())
+                    .expect("Failed to store data");
+            }
+        }
+
+        let mut store = LocalStore::new(get_logger(), dir.path().to_path_buf());
+        let timestamps = store
+            .get_sample_timestamps(None)
+            .expect("Failed to list samples")
+            .expect("Local store should list samples");
+        assert_eq!(timestamps, expected);
+        // Only samples newer than since, including those of later shards
+        for (since, skip) in [(expected[2], 3), (expected[3], 4), (expected[5], 6)] {
+            let timestamps = store
+                .get_sample_timestamps(Some(since))
+                .expect("Failed to list samples")
+                .expect("Local store should list samples");
+            assert_eq!(timestamps, expected[skip..]);
+        }
+        // Reading samples still starts from the beginning
+        let (first, _) = store
+            .get_sample_at_timestamp(ts, Direction::Forward)
+            .expect("Failed to read sample")
+            .expect("Did not find stored sample");
+        assert_ts!(first, ts);
+    }
 }
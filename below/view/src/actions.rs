@@ -0,0 +1,216 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use cursive::event::Key;
+use cursive::views::Dialog;
+use cursive::views::OnEventView;
+use cursive::Cursive;
+use slog::error;
+use slog::info;
+
+use crate::refresh;
+use crate::ViewMode;
+use crate::ViewState;
+
+/// Prefix of the key of cgroup rows whose cgroup was recreated since the
+/// previous sample.
+const RECREATED_PREFIX: &str = "[RECREATED] ";
+
+/// Action on the live host requested from the command palette.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Kill { pid: i32, signal: i32 },
+    Renice { pid: i32, nice: i32 },
+    Freeze { cgroup: String, frozen: bool },
+    MoveCgroup { pid: i32, cgroup: String },
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Kill { pid, signal } => write!(f, "Send signal {} to pid {}", signal, pid),
+            Action::Renice { pid, nice } => write!(f, "Renice pid {} to {}", pid, nice),
+            Action::Freeze { cgroup, frozen } => write!(
+                f,
+                "{} cgroup {}",
+                if *frozen { "Freeze" } else { "Thaw" },
+                cgroup
+            ),
+            Action::MoveCgroup { pid, cgroup } => {
+                write!(f, "Move pid {} to cgroup {}", pid, cgroup)
+            }
+        }
+    }
+}
+
+/// Runs actions against the host below is monitoring. Only set up by live
+/// local mode, as replay and remote data cannot be acted upon.
+pub struct Actions {
+    logger: slog::Logger,
+    proc_root: PathBuf,
+    cgroup_root: PathBuf,
+}
+
+impl Actions {
+    pub fn new(logger: slog::Logger, proc_root: PathBuf, cgroup_root: PathBuf) -> Self {
+        Self {
+            logger,
+            proc_root,
+            cgroup_root,
+        }
+    }
+
+    /// Run action and log the outcome to the command palette.
+    pub fn run(&self, action: &Action) -> Result<()> {
+        let res = self.run_impl(action);
+        match &res {
+            Ok(()) => info!(self.logger, #"V", "{}: done", action),
+            Err(e) => error!(self.logger, #"V", "{}: {:#}", action, e),
+        }
+        res
+    }
+
+    fn run_impl(&self, action: &Action) -> Result<()> {
+        match action {
+            Action::Kill { pid, signal } => {
+                self.check_pid(*pid)?;
+                if unsafe { libc::kill(*pid, *signal) } != 0 {
+                    return Err(std::io::Error::last_os_error().into());
+                }
+            }
+            Action::Renice { pid, nice } => {
+                self.check_pid(*pid)?;
+                if unsafe { libc::setpriority(libc::PRIO_PROCESS, *pid as libc::id_t, *nice) } != 0
+                {
+                    return Err(std::io::Error::last_os_error().into());
+                }
+            }
+            Action::Freeze { cgroup, frozen } => {
+                let path = self.cgroup_path(cgroup)?.join("cgroup.freeze");
+                write_file(&path, if *frozen { "1" } else { "0" })?;
+            }
+            Action::MoveCgroup { pid, cgroup } => {
+                self.check_pid(*pid)?;
+                let path = self.cgroup_path(cgroup)?.join("cgroup.procs");
+                write_file(&path, &pid.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_pid(&self, pid: i32) -> Result<()> {
+        // Non-positive pids address process groups or every process.
+        if pid <= 0 || !self.proc_root.join(pid.to_string()).is_dir() {
+            bail!("No such process");
+        }
+        Ok(())
+    }
+
+    /// Directory of a cgroup given its full path, e.g. /system.slice.
+    fn cgroup_path(&self, cgroup: &str) -> Result<PathBuf> {
+        let relative = Path::new(cgroup.trim_start_matches('/'));
+        if relative.as_os_str().is_empty() {
+            bail!("Not supported on the root cgroup");
+        }
+        // Keep writes within cgroupfs, e.g. no ../../tmp/x
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            bail!("Invalid cgroup path: {}", cgroup);
+        }
+        let path = self.cgroup_root.join(relative);
+        if !path.is_dir() {
+            bail!("No such cgroup");
+        }
+        Ok(path)
+    }
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Parse a signal given as a number or a name with or without the SIG
+/// prefix, e.g. 9, KILL or SIGKILL.
+pub fn parse_signal(signal: &str) -> Result<i32> {
+    if let Ok(num) = signal.parse::<i32>() {
+        return Ok(num);
+    }
+    let upper = signal.to_uppercase();
+    Ok(match upper.strip_prefix("SIG").unwrap_or(&upper) {
+        "HUP" => libc::SIGHUP,
+        "INT" => libc::SIGINT,
+        "QUIT" => libc::SIGQUIT,
+        "KILL" => libc::SIGKILL,
+        "USR1" => libc::SIGUSR1,
+        "USR2" => libc::SIGUSR2,
+        "TERM" => libc::SIGTERM,
+        "CONT" => libc::SIGCONT,
+        "STOP" => libc::SIGSTOP,
+        _ => return Err(anyhow!("Unknown signal: {}", signal)),
+    })
+}
+
+/// Full path of the cgroup of a cgroup view row key.
+pub fn cgroup_from_key(key: &str) -> &str {
+    key.strip_prefix(RECREATED_PREFIX).unwrap_or(key)
+}
+
+/// Ask the user to confirm action, then run it. Refused unless actions were
+/// enabled for this session, i.e. in live local mode, and the view is not
+/// paused, as the pid shown may have since exited or been reused.
+pub fn confirm(c: &mut Cursive, action: Action) {
+    let state = c.user_data::<ViewState>().expect("No user data set");
+    let actions = match (&state.mode, &state.actions) {
+        (ViewMode::Live(_), Some(actions)) => actions.clone(),
+        (ViewMode::Pause(_), Some(_)) => {
+            view_warn!(c, "Actions are not available while paused, resume first");
+            return;
+        }
+        _ => {
+            view_warn!(c, "Actions are only available in live local mode");
+            return;
+        }
+    };
+    c.add_layer(confirm_dialog(actions, action));
+}
+
+fn confirm_dialog(actions: Rc<Actions>, action: Action) -> OnEventView<Dialog> {
+    OnEventView::new(
+        Dialog::text(format!("{}?", action))
+            .title("Confirm")
+            .padding_lrtb(1, 1, 1, 0)
+            .dismiss_button("Cancel")
+            .button("Confirm", move |c| {
+                c.pop_layer();
+                // Outcome is logged to the command palette
+                let _ = actions.run(&action);
+                refresh(c);
+            }),
+    )
+    .on_event(Key::Esc, |s| {
+        s.pop_layer();
+    })
+}
@@ -162,8 +162,16 @@ impl CgroupTab {
         state: &CgroupState,
         offset: Option<usize>,
     ) -> Vec<(StyledString, String)> {
-        let filtered_set = if let Some((field_id, filter)) = &state.filter_info {
-            Some(calculate_filtered_set(&state.get_model(), field_id, filter))
+        let filtered_set = if state.filter_info.is_some() || !state.filter_exprs.is_empty() {
+            Some(calculate_filtered_set(&state.get_model(), &|cgroup| {
+                let matches_filter = match &state.filter_info {
+                    Some((field_id, filter)) => cgroup
+                        .query(field_id)
+                        .map_or(false, |value| value.to_string().contains(filter)),
+                    None => true,
+                };
+                matches_filter && state.filter_exprs.iter().all(|expr| expr.eval(cgroup))
+            }))
         } else {
             None
         };
@@ -173,27 +181,15 @@ impl CgroupTab {
     }
 }
 
-/// Returns a set of full cgroup paths that should be filtered by the filter string.
+/// Returns a set of full cgroup paths that should be kept by the filter.
 ///
 /// Note that this algorithm recursively whitelists parents of cgroups that are
 /// whitelisted. The reason for this is because cgroups are inherently tree-like
 /// and displaying a lone cgroup without its ancestors doesn't make much sense.
 pub fn calculate_filtered_set(
     cgroup: &CgroupModel,
-    field_id: &SingleCgroupModelFieldId,
-    filter: &str,
+    matches: &dyn Fn(&SingleCgroupModel) -> bool,
 ) -> HashSet<String> {
-    fn field_val_matches_filter(
-        cgroup: &CgroupModel,
-        field_id: &SingleCgroupModelFieldId,
-        filter: &str,
-    ) -> bool {
-        match cgroup.data.query(field_id) {
-            None => false,
-            Some(value) => value.to_string().contains(filter),
-        }
-    }
-
     // insert all descendents of cgroup into set
     fn insert_cgroup_and_descendents(set: &mut HashSet<String>, cgroup: &CgroupModel) {
         set.insert(cgroup.data.full_path.clone());
@@ -204,11 +200,10 @@ pub fn calculate_filtered_set(
 
     fn should_keep(
         cgroup: &CgroupModel,
-        field_id: &SingleCgroupModelFieldId,
-        filter: &str,
+        matches: &dyn Fn(&SingleCgroupModel) -> bool,
         set: &mut HashSet<String>,
     ) -> bool {
-        let match_filter = field_val_matches_filter(cgroup, field_id, filter);
+        let match_filter = matches(&cgroup.data);
         if match_filter {
             insert_cgroup_and_descendents(set, cgroup);
             return match_filter;
@@ -217,7 +212,7 @@ pub fn calculate_filtered_set(
         let mut keep_cgroup = false;
         for child in &cgroup.children {
             // keep children that match filter and children of cgroups that match filter
-            if should_keep(child, field_id, filter, set) {
+            if should_keep(child, matches, set) {
                 // keep parent cgroup if child isn't filtered out
                 keep_cgroup = true;
             }
@@ -229,7 +224,7 @@ pub fn calculate_filtered_set(
         keep_cgroup
     }
     let mut set = HashSet::new();
-    should_keep(cgroup, field_id, filter, &mut set);
+    should_keep(cgroup, matches, &mut set);
     set
 }
 
@@ -237,6 +232,8 @@ pub mod default_tabs {
     use base_render::RenderConfigBuilder as Rc;
     use model::CgroupCpuModelFieldId::NrPeriodsPerSec;
     use model::CgroupCpuModelFieldId::NrThrottledPerSec;
+    use model::CgroupCpuModelFieldId::RunDelayUsecP50;
+    use model::CgroupCpuModelFieldId::RunDelayUsecP99;
     use model::CgroupCpuModelFieldId::SystemPct;
     use model::CgroupCpuModelFieldId::ThrottledPct;
     use model::CgroupCpuModelFieldId::UsagePct;
@@ -297,6 +294,7 @@ pub mod default_tabs {
     use model::CgroupMemoryModelFieldId::WorkingsetRestoreFile;
     use model::CgroupMemoryModelFieldId::Zswap;
     use model::CgroupMemoryModelFieldId::Zswapped;
+    use model::CgroupPidsModelFieldId::ExecPerSec;
     use model::CgroupPidsModelFieldId::TidsCurrent;
     use model::CgroupPressureModelFieldId::CpuFullPct;
     use model::CgroupPressureModelFieldId::CpuSomePct;
@@ -319,7 +317,9 @@ pub mod default_tabs {
     use model::CgroupPropertiesFieldId::TidsMax;
     use model::CgroupStatModelFieldId::NrDescendants;
     use model::CgroupStatModelFieldId::NrDyingDescendants;
+    use model::ContainerModelFieldId;
     use model::SingleCgroupModelFieldId::CgroupStat;
+    use model::SingleCgroupModelFieldId::Container;
     use model::SingleCgroupModelFieldId::Cpu;
     use model::SingleCgroupModelFieldId::Io;
     use model::SingleCgroupModelFieldId::Mem;
@@ -342,6 +342,7 @@ pub mod default_tabs {
             ViewItem::from_default(CgroupStat(NrDescendants)),
             ViewItem::from_default(CgroupStat(NrDyingDescendants)),
             ViewItem::from_default(Pids(TidsCurrent)),
+            ViewItem::from_default(Pids(ExecPerSec)),
         ]
     }
 
@@ -353,6 +354,8 @@ pub mod default_tabs {
             ViewItem::from_default(Cpu(NrPeriodsPerSec)),
             ViewItem::from_default(Cpu(NrThrottledPerSec)),
             ViewItem::from_default(Cpu(ThrottledPct)),
+            ViewItem::from_default(Cpu(RunDelayUsecP50)),
+            ViewItem::from_default(Cpu(RunDelayUsecP99)),
         ]
     }
 
@@ -448,6 +451,9 @@ pub mod default_tabs {
             ViewItem::from_default(Props(CpusetCpusEffective)),
             ViewItem::from_default(Props(TidsMax)),
             ViewItem::from_default(Props(CgroupControllers)),
+            ViewItem::from_default(Container(ContainerModelFieldId::Runtime)),
+            ViewItem::from_default(Container(ContainerModelFieldId::Name)),
+            ViewItem::from_default(Container(ContainerModelFieldId::PodUid)),
         ]
     }
 }
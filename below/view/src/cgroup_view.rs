@@ -19,26 +19,32 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::rc::Rc;
 
+use anyhow::Result;
 use cursive::utils::markup::StyledString;
 use cursive::view::Nameable;
 use cursive::views::NamedView;
 use cursive::views::SelectView;
 use cursive::views::ViewRef;
 use cursive::Cursive;
+use model::filter_expr::FilterExpr;
 use model::CgroupCpuModelFieldId;
 use model::CgroupIoModelFieldId;
 use model::CgroupMemoryModelFieldId;
 use model::CgroupModel;
+use model::CgroupPath;
 use model::Queriable;
+use model::QueriableContainer;
 use model::SingleCgroupModelFieldId;
 
 use crate::cgroup_tabs::default_tabs;
 use crate::cgroup_tabs::CgroupTab;
+use crate::graph_popup::GraphQuery;
 use crate::render::ViewItem;
 use crate::stats_view::ColumnTitles;
 use crate::stats_view::StateCommon;
 use crate::stats_view::StatsView;
 use crate::stats_view::ViewBridge;
+use crate::viewrc::DefaultFrontView;
 use crate::ViewState;
 
 pub type ViewType = StatsView<CgroupView>;
@@ -54,6 +60,8 @@ pub struct CgroupState {
     // moved to the cgroup
     pub cgroup_to_focus: Option<String>,
     pub filter_info: Option<(SingleCgroupModelFieldId, String)>,
+    // Filter expressions every row must match, e.g. cpu.usage_pct > 10
+    pub filter_exprs: Vec<FilterExpr<SingleCgroupModelFieldId>>,
     pub sort_order: Option<SingleCgroupModelFieldId>,
     pub sort_tags: HashMap<String, Vec<ViewItem<SingleCgroupModelFieldId>>>,
     pub reverse: bool,
@@ -105,6 +113,27 @@ impl StateCommon for CgroupState {
         true
     }
 
+    fn add_filter_expr(&mut self, expr: &str) -> Result<()> {
+        self.filter_exprs.push(expr.parse()?);
+        Ok(())
+    }
+
+    fn set_filter_exprs(&mut self, exprs: &[String]) -> Result<()> {
+        self.filter_exprs = exprs
+            .iter()
+            .map(|expr| expr.parse())
+            .collect::<Result<_>>()?;
+        Ok(())
+    }
+
+    fn get_filter_exprs(&self) -> Vec<String> {
+        self.filter_exprs.iter().map(|e| e.to_string()).collect()
+    }
+
+    fn clear_filter_exprs(&mut self) {
+        self.filter_exprs.clear();
+    }
+
     fn set_sort_tag(&mut self, sort_order: Self::TagType, reverse: &mut bool) -> bool {
         let sort_order = Some(sort_order);
         if self.sort_order == sort_order {
@@ -130,6 +159,24 @@ impl StateCommon for CgroupState {
         }
     }
 
+    fn get_graph_query(&self, tab: &str, idx: usize, key: &String) -> Option<GraphQuery> {
+        if idx == 0 {
+            return None;
+        }
+        let path = CgroupPath {
+            path: key
+                .split('/')
+                .filter(|name| !name.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        };
+        Some(GraphQuery::new(
+            self.get_tag_from_tab_idx(tab, idx),
+            key,
+            move |model| model.cgroup.get_item(&path),
+        ))
+    }
+
     fn get_model(&self) -> Ref<Self::ModelType> {
         self.model.borrow()
     }
@@ -151,6 +198,7 @@ impl StateCommon for CgroupState {
             current_selected_cgroup: "<root>".into(),
             cgroup_to_focus: None,
             filter_info: None,
+            filter_exprs: Vec::new(),
             sort_order: None,
             sort_tags,
             reverse: false,
@@ -247,7 +295,7 @@ impl CgroupView {
             width: user_data.viewrc.cgroup_name_width,
             ..Default::default()
         };
-        let tabs = vec![
+        let mut tabs = vec![
             "General".into(),
             "CPU".into(),
             "Mem".into(),
@@ -296,6 +344,16 @@ impl CgroupView {
         if user_data.viewrc.collapse_cgroups == Some(true) {
             cgroup_state.collapse_all_top_level_cgroup = true;
         }
+        for (name, items) in user_data.viewrc.tabs_for(DefaultFrontView::Cgroup) {
+            tabs.push(name.clone());
+            tabs_map.insert(
+                name.clone(),
+                CgroupView {
+                    tab: CgroupTab::new(items.clone(), &cgroup_name_config),
+                },
+            );
+            cgroup_state.sort_tags.insert(name, items);
+        }
         StatsView::new(
             "Cgroup",
             tabs,
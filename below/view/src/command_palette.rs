@@ -54,7 +54,10 @@ enum CPMode {
 pub struct CommandPalette {
     content: String,
     filter_info: Option<(String, String)>,
+    filter_exprs: Vec<String>,
     fold: bool,
+    tree: bool,
+    search: Option<String>,
     mode: CPMode,
     cmd_view: RefCell<EditView>,
     cmd_controllers: Rc<RefCell<HashMap<&'static str, Controllers>>>,
@@ -77,12 +80,30 @@ impl View for CommandPalette {
             printer.print((max_x, 0), &output);
         }
 
+        if !self.filter_exprs.is_empty() {
+            let output = format!("| Filters: {:.40} |", self.filter_exprs.join(" && "));
+            max_x -= output.len();
+            printer.print((max_x, 0), &output);
+        }
+
         if self.fold {
             let text = "| Fold |";
             max_x -= text.len();
             printer.print((max_x, 0), text);
         }
 
+        if self.tree {
+            let text = "| Tree |";
+            max_x -= text.len();
+            printer.print((max_x, 0), text);
+        }
+
+        if let Some(search) = &self.search {
+            let output = format!("| Search: {:>10.10} |", search);
+            max_x -= output.len();
+            printer.print((max_x, 0), &output);
+        }
+
         match self.mode {
             CPMode::Command => {
                 printer.print((0, 1), ":");
@@ -135,7 +156,10 @@ impl CommandPalette {
         Self {
             content: content.into(),
             filter_info: None,
+            filter_exprs: Vec::new(),
             fold: false,
+            tree: false,
+            search: None,
             mode: CPMode::Info,
             cmd_view: RefCell::new(
                 EditView::new()
@@ -269,10 +293,22 @@ impl CommandPalette {
         self.filter_info = filter_info;
     }
 
+    pub fn set_filter_exprs(&mut self, filter_exprs: Vec<String>) {
+        self.filter_exprs = filter_exprs;
+    }
+
     pub fn toggle_fold(&mut self) {
         self.fold = !self.fold;
     }
 
+    pub fn toggle_tree(&mut self) {
+        self.tree = !self.tree;
+    }
+
+    pub fn set_search(&mut self, search: Option<String>) {
+        self.search = search;
+    }
+
     fn print_info(&self, printer: &Printer, pos: Vec2, idx: usize) {
         if idx + printer.size.x > self.content.len() {
             printer.print(pos, &self.content[idx..]);
@@ -0,0 +1,177 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use base_render::HasRenderConfig;
+use chrono::DateTime;
+use chrono::Local;
+use cursive::event::Key;
+use cursive::view::Scrollable;
+use cursive::view::View;
+use cursive::views::Dialog;
+use cursive::views::LinearLayout;
+use cursive::views::OnEventView;
+use cursive::views::SelectView;
+use cursive::views::TextView;
+use model::diff::cgroup_entities;
+use model::diff::diff_queriables;
+use model::diff::process_entities;
+use model::diff::sort_diffs;
+use model::diff::DiffOrder;
+use model::Field;
+use model::Model;
+use model::SystemModelFieldId;
+
+use crate::MainViewState;
+
+/// Number of changes shown in the popup.
+const MAX_ROWS: usize = 200;
+
+fn get_title() -> String {
+    format!(
+        "{:<32} {:>12} {:>12} {:>12} {:>8} {}",
+        "Field", "Marked", "Current", "Delta", "Delta%", "Entity"
+    )
+}
+
+/// Most significant relative changes of field_ids between the marked and
+/// the current entities.
+fn get_rows<Q: HasRenderConfig>(
+    marked: &BTreeMap<String, &Q>,
+    current: &BTreeMap<String, &Q>,
+    field_ids: &[Q::FieldId],
+) -> Vec<String>
+where
+    Q::FieldId: Clone + ToString,
+{
+    let mut diffs = diff_queriables(marked, current, field_ids);
+    sort_diffs(&mut diffs, DiffOrder::Rel);
+    diffs.truncate(MAX_ROWS);
+    diffs
+        .iter()
+        .map(|diff| {
+            let rc = Q::get_render_config(&diff.field_id);
+            let render = |field: &Option<Field>| match field {
+                Some(_) => rc.render(field.clone(), false),
+                None => "-".to_owned(),
+            };
+            format!(
+                "{:<32.32} {:>12} {:>12} {:>12} {:>8} {}",
+                diff.field_id.to_string(),
+                render(&diff.a),
+                render(&diff.b),
+                diff.delta().map_or("-".to_owned(), |delta| {
+                    rc.render_delta(delta, diff.b.as_ref().or(diff.a.as_ref()), false)
+                }),
+                diff.relative_string(),
+                diff.entity,
+            )
+        })
+        .collect()
+}
+
+fn cgroup_field_ids() -> Vec<model::SingleCgroupModelFieldId> {
+    use crate::cgroup_tabs::default_tabs::*;
+    let mut field_ids = Vec::new();
+    for item in get_general_items()
+        .into_iter()
+        .chain(get_cpu_items())
+        .chain(get_mem_items())
+        .chain(get_io_items())
+        .chain(get_pressure_items())
+        .chain(get_properties_items())
+    {
+        if !field_ids.contains(&item.field_id) {
+            field_ids.push(item.field_id);
+        }
+    }
+    field_ids
+}
+
+fn process_field_ids() -> Vec<model::SingleProcessModelFieldId> {
+    use crate::process_tabs::default_tabs::*;
+    let mut field_ids = Vec::new();
+    for tab in [
+        &*PROCESS_GENERAL_TAB,
+        &*PROCESS_CPU_TAB,
+        &*PROCESS_MEM_TAB,
+        &*PROCESS_IO_TAB,
+    ] {
+        for item in &tab.view_items {
+            if !field_ids.contains(&item.field_id) {
+                field_ids.push(item.field_id.clone());
+            }
+        }
+    }
+    field_ids
+}
+
+/// Rows comparing the entities of the current main view, or None if the
+/// view does not support comparison.
+fn get_view_rows(
+    marked: &Model,
+    current: &Model,
+    main_view_state: &MainViewState,
+) -> Option<Vec<String>> {
+    match main_view_state {
+        MainViewState::Cgroup => Some(get_rows(
+            &cgroup_entities(&marked.cgroup),
+            &cgroup_entities(&current.cgroup),
+            &cgroup_field_ids(),
+        )),
+        MainViewState::Process(_) => Some(get_rows(
+            &process_entities(&marked.process),
+            &process_entities(&current.process),
+            &process_field_ids(),
+        )),
+        MainViewState::System => Some(get_rows(
+            &BTreeMap::from([("system".to_owned(), &marked.system)]),
+            &BTreeMap::from([("system".to_owned(), &current.system)]),
+            &enum_iterator::all::<SystemModelFieldId>().collect::<Vec<_>>(),
+        )),
+        #[cfg(fbcode_build)]
+        MainViewState::Gpu => None,
+    }
+}
+
+pub fn new(marked: &Model, current: &Model, main_view_state: &MainViewState) -> impl View {
+    let title = format!(
+        "Changes since marked sample at {}",
+        DateTime::<Local>::from(marked.timestamp).format("%m/%d/%Y %H:%M:%S"),
+    );
+    let content = match get_view_rows(marked, current, main_view_state) {
+        Some(rows) if rows.is_empty() => {
+            LinearLayout::vertical().child(TextView::new("No changes"))
+        }
+        Some(rows) => {
+            let mut list = SelectView::<String>::new();
+            list.add_all_str(rows);
+            LinearLayout::vertical()
+                .child(TextView::new(get_title()))
+                .child(list.scrollable())
+        }
+        None => LinearLayout::vertical().child(TextView::new("Not supported in this view")),
+    };
+    OnEventView::new(
+        Dialog::new()
+            .title(title)
+            .padding_lrtb(1, 1, 1, 0)
+            .content(content)
+            .dismiss_button("Close"),
+    )
+    .on_event(Key::Esc, |s| {
+        s.pop_layer();
+    })
+}
@@ -0,0 +1,146 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Actions acting on the host have no default key binding so that they are
+// never triggered by a stray key press.
+
+use super::*;
+use crate::actions;
+use crate::actions::Action;
+use crate::MainViewState;
+
+fn selected_pid(c: &mut Cursive) -> Option<i32> {
+    let state = c
+        .user_data::<ViewState>()
+        .expect("No data stored in Cursive object!")
+        .main_view_state
+        .clone();
+    match state {
+        MainViewState::Process(_) => {
+            crate::process_view::ProcessView::get_process_view(c)
+                .state
+                .borrow()
+                .current_selected_pid
+        }
+        _ => None,
+    }
+}
+
+fn selected_cgroup(c: &mut Cursive) -> Option<String> {
+    let state = c
+        .user_data::<ViewState>()
+        .expect("No data stored in Cursive object!")
+        .main_view_state
+        .clone();
+    match state {
+        MainViewState::Cgroup => {
+            let key = crate::cgroup_view::CgroupView::get_cgroup_view(c)
+                .state
+                .borrow()
+                .current_selected_cgroup
+                .clone();
+            Some(actions::cgroup_from_key(&key).to_owned())
+        }
+        _ => None,
+    }
+}
+
+// Send a signal to the selected process
+make_event_controller!(
+    KillProcess,
+    "kill",
+    "",
+    vec![],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, cmd_vec: &[&str]| {
+        let signal = match actions::parse_signal(cmd_vec.get(1).unwrap_or(&"TERM")) {
+            Ok(signal) => signal,
+            Err(e) => {
+                view_warn!(c, "{}", e);
+                return;
+            }
+        };
+        match selected_pid(c) {
+            Some(pid) => actions::confirm(c, Action::Kill { pid, signal }),
+            None => view_warn!(c, "Select a process in the process view first"),
+        }
+    }
+);
+
+// Change the nice value of the selected process
+make_event_controller!(
+    ReniceProcess,
+    "renice",
+    "",
+    vec![],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, cmd_vec: &[&str]| {
+        let nice = match cmd_vec.get(1).map(|nice| nice.parse::<i32>()) {
+            Some(Ok(nice)) => nice,
+            _ => {
+                view_warn!(c, "Usage: renice NICE");
+                return;
+            }
+        };
+        match selected_pid(c) {
+            Some(pid) => actions::confirm(c, Action::Renice { pid, nice }),
+            None => view_warn!(c, "Select a process in the process view first"),
+        }
+    }
+);
+
+// Move the selected process to another cgroup
+make_event_controller!(
+    MoveProcessCgroup,
+    "move_cgroup",
+    "",
+    vec![],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, cmd_vec: &[&str]| {
+        let cgroup = match cmd_vec.get(1) {
+            Some(cgroup) => cgroup.to_string(),
+            None => {
+                view_warn!(c, "Usage: move_cgroup CGROUP_PATH");
+                return;
+            }
+        };
+        match selected_pid(c) {
+            Some(pid) => actions::confirm(c, Action::MoveCgroup { pid, cgroup }),
+            None => view_warn!(c, "Select a process in the process view first"),
+        }
+    }
+);
+
+// Freeze or thaw the selected cgroup
+make_event_controller!(
+    FreezeCgroup,
+    "freeze",
+    "",
+    vec![],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, cmd_vec: &[&str]| {
+        let frozen = match cmd_vec.get(1).copied() {
+            None | Some("1") => true,
+            Some("0") => false,
+            _ => {
+                view_warn!(c, "Usage: freeze [1|0]");
+                return;
+            }
+        };
+        match selected_cgroup(c) {
+            Some(cgroup) => actions::confirm(c, Action::Freeze { cgroup, frozen }),
+            None => view_warn!(c, "Select a cgroup in the cgroup view first"),
+        }
+    }
+);
@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use common::util::get_belowrc_filename;
+
 use super::*;
 use crate::filter_popup;
+use crate::search_popup;
+use crate::viewrc::ViewRc;
 
 // Sort by selected column
 make_event_controller!(
@@ -105,7 +109,180 @@ make_event_controller!(
     |c: &mut Cursive, _cmd_vec: &[&str]| {
         let state = StatsView::<T>::get_view(c).state.clone();
         state.borrow_mut().set_filter_from_tab_idx("", 0, None); // clear filter
+        state.borrow_mut().clear_filter_exprs();
         StatsView::<T>::cp_filter(c, None);
+        StatsView::<T>::cp_filter_exprs(c);
+        StatsView::<T>::refresh_myself(c);
+    }
+);
+
+// Add a filter expression on top of the current filters
+make_event_controller!(
+    AddFilterExpr,
+    "filter_add",
+    "fa",
+    vec![],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, cmd_vec: &[&str]| {
+        if cmd_vec.len() < 2 {
+            StatsView::<T>::cp_warn(c, "Usage: filter_add EXPR, e.g. cpu.usage_pct > 10%");
+            return;
+        }
+        let state = StatsView::<T>::get_view(c).state.clone();
+        let res = state.borrow_mut().add_filter_expr(&cmd_vec[1..].join(" "));
+        match res {
+            Ok(()) => {
+                StatsView::<T>::cp_filter_exprs(c);
+                StatsView::<T>::refresh_myself(c);
+            }
+            Err(e) => StatsView::<T>::cp_warn(c, &format!("{:#}", e)),
+        }
+    }
+);
+
+// Save the current filter expressions as a named preset in belowrc
+make_event_controller!(
+    SaveFilterPreset,
+    "filter_save",
+    "fs",
+    vec![],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, cmd_vec: &[&str]| {
+        let name = match cmd_vec {
+            [_, name] => name.to_string(),
+            _ => {
+                StatsView::<T>::cp_warn(c, "Usage: filter_save NAME");
+                return;
+            }
+        };
+        let filters = StatsView::<T>::get_view(c)
+            .state
+            .borrow()
+            .get_filter_exprs();
+        if filters.is_empty() {
+            StatsView::<T>::cp_warn(c, "No filter expression to save, use filter_add first");
+            return;
+        }
+        let path = std::path::PathBuf::from(get_belowrc_filename());
+        match ViewRc::save_filter_preset(&path, &name, &filters) {
+            Ok(()) => {
+                c.user_data::<ViewState>()
+                    .expect("No data stored in Cursive object!")
+                    .viewrc
+                    .filter_presets
+                    .get_or_insert_with(Default::default)
+                    .insert(name.clone(), filters);
+                StatsView::<T>::cp_warn(c, &format!("Saved filter preset {}", name));
+            }
+            Err(e) => StatsView::<T>::cp_warn(c, &format!("{:#}", e)),
+        }
+    }
+);
+
+// Replace the filter expressions with a named preset from belowrc
+make_event_controller!(
+    LoadFilterPreset,
+    "filter_load",
+    "fl",
+    vec![],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, cmd_vec: &[&str]| {
+        let name = match cmd_vec {
+            [_, name] => *name,
+            _ => {
+                StatsView::<T>::cp_warn(c, "Usage: filter_load NAME");
+                return;
+            }
+        };
+        let filters = c
+            .user_data::<ViewState>()
+            .expect("No data stored in Cursive object!")
+            .viewrc
+            .filter_presets
+            .as_ref()
+            .and_then(|presets| presets.get(name))
+            .cloned();
+        let filters = match filters {
+            Some(filters) => filters,
+            None => {
+                StatsView::<T>::cp_warn(c, &format!("No filter preset named {}", name));
+                return;
+            }
+        };
+        let res = StatsView::<T>::get_view(c)
+            .state
+            .borrow_mut()
+            .set_filter_exprs(&filters);
+        StatsView::<T>::cp_filter_exprs(c);
         StatsView::<T>::refresh_myself(c);
+        if let Err(e) = res {
+            StatsView::<T>::cp_warn(c, &format!("Filter preset {}: {:#}", name, e));
+        }
+    }
+);
+
+// Search rows for a pattern, highlighting matches
+make_event_controller!(
+    SearchPopup,
+    "search",
+    "se",
+    vec![Event::Char('F')],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, cmd_vec: &[&str]| {
+        if cmd_vec.len() > 1 {
+            let pattern = cmd_vec[1..].join(" ");
+            let found = {
+                let mut view = StatsView::<T>::get_view(c);
+                view.set_search(Some(pattern.clone()));
+                view.select_search_match(c, false, true)
+            };
+            StatsView::<T>::refresh_myself(c);
+            if !found {
+                StatsView::<T>::cp_warn(c, &format!("Pattern not found: {}", pattern));
+            }
+        } else {
+            let pattern = StatsView::<T>::get_view(c).get_search().cloned();
+            c.add_layer(search_popup::new::<T>(pattern));
+        }
     }
 );
+
+// Select the next row matching the search pattern
+make_event_controller!(
+    SearchNext,
+    "search_next",
+    "sn",
+    vec![Event::Char('n')],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        search_move::<T>(c, false);
+    }
+);
+
+// Select the previous row matching the search pattern
+make_event_controller!(
+    SearchPrev,
+    "search_prev",
+    "sp",
+    vec![Event::AltChar('n')],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        search_move::<T>(c, true);
+    }
+);
+
+fn search_move<T: 'static + ViewBridge>(c: &mut Cursive, backward: bool) {
+    let (pattern, found) = {
+        let mut view = StatsView::<T>::get_view(c);
+        let pattern = view.get_search().cloned();
+        let found = view.select_search_match(c, backward, false);
+        (pattern, found)
+    };
+    match pattern {
+        None => StatsView::<T>::cp_warn(c, "No search pattern, use the search command first"),
+        Some(pattern) if !found => {
+            StatsView::<T>::cp_warn(c, &format!("Pattern not found: {}", pattern))
+        }
+        Some(_) => StatsView::<T>::refresh_myself(c),
+    }
+}
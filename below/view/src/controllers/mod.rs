@@ -79,6 +79,7 @@ use toml::value::Value;
 
 #[macro_use]
 mod controller_infra;
+mod action_controllers;
 mod content_controllers;
 mod sample_controllers;
 mod view_controllers;
@@ -86,7 +87,10 @@ mod view_controllers;
 #[cfg(test)]
 mod test;
 
+use action_controllers::*;
 use common::open_source_shim;
+use common::util::get_belowrc_cmd_section_key;
+use common::util::ConfigIssue;
 use content_controllers::*;
 use controller_infra::*;
 use sample_controllers::*;
@@ -116,10 +120,21 @@ make_controllers!(
     SortCol: SortByColumn,
     Filter: FilterPopup,
     CFilter: ClearFilter,
+    AddFilter: AddFilterExpr,
+    SaveFilter: SaveFilterPreset,
+    LoadFilter: LoadFilterPreset,
+    Search: SearchPopup,
+    SearchNext: SearchNext,
+    SearchPrev: SearchPrev,
     JForward: JumpForward,
     JBackward: JumpBackward,
+    Goto: GotoTime,
     NSample: NextSample,
     PSample: PrevSample,
+    MarkSample: MarkSample,
+    CompareMarked: CompareMarked,
+    Annotate: AnnotateSample,
+    Graph: GraphSelected,
     Pause: PauseImpl,
     Quit: QuitImpl,
     Help: HelpMenu,
@@ -131,8 +146,54 @@ make_controllers!(
     GpuZoom: GpuZoomView,
     Zoom: ZoomView,
     Fold: FoldProcessView,
+    Tree: ProcessTreeView,
+    Kill: KillProcess,
+    Renice: ReniceProcess,
+    MoveCgroup: MoveProcessCgroup,
+    Freeze: FreezeCgroup,
     NextPage: NextPageImpl,
     PrevPage: PrevPageImpl,
     NextSelection: NextSelectionImpl,
     PrevSelection: PrevSelectionImpl,
 );
+
+/// Check the [cmd] section of a belowrc the way make_event_controller_map
+/// reads it, which only warns in the view.
+pub fn check_cmdrc(cmdrc: &Value) -> Vec<ConfigIssue> {
+    let section = get_belowrc_cmd_section_key();
+    let table = match cmdrc.as_table() {
+        Some(table) => table,
+        None => return vec![ConfigIssue::error(section, "Not a table")],
+    };
+    let cmd_controllers = make_cmd_controller_map();
+    let mut issues = Vec::new();
+    for (cmd, events) in table {
+        let key = format!("{}.{}", section, cmd);
+        if !cmd_controllers.contains_key(cmd.as_str()) {
+            issues.push(ConfigIssue::warning(key, "Unknown command"));
+            continue;
+        }
+        // Single key bindings may be strings rather than arrays
+        let events = match events {
+            Value::String(_) => std::slice::from_ref(events),
+            Value::Array(events) => events.as_slice(),
+            _ => {
+                issues.push(ConfigIssue::error(
+                    key,
+                    "Key bindings must be a string or an array of strings",
+                ));
+                continue;
+            }
+        };
+        for event in events {
+            match event.as_str() {
+                Some(event_str) if str_to_event(event_str).is_some() => {}
+                _ => issues.push(ConfigIssue::error(
+                    &key,
+                    format!("Invalid key binding {}", event),
+                )),
+            }
+        }
+    }
+    issues
+}
@@ -68,6 +68,29 @@ make_event_controller!(
     }
 );
 
+// Jump to an absolute time
+make_event_controller!(
+    GotoTime,
+    "goto",
+    "",
+    vec![],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, cmd_vec: &[&str]| {
+        let mode = c
+            .user_data::<ViewState>()
+            .expect("user data not set")
+            .mode
+            .clone();
+        match mode {
+            ViewMode::Pause(adv) | ViewMode::Replay(adv) if cmd_vec.len() > 1 => {
+                jump_popup::goto_helper(&adv, c, &cmd_vec[1..].join(" "));
+            }
+            ViewMode::Pause(_) | ViewMode::Replay(_) => view_warn!(c, "Usage: goto TIME"),
+            ViewMode::Live(_) => view_warn!(c, "Pause or replay to go to a time"),
+        }
+    }
+);
+
 // Next sample
 make_event_controller!(
     NextSample,
@@ -89,6 +112,7 @@ make_event_controller!(
             _ => {}
         };
         crate::status_bar::refresh(c);
+        crate::timeline::refresh(c);
         crate::summary_view::refresh(c);
         StatsView::<T>::refresh_myself(c);
     }
@@ -115,6 +139,7 @@ make_event_controller!(
             _ => {}
         }
         crate::status_bar::refresh(c);
+        crate::timeline::refresh(c);
         crate::summary_view::refresh(c);
         StatsView::<T>::refresh_myself(c);
     }
@@ -148,3 +173,95 @@ make_event_controller!(
         crate::status_bar::refresh(c);
     }
 );
+
+// Mark the current sample for comparison
+make_event_controller!(
+    MarkSample,
+    "mark_sample",
+    "mk",
+    vec![Event::Char('B')],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        let view_state = c.user_data::<ViewState>().expect("user data not set");
+        let model = view_state.model.borrow().clone();
+        view_state.marked_model = Some(model);
+        crate::timeline::refresh(c);
+        StatsView::<T>::get_view(c)
+            .get_cmd_palette()
+            .set_info("Marked current sample for comparison");
+    }
+);
+
+// Annotate the current sample on the replay timeline
+make_event_controller!(
+    AnnotateSample,
+    "annotate",
+    "an",
+    vec![],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, cmd_vec: &[&str]| {
+        let view_state = c.user_data::<ViewState>().expect("user data not set");
+        let timestamp = view_state.timestamp;
+        match view_state.timeline.as_mut() {
+            Some(timeline) => timeline.annotate(timestamp, &cmd_vec[1..].join(" ")),
+            None => {
+                view_warn!(c, "Annotations are only shown on the replay timeline");
+                return;
+            }
+        }
+        crate::timeline::refresh(c);
+    }
+);
+
+// Compare the current sample to the marked one
+make_event_controller!(
+    CompareMarked,
+    "compare_marked",
+    "cmp",
+    vec![Event::Char('V')],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        let view_state = c.user_data::<ViewState>().expect("user data not set");
+        let popup = match &view_state.marked_model {
+            Some(marked) => Some(crate::compare_popup::new(
+                marked,
+                &view_state.model.borrow(),
+                &view_state.main_view_state,
+            )),
+            None => None,
+        };
+        match popup {
+            Some(popup) => c.add_layer(popup),
+            None => view_warn!(c, "No marked sample, use mark_sample first"),
+        }
+    }
+);
+
+// Graph the selected cell over the recent samples
+make_event_controller!(
+    GraphSelected,
+    "graph",
+    "gr",
+    vec![Event::Char('H')],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        let query = {
+            let mut view = StatsView::<T>::get_view(c);
+            let tab = view.get_tab_view().get_cur_selected().to_owned();
+            let title_idx = view.get_title_view().current_selected;
+            let key = view.get_detail_view().selection();
+            let state = view.state.borrow();
+            key.and_then(|key| state.get_graph_query(&tab, title_idx, &key))
+        };
+        match query {
+            Some(query) => {
+                let history = c
+                    .user_data::<ViewState>()
+                    .expect("user data not set")
+                    .get_history();
+                c.add_layer(crate::graph_popup::new(&query, &history));
+            }
+            None => view_warn!(c, "Selected column cannot be graphed"),
+        }
+    }
+);
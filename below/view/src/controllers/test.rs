@@ -338,3 +338,439 @@ next_col = 'd'
         Some(&Controllers::Cgroup)
     );
 }
+
+#[test]
+fn test_graph_plot() {
+    use crate::graph_popup::plot;
+
+    assert_eq!(
+        plot(&[Some(0.0), Some(8.0), None, Some(16.0), Some(4.0)], 2),
+        vec!["   █ ".to_owned(), " █ █▄".to_owned()]
+    );
+    // Scaled from the minimum if negative
+    assert_eq!(plot(&[Some(-2.0), Some(2.0)], 1), vec![" █".to_owned()]);
+    assert_eq!(plot(&[Some(0.0), None], 1), vec!["  ".to_owned()]);
+    assert_eq!(plot(&[], 2), vec![String::new(), String::new()]);
+}
+
+#[test]
+fn test_process_tree_rows() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use model::ProcessCpuModel;
+    use model::ProcessCpuModelFieldId;
+    use model::ProcessModel;
+    use model::SingleProcessModel;
+    use model::SingleProcessModelFieldId;
+
+    use crate::process_tabs::default_tabs::PROCESS_CPU_TAB;
+    use crate::process_tabs::default_tabs::PROCESS_GENERAL_TAB;
+    use crate::process_tabs::default_tabs::PROCESS_TREE_TAB;
+    use crate::process_view::ProcessState;
+
+    let process = |pid: i32, ppid: i32, usage_pct: f64| SingleProcessModel {
+        pid: Some(pid),
+        ppid: Some(ppid),
+        cpu: Some(ProcessCpuModel {
+            usage_pct: Some(usage_pct),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    // 1 -> {2 -> 4, 3}, the parent of 5 is not shown
+    let model = ProcessModel {
+        processes: [
+            process(1, 0, 1.0),
+            process(2, 1, 2.0),
+            process(3, 1, 3.0),
+            process(4, 2, 4.0),
+            process(5, 100, 0.5),
+        ]
+        .into_iter()
+        .map(|spm| (spm.pid.unwrap(), spm))
+        .collect(),
+    };
+    let mut state = ProcessState::new(Rc::new(RefCell::new(model)));
+    state.tree = true;
+    state.sort_order = Some(SingleProcessModelFieldId::Cpu(
+        ProcessCpuModelFieldId::UsagePct,
+    ));
+    state.reverse = true;
+    let pids = |state: &ProcessState| {
+        PROCESS_GENERAL_TAB
+            .get_rows(state, None)
+            .into_iter()
+            .map(|(_, pid)| pid)
+            .collect::<Vec<_>>()
+    };
+
+    // Siblings are sorted within each level
+    assert_eq!(pids(&state), vec![1, 3, 2, 4, 5]);
+    state.toggle_collapsed(2);
+    assert_eq!(pids(&state), vec![1, 3, 2, 5]);
+    state.toggle_collapsed(2);
+    assert_eq!(pids(&state), vec![1, 3, 2, 4, 5]);
+
+    // Subtree totals only have their own tab
+    let has_subtree = |titles: Vec<String>| titles.iter().any(|title| title.starts_with("Tree "));
+    assert!(!has_subtree(PROCESS_GENERAL_TAB.get_titles().titles));
+    assert!(!has_subtree(PROCESS_CPU_TAB.get_titles().titles));
+    assert!(has_subtree(PROCESS_TREE_TAB.get_titles().titles));
+}
+
+#[test]
+fn test_actions() {
+    use common::logutil::get_logger;
+
+    use crate::actions::cgroup_from_key;
+    use crate::actions::parse_signal;
+    use crate::actions::Action;
+    use crate::actions::Actions;
+
+    let root = TempDir::with_prefix("below_view_actions_test.").expect("Failed to create dir");
+    let proc_root = root.path().join("proc");
+    let cgroup_root = root.path().join("cgroup");
+    std::fs::create_dir_all(proc_root.join("1234")).unwrap();
+    std::fs::create_dir_all(cgroup_root.join("system.slice/foo.service")).unwrap();
+    std::fs::create_dir_all(root.path().join("outside")).unwrap();
+    let actions = Actions::new(get_logger(), proc_root, cgroup_root.clone());
+    let read = |path: &str| std::fs::read_to_string(cgroup_root.join(path)).unwrap();
+
+    actions
+        .run(&Action::Freeze {
+            cgroup: "/system.slice/foo.service".into(),
+            frozen: true,
+        })
+        .expect("Failed to freeze");
+    assert_eq!(read("system.slice/foo.service/cgroup.freeze"), "1");
+    actions
+        .run(&Action::Freeze {
+            cgroup: "/system.slice/foo.service".into(),
+            frozen: false,
+        })
+        .expect("Failed to thaw");
+    assert_eq!(read("system.slice/foo.service/cgroup.freeze"), "0");
+    actions
+        .run(&Action::MoveCgroup {
+            pid: 1234,
+            cgroup: "/system.slice".into(),
+        })
+        .expect("Failed to move");
+    assert_eq!(read("system.slice/cgroup.procs"), "1234");
+
+    // Nothing is done for processes or cgroups not in the fake roots
+    for action in [
+        Action::Freeze {
+            cgroup: "".into(),
+            frozen: true,
+        },
+        Action::Freeze {
+            cgroup: "/missing.slice".into(),
+            frozen: true,
+        },
+        Action::MoveCgroup {
+            pid: 4321,
+            cgroup: "/system.slice".into(),
+        },
+        // Must not escape the cgroup root
+        Action::MoveCgroup {
+            pid: 1234,
+            cgroup: "/../outside".into(),
+        },
+        Action::MoveCgroup {
+            pid: 1234,
+            cgroup: "/system.slice/../../outside".into(),
+        },
+        Action::Kill {
+            pid: 4321,
+            signal: libc::SIGTERM,
+        },
+        Action::Kill {
+            pid: -1,
+            signal: libc::SIGTERM,
+        },
+        Action::Renice { pid: 4321, nice: 5 },
+    ] {
+        assert!(actions.run(&action).is_err(), "{} succeeded", action);
+    }
+    assert!(!root.path().join("outside/cgroup.procs").exists());
+
+    assert_eq!(parse_signal("9").unwrap(), libc::SIGKILL);
+    assert_eq!(parse_signal("kill").unwrap(), libc::SIGKILL);
+    assert_eq!(parse_signal("SIGTERM").unwrap(), libc::SIGTERM);
+    assert!(parse_signal("SIGFOO").is_err());
+
+    assert_eq!(cgroup_from_key("[RECREATED] /a.slice"), "/a.slice");
+    assert_eq!(cgroup_from_key("/a.slice"), "/a.slice");
+}
+
+#[test]
+fn test_search_matches() {
+    use cursive::theme::BaseColor;
+    use cursive::theme::Color;
+    use cursive::utils::markup::StyledString;
+
+    use crate::search_popup::find_matches;
+    use crate::search_popup::highlight_matches;
+
+    assert_eq!(find_matches("below belOW", "below"), vec![(0, 5), (6, 11)]);
+    // Case sensitive once the pattern has an uppercase letter
+    assert_eq!(find_matches("below belOW", "belOW"), vec![(6, 11)]);
+    assert_eq!(find_matches("aaaa", "aa"), vec![(0, 2), (2, 4)]);
+    assert_eq!(find_matches("below", ""), vec![]);
+
+    let mut row = StyledString::plain("systemd ");
+    row.append_styled("120.0%", Color::Light(BaseColor::Red));
+    let highlighted = highlight_matches(row.clone(), "d 1");
+    assert_eq!(highlighted.source(), row.source());
+    let spans: Vec<_> = highlighted.spans().map(|span| span.content).collect();
+    assert_eq!(spans, vec!["system", "d ", "1", "20.0%"]);
+    let styles: Vec<_> = highlighted.spans().map(|span| *span.attr).collect();
+    assert_eq!(styles[1], styles[2]);
+    assert_ne!(styles[0], styles[1]);
+    // Unmatched part of a span keeps its style
+    assert_eq!(styles[3], *row.spans().nth(1).unwrap().attr);
+    assert_eq!(highlight_matches(row.clone(), "foo"), row);
+}
+
+#[test]
+fn test_filter_exprs() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use model::ProcessCpuModel;
+    use model::ProcessModel;
+    use model::SingleProcessModel;
+
+    use crate::process_tabs::default_tabs::PROCESS_GENERAL_TAB;
+    use crate::process_view::ProcessState;
+    use crate::stats_view::StateCommon;
+
+    let process = |pid: i32, cgroup: &str, usage_pct: f64| SingleProcessModel {
+        pid: Some(pid),
+        cgroup: Some(cgroup.to_owned()),
+        cpu: Some(ProcessCpuModel {
+            usage_pct: Some(usage_pct),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let model = ProcessModel {
+        processes: [
+            process(1, "/system.slice", 20.0),
+            process(2, "/workload.slice", 5.0),
+            process(3, "/workload.slice", 50.0),
+        ]
+        .into_iter()
+        .map(|spm| (spm.pid.unwrap(), spm))
+        .collect(),
+    };
+    let mut state = ProcessState::new(Rc::new(RefCell::new(model)));
+    let pids = |state: &ProcessState| {
+        let mut pids: Vec<_> = PROCESS_GENERAL_TAB
+            .get_rows(state, None)
+            .into_iter()
+            .map(|(_, pid)| pid)
+            .collect();
+        pids.sort();
+        pids
+    };
+
+    state
+        .add_filter_expr("cpu.usage_pct > 10%")
+        .expect("Failed to add filter");
+    assert_eq!(pids(&state), vec![1, 3]);
+    // Filters stack
+    state
+        .add_filter_expr("cgroup ~ workload")
+        .expect("Failed to add filter");
+    assert_eq!(pids(&state), vec![3]);
+    assert_eq!(
+        state.get_filter_exprs(),
+        vec!["cpu.usage_pct > 10%", "cgroup =~ 'workload'"]
+    );
+    assert!(state.add_filter_expr("no_such_field > 1").is_err());
+    assert!(state.add_filter_expr("pid > 1%").is_err());
+    assert_eq!(state.get_filter_exprs().len(), 2);
+    // Presets are only applied if all of their expressions are valid
+    let preset = |exprs: &[&str]| exprs.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert!(
+        state
+            .set_filter_exprs(&preset(&["pid == 2", "no_such_field > 1"]))
+            .is_err()
+    );
+    assert_eq!(pids(&state), vec![3]);
+    state
+        .set_filter_exprs(&preset(&["pid <= 2"]))
+        .expect("Failed to set filters");
+    assert_eq!(pids(&state), vec![1, 2]);
+    state.clear_filter_exprs();
+    assert_eq!(pids(&state), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_save_filter_preset() {
+    use crate::viewrc::ViewRc;
+
+    let tempdir = TempDir::with_prefix("below_viewrc_test.").expect("Failed to create temp dir");
+    let path = tempdir.path().join("belowrc");
+    let view = "# My settings\n[view]\ndefault_view = 'process'\n\n";
+    let cmd = "[cmd]\nquit = 'Q' # Capital\n";
+    std::fs::write(&path, format!("{}{}", view, cmd)).expect("Failed to write belowrc");
+
+    let filters = vec!["cpu.usage_pct > 10".to_owned()];
+    ViewRc::save_filter_preset(&path, "busy", &filters).expect("Failed to save preset");
+    ViewRc::save_filter_preset(&path, "idle", &[]).expect("Failed to save preset");
+
+    let belowrc_str = std::fs::read_to_string(&path).expect("Failed to read belowrc");
+    // Only the presets are added, the rest of the file is kept as is
+    assert_eq!(
+        belowrc_str,
+        format!(
+            "{}[view.filter_presets]\nbusy = [\"cpu.usage_pct > 10\"]\nidle = []\n\n{}",
+            view, cmd
+        )
+    );
+    let belowrc = belowrc_str
+        .parse::<Value>()
+        .expect("Failed to parse belowrc");
+    assert_eq!(belowrc["cmd"]["quit"].as_str(), Some("Q"));
+    let viewrc: ViewRc = belowrc["view"]
+        .clone()
+        .try_into()
+        .expect("Failed to parse view section");
+    assert!(matches!(
+        viewrc.default_view,
+        Some(crate::viewrc::DefaultFrontView::Process)
+    ));
+    let presets = viewrc.filter_presets.expect("No presets");
+    assert_eq!(presets["busy"], filters);
+    assert!(presets["idle"].is_empty());
+
+    // Missing belowrc is created
+    let path = tempdir.path().join("new/belowrc");
+    ViewRc::save_filter_preset(&path, "busy", &filters).expect("Failed to save preset");
+    assert!(path.exists());
+}
+
+#[test]
+fn test_custom_tabs() {
+    use base_render::RenderFormat;
+    use model::SingleCgroupModelFieldId;
+
+    use crate::viewrc::ViewRc;
+
+    let mut viewrc: ViewRc = r#"
+        [[cgroup_tabs]]
+        name = "Mine"
+        items = [
+            { field_id = "cpu.usage_pct", alias = "Busy", width = 8, format = "precision:1" },
+            { field_id = "mem.total" },
+        ]
+
+        [[cgroup_tabs]]
+        name = "Typo"
+        items = [{ field_id = "cpu.usage_pctt" }]
+
+        [[process_tabs]]
+        name = "CPU"
+        items = [{ field_id = "pid" }]
+
+        [[process_tabs]]
+        name = "Rss"
+        items = [{ field_id = "mem.rss_bytes", format = "readable_bytes" }]
+
+        [[system_tabs]]
+        name = "Host"
+        items = [{ field_id = "hostname" }, { field_id = "mem.total" }]
+    "#
+    .parse::<Value>()
+    .expect("Failed to parse viewrc")
+    .try_into()
+    .expect("Failed to deserialize viewrc");
+
+    let error = viewrc.validate().expect("Invalid tabs not reported");
+    assert!(error.contains("cgroup_tabs: Invalid field id 'cpu.usage_pctt' in tab 'Typo'"));
+    assert!(error.contains("process_tabs: Duplicate tab name 'CPU'"));
+    assert!(error.contains("Unknown render format: readable_bytes for 'mem.rss_bytes'"));
+
+    let names = |tabs: &Option<Vec<crate::viewrc::CustomTab>>| {
+        tabs.iter()
+            .flatten()
+            .map(|tab| tab.name.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&viewrc.cgroup_tabs), vec!["Mine"]);
+    assert!(names(&viewrc.process_tabs).is_empty());
+    assert_eq!(names(&viewrc.system_tabs), vec!["Host"]);
+    assert!(viewrc.validate().is_none());
+
+    let items = viewrc.cgroup_tabs.as_ref().unwrap()[0]
+        .get_view_items::<SingleCgroupModelFieldId>()
+        .expect("Failed to build view items");
+    assert_eq!(items.len(), 2);
+    let config = &items[0].config.render_config;
+    assert_eq!(config.get_title(), "Busy");
+    assert_eq!(config.width, Some(8));
+    assert!(matches!(config.format, Some(RenderFormat::Precision(1))));
+    assert_eq!(
+        items[1].field_id.to_string(),
+        "mem.total",
+        "Items keep their order"
+    );
+}
+
+#[test]
+fn test_timeline_bar() {
+    use std::time::Duration;
+    use std::time::Instant;
+    use std::time::SystemTime;
+    use std::time::UNIX_EPOCH;
+
+    use crate::timeline::Timeline;
+
+    let at = |secs: u64| UNIX_EPOCH + Duration::from_secs(1_000_000 + secs);
+    // Samples every 5s for 50s, nothing recorded for 100s, then 50s again
+    let timestamps: Vec<_> = (0..=10).chain(30..=40).map(|i| at(i * 5)).collect();
+    let timeline = Timeline::from_timestamps(&timestamps).expect("Timeline not built");
+
+    let bar = timeline.render_bar(20, at(0), None);
+    assert_eq!(bar.source(), "█─────        ──────");
+    let bar = timeline.render_bar(20, at(200), Some(at(50)));
+    assert_eq!(bar.source(), "─────M        ─────█");
+    // Out of range positions are not drawn
+    let bar = timeline.render_bar(20, at(500), None);
+    assert!(!bar.source().contains('█'));
+
+    assert!(Timeline::from_timestamps(&[]).is_none());
+    let mut single = Timeline::from_timestamps(&[at(0)]).expect("Timeline not built");
+    assert_eq!(single.render_bar(3, at(0), None).source(), "█──");
+
+    // Samples recorded after the timeline was built extend it
+    single.extend(&timestamps);
+    assert_eq!(
+        single.render_bar(20, at(0), None).source(),
+        timeline.render_bar(20, at(0), None).source()
+    );
+
+    // Samples are checked for high pressure a few at a time
+    let is_high = |timestamp: SystemTime| timestamp == at(25);
+    single.scan(Instant::now(), is_high);
+    assert!(!single.render_bar(20, at(0), None).source().contains('!'));
+    single.scan(Instant::now() + Duration::from_secs(3600), is_high);
+    assert_eq!(
+        single.render_bar(20, at(0), None).source(),
+        "█─!───        ──────"
+    );
+
+    single.annotate(at(45), "deploy");
+    single.annotate(at(175), "rollback");
+    assert_eq!(single.annotation(at(45)), Some("deploy"));
+    assert_eq!(
+        single.render_bar(20, at(0), None).source(),
+        "█─!─A─        ───A──"
+    );
+    single.annotate(at(175), "");
+    assert_eq!(single.annotation(at(175)), None);
+}
@@ -348,6 +348,31 @@ make_event_controller!(
     }
 );
 
+// Toggle showing processes as a tree
+make_event_controller!(
+    ProcessTreeView,
+    "process_tree",
+    "tree",
+    vec![Event::Char('A')],
+    |_view: &mut StatsView<T>, _cmd_vec: &[&str]| {},
+    |c: &mut Cursive, _cmd_vec: &[&str]| {
+        let current_state = c
+            .user_data::<ViewState>()
+            .expect("No data stored in Cursive object!")
+            .main_view_state
+            .clone();
+
+        // NB: scope the borrowing to refresh() doesn't re-borrow and panic
+        if let MainViewState::Process(_) = current_state {
+            let mut process_view = crate::process_view::ProcessView::get_process_view(c);
+            process_view.get_cmd_palette().toggle_tree();
+            process_view.state.borrow_mut().toggle_tree();
+        }
+
+        refresh(c)
+    }
+);
+
 // utl function to parse page length
 fn parse_page_length(cmd_vec: &[&str]) -> Result<usize, String> {
     static DEFAULT_PAGE_LENGTH: usize = 15;
@@ -0,0 +1,146 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use base_render::HasRenderConfig;
+use base_render::RenderConfig;
+use chrono::DateTime;
+use chrono::Local;
+use cursive::event::Key;
+use cursive::view::View;
+use cursive::views::Dialog;
+use cursive::views::LinearLayout;
+use cursive::views::OnEventView;
+use cursive::views::TextView;
+use model::diff::numeric;
+use model::Field;
+use model::Model;
+
+/// Number of samples graphed. Live mode keeps this many recent models in
+/// memory, replay mode reads them from the store.
+pub const HISTORY_LEN: usize = 120;
+
+/// Number of text rows of the graph.
+const GRAPH_HEIGHT: usize = 10;
+
+/// Blocks filling an eighth of a row each more than the previous one.
+const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// How to graph a cell of a stats view over time.
+pub struct GraphQuery {
+    /// Field and entity graphed, e.g. "cpu.usage_pct of /system.slice"
+    pub title: String,
+    pub render_config: RenderConfig,
+    pub query: Box<dyn Fn(&Model) -> Option<Field>>,
+}
+
+impl GraphQuery {
+    /// Graphs field_id of the Queriable returned by get, e.g. a cgroup or a
+    /// process picked from the model by the key of the selected row.
+    pub fn new<Q, F>(field_id: Q::FieldId, entity: &str, get: F) -> Self
+    where
+        Q: HasRenderConfig + 'static,
+        Q::FieldId: ToString + 'static,
+        F: Fn(&Model) -> Option<&Q> + 'static,
+    {
+        Self {
+            title: format!("{} of {}", field_id.to_string(), entity),
+            render_config: Q::get_render_config(&field_id),
+            query: Box::new(move |model| get(model)?.query(&field_id)),
+        }
+    }
+}
+
+/// Bar chart of values, one column per value and `height` rows, top row
+/// first. Missing values are left blank. The chart starts at zero, or at the
+/// minimum if a value is negative, and ends at the maximum.
+pub(crate) fn plot(values: &[Option<f64>], height: usize) -> Vec<String> {
+    let min = values.iter().flatten().fold(0.0, |min: f64, v| min.min(*v));
+    let max = values.iter().flatten().fold(min, |max: f64, v| max.max(*v));
+    let row_steps = BLOCKS.len() - 1;
+    let steps = (height * row_steps) as f64;
+    let levels: Vec<Option<usize>> = values
+        .iter()
+        .map(|value| {
+            value.map(|v| {
+                if max > min {
+                    ((v - min) / (max - min) * steps).round() as usize
+                } else {
+                    0
+                }
+            })
+        })
+        .collect();
+    (0..height)
+        .rev()
+        .map(|row| {
+            levels
+                .iter()
+                .map(|level| match level {
+                    Some(level) => BLOCKS[level.saturating_sub(row * row_steps).min(row_steps)],
+                    None => ' ',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn get_content(query: &GraphQuery, history: &[Model]) -> LinearLayout {
+    let fields: Vec<Option<Field>> = history.iter().map(|model| (query.query)(model)).collect();
+    let values: Vec<Option<f64>> = fields.iter().map(numeric).collect();
+    let numeric_fields = || {
+        fields
+            .iter()
+            .zip(&values)
+            .filter_map(|(field, value)| Some((field.clone()?, (*value)?)))
+    };
+    let min = numeric_fields().min_by(|a, b| a.1.total_cmp(&b.1));
+    let max = numeric_fields().max_by(|a, b| a.1.total_cmp(&b.1));
+    let last = numeric_fields().last();
+    let (min, max, last) = match (min, max, last) {
+        (Some(min), Some(max), Some(last)) => (min.0, max.0, last.0),
+        _ => return LinearLayout::vertical().child(TextView::new("No numeric data to graph")),
+    };
+    let time = |model: &Model| {
+        DateTime::<Local>::from(model.timestamp)
+            .format("%m/%d/%Y %H:%M:%S")
+            .to_string()
+    };
+    let rc = &query.render_config;
+    LinearLayout::vertical()
+        .child(TextView::new(plot(&values, GRAPH_HEIGHT).join("\n")))
+        .child(TextView::new(format!(
+            "{} - {}",
+            history.first().map_or_else(String::new, time),
+            history.last().map_or_else(String::new, time),
+        )))
+        .child(TextView::new(format!(
+            "Min: {}  Max: {}  Last: {}",
+            rc.render(Some(min), false),
+            rc.render(Some(max), false),
+            rc.render(Some(last), false),
+        )))
+}
+
+pub fn new(query: &GraphQuery, history: &[Model]) -> impl View {
+    OnEventView::new(
+        Dialog::new()
+            .title(format!("{} over {} samples", query.title, history.len()))
+            .padding_lrtb(1, 1, 1, 0)
+            .content(get_content(query, history))
+            .dismiss_button("Close"),
+    )
+    .on_event(Key::Esc, |s| {
+        s.pop_layer();
+    })
+}
@@ -76,15 +76,38 @@ fn get_description(controller: &Controllers) -> &'static str {
             "Sort by the selected title, reverse the result by hitting 'S' again(cgroup view and process view only)."
         }
         Controllers::Filter => "Filter by selected column.",
-        Controllers::CFilter => "Clear the current filter.",
+        Controllers::CFilter => "Clear the current filter and filter expressions.",
+        Controllers::AddFilter => {
+            "Add a filter expression rows must match, e.g. 'cpu.usage_pct > 10% && cgroup ~ workload'."
+        }
+        Controllers::SaveFilter => "Save the filter expressions as a preset in the [view] section of belowrc.",
+        Controllers::LoadFilter => "Replace the filter expressions with a preset from belowrc, if all of its expressions are valid.",
+        Controllers::Search => {
+            "Search all columns for a pattern and highlight matches, case insensitive unless the pattern has uppercase letters."
+        }
+        Controllers::SearchNext => "Select the next row matching the search pattern.",
+        Controllers::SearchPrev => "Select the previous row matching the search pattern.",
         Controllers::JForward => {
             "Jump time by a specific amount forward or to a specific timestamp (replay and live-paused mode)."
         }
         Controllers::JBackward => {
             "Jump time by a specific amount backward or to a specific timestamp (replay and live-paused mode)."
         }
+        Controllers::Goto => {
+            "Go to an absolute time such as 10:00am, 2024-01-01 09:00 or 2h ago (replay and live-paused mode)."
+        }
         Controllers::NSample => "Show next sample (replay and live-paused mode).",
         Controllers::PSample => "Show previous sample (replay and live-paused mode).",
+        Controllers::MarkSample => "Mark the current sample for comparison.",
+        Controllers::CompareMarked => {
+            "Show the largest changes of the current view since the marked sample."
+        }
+        Controllers::Annotate => {
+            "Annotate the current sample on the timeline, or remove its annotation without text (replay mode)."
+        }
+        Controllers::Graph => {
+            "Graph the selected column of the selected row over the recent samples."
+        }
         Controllers::Pause => {
             "pause/resume the live mode. While pausing, use the above commands to go forwards or backwards in time"
         }
@@ -100,6 +123,13 @@ fn get_description(controller: &Controllers) -> &'static str {
             "If in cgroup view, zoom into process view filtered by cgroup. If in process view, zoom into cgroup view, selected on cgroup of process."
         }
         Controllers::Fold => "Fold processes (post filter) and display aggregated values.",
+        Controllers::Tree => {
+            "Toggle showing processes as a tree by parent pid, press enter to collapse or expand a process. The Tree tab shows totals of each process and its descendants."
+        }
+        Controllers::Kill => "Send a signal to the selected process, SIGTERM by default. Live mode only.",
+        Controllers::Renice => "Change the nice value of the selected process. Live mode only.",
+        Controllers::MoveCgroup => "Move the selected process to another cgroup. Live mode only.",
+        Controllers::Freeze => "Freeze the selected cgroup, or thaw it with 0. Live mode only.",
         Controllers::NextPage => "Scroll down 15 lines primary display.",
         Controllers::PrevPage => "Scroll up 15 lines primary display.",
         _ => "Unknown",
@@ -110,8 +140,18 @@ fn get_args(controller: &Controllers) -> &'static str {
     match controller {
         Controllers::SortCol => "SortKey",
         Controllers::Filter => "Substring",
+        Controllers::Search => "Substring",
+        Controllers::AddFilter => "Expression",
+        Controllers::SaveFilter => "Name",
+        Controllers::LoadFilter => "Name",
         Controllers::JForward => "Time",
         Controllers::JBackward => "Time",
+        Controllers::Goto => "Time",
+        Controllers::Annotate => "Text",
+        Controllers::Kill => "Signal",
+        Controllers::Renice => "Nice",
+        Controllers::MoveCgroup => "CgroupPath",
+        Controllers::Freeze => "1|0",
         _ => "-",
     }
 }
@@ -168,10 +208,18 @@ fn fill_controllers(
         cmd_map.get(&Controllers::JBackward).unwrap().to_string(),
         cmd_map.get(&Controllers::NSample).unwrap().to_string(),
         cmd_map.get(&Controllers::PSample).unwrap().to_string(),
+        cmd_map.get(&Controllers::MarkSample).unwrap().to_string(),
+        cmd_map
+            .get(&Controllers::CompareMarked)
+            .unwrap()
+            .to_string(),
         cmd_map.get(&Controllers::Pause).unwrap().to_string(),
         cmd_map.get(&Controllers::SortCol).unwrap().to_string(),
         cmd_map.get(&Controllers::Filter).unwrap().to_string(),
         cmd_map.get(&Controllers::CFilter).unwrap().to_string(),
+        cmd_map.get(&Controllers::Search).unwrap().to_string(),
+        cmd_map.get(&Controllers::SearchNext).unwrap().to_string(),
+        cmd_map.get(&Controllers::SearchPrev).unwrap().to_string(),
         cmd_map.get(&Controllers::Zoom).unwrap().to_string(),
         cmd_map.get(&Controllers::Fold).unwrap().to_string(),
         cmd_map.get(&Controllers::Process).unwrap().to_string(),
@@ -14,6 +14,9 @@
 
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 use common::dateutil;
 use cursive::event::Key;
@@ -66,55 +69,66 @@ pub fn advance_helper(
                 // silently doing nothing.
             }
         }
-        _ => match dateutil::HgTime::parse_time_of_day(input) {
-            Some(time_of_day) => {
-                // If an absolute time without date is provided, the viewing date will be used
-                let view_time = c
-                    .user_data::<ViewState>()
-                    .expect("user data not set")
-                    .timestamp;
+        _ => {
+            if !jump_to_time(adv, c, input) {
+                return;
+            }
+        }
+    };
+
+    crate::refresh(c);
+}
 
-                match dateutil::HgTime::time_of_day_relative_to_system_time(view_time, time_of_day)
-                {
-                    Some(timestamp) => match adv.borrow_mut().jump_sample_to(timestamp) {
-                        Some(data) => c
-                            .user_data::<ViewState>()
-                            .expect("No user data set")
-                            .update(data),
-                        None => view_warn!(c, "Cannot find available data sample"),
-                    },
-                    None => {
-                        view_warn!(c, "Failed to parse time of day value: {}", input);
-                        return;
-                    }
-                }
+/// Parse an absolute time, either a time of day on the viewing date or any
+/// format dateutil::HgTime::parse accepts.
+fn parse_time(c: &mut Cursive, input: &str) -> Option<SystemTime> {
+    match dateutil::HgTime::parse_time_of_day(input) {
+        Some(time_of_day) => {
+            // If an absolute time without date is provided, the viewing date will be used
+            let view_time = c
+                .user_data::<ViewState>()
+                .expect("user data not set")
+                .timestamp;
+
+            let timestamp =
+                dateutil::HgTime::time_of_day_relative_to_system_time(view_time, time_of_day);
+            if timestamp.is_none() {
+                view_warn!(c, "Failed to parse time of day value: {}", input);
             }
+            timestamp
+        }
+        None => match dateutil::HgTime::parse(input) {
+            Some(pt) => Some(UNIX_EPOCH + Duration::from_secs(pt.unixtime)),
             None => {
-                match dateutil::HgTime::parse(input) {
-                    // Jump for absolute time
-                    Some(pt) => {
-                        // For forward jumping: we will find the next available sample of the input time forward
-                        // For backward jumping: we will find the next available sample of the input time backward
-                        let timestamp =
-                            std::time::UNIX_EPOCH + std::time::Duration::from_secs(pt.unixtime);
-                        match adv.borrow_mut().jump_sample_to(timestamp) {
-                            Some(data) => c
-                                .user_data::<ViewState>()
-                                .expect("No user data set")
-                                .update(data),
-                            None => view_warn!(c, "Cannot find available data sample"),
-                        }
-                    }
-                    None => {
-                        view_warn!(c, "Failed to parse time value: {}", input);
-                        return;
-                    }
-                }
+                view_warn!(c, "Failed to parse time value: {}", input);
+                None
             }
         },
+    }
+}
+
+/// Jump to the sample at the absolute time input, or the closest one after
+/// it. Returns false if nothing was jumped to, after warning why.
+fn jump_to_time(adv: &Rc<RefCell<Advance>>, c: &mut Cursive, input: &str) -> bool {
+    let timestamp = match parse_time(c, input) {
+        Some(timestamp) => timestamp,
+        None => return false,
     };
+    match adv.borrow_mut().jump_sample_to(timestamp) {
+        Some(data) => c
+            .user_data::<ViewState>()
+            .expect("No user data set")
+            .update(data),
+        None => view_warn!(c, "Cannot find available data sample"),
+    }
+    true
+}
 
-    crate::refresh(c);
+/// Jump to an absolute time given by the goto command.
+pub fn goto_helper(adv: &Rc<RefCell<Advance>>, c: &mut Cursive, input: &str) {
+    if jump_to_time(adv, c, input) {
+        crate::refresh(c);
+    }
 }
 
 pub fn new(adv: Rc<RefCell<Advance>>, direction: Direction) -> impl View {
@@ -54,6 +54,8 @@
 ///   or `S` to sort in ascending or descending order.
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::time::Duration;
 use std::time::SystemTime;
@@ -98,18 +100,22 @@ open_source_shim!();
 mod cgroup_tabs;
 pub mod cgroup_view;
 pub mod command_palette;
+mod compare_popup;
 mod default_styles;
 mod filter_popup;
+mod graph_popup;
 mod help_menu;
 mod process_tabs;
 mod process_view;
 mod render;
+mod search_popup;
 pub mod stats_view;
 mod status_bar;
 mod summary_view;
 mod system_tabs;
 mod system_view;
 mod tab_view;
+mod timeline;
 
 pub struct View {
     inner: CursiveRunnable,
@@ -159,7 +165,8 @@ macro_rules! view_warn {
 // controllers depends on Advance
 pub mod controllers;
 pub mod viewrc;
-// Jump popup depends on view_warn
+// Jump popup and actions depend on view_warn
+mod actions;
 mod jump_popup;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -195,6 +202,7 @@ pub enum ViewMode {
 // periodically (during live mode)
 fn refresh(c: &mut Cursive) {
     status_bar::refresh(c);
+    timeline::refresh(c);
     summary_view::refresh(c);
     let current_state = c
         .user_data::<ViewState>()
@@ -241,6 +249,11 @@ pub struct ViewState {
     pub timestamp: SystemTime,
     // TODO: Replace other fields with model
     pub model: Rc<RefCell<Model>>,
+    /// Sample marked by the user for the "compare to marked sample" popup.
+    pub marked_model: Option<Model>,
+    /// Recent models for graphs in live mode, oldest first. Replay and
+    /// paused modes read them from the store instead.
+    pub history: VecDeque<Model>,
     pub system: Rc<RefCell<SystemModel>>,
     pub cgroup: Rc<RefCell<CgroupModel>>,
     pub process: Rc<RefCell<ProcessModel>>,
@@ -254,6 +267,10 @@ pub struct ViewState {
     pub viewrc_error: Option<String>,
     pub event_controllers: Rc<RefCell<HashMap<Event, controllers::Controllers>>>,
     pub cmd_controllers: Rc<RefCell<HashMap<&'static str, controllers::Controllers>>>,
+    /// Set when the TUI may act on processes and cgroups of the host.
+    pub actions: Option<Rc<actions::Actions>>,
+    /// Recorded range of the store, only built in replay mode.
+    pub timeline: Option<timeline::Timeline>,
 }
 
 impl ViewState {
@@ -263,6 +280,12 @@ impl ViewState {
             self.lowest_time_elapsed = model.time_elapsed;
         }
         self.timestamp = model.timestamp;
+        if let ViewMode::Live(_) = self.mode {
+            if self.history.len() == graph_popup::HISTORY_LEN {
+                self.history.pop_front();
+            }
+            self.history.push_back(model.clone());
+        }
         self.model.replace(model.clone());
         self.system.replace(model.system);
         self.cgroup.replace(model.cgroup);
@@ -284,6 +307,8 @@ impl ViewState {
             lowest_time_elapsed: model.time_elapsed,
             timestamp: model.timestamp,
             model: Rc::new(RefCell::new(model.clone())),
+            marked_model: None,
+            history: VecDeque::new(),
             system: Rc::new(RefCell::new(model.system)),
             cgroup: Rc::new(RefCell::new(model.cgroup)),
             process: Rc::new(RefCell::new(model.process)),
@@ -297,6 +322,8 @@ impl ViewState {
             viewrc_error,
             event_controllers: Rc::new(RefCell::new(HashMap::new())),
             cmd_controllers: Rc::new(RefCell::new(controllers::make_cmd_controller_map())),
+            actions: None,
+            timeline: None,
         }
     }
 
@@ -311,6 +338,16 @@ impl ViewState {
     pub fn is_paused(&self) -> bool {
         matches!(self.mode, ViewMode::Pause(_))
     }
+
+    /// Models of the recent samples up to the current one, oldest first.
+    pub fn get_history(&self) -> Vec<Model> {
+        match &self.mode {
+            ViewMode::Live(_) => self.history.iter().cloned().collect(),
+            ViewMode::Pause(adv) | ViewMode::Replay(adv) => adv
+                .borrow_mut()
+                .history(self.timestamp, graph_popup::HISTORY_LEN),
+        }
+    }
 }
 
 impl View {
@@ -334,6 +371,19 @@ impl View {
         View { inner }
     }
 
+    /// Allow commands such as kill, renice and freeze to act on the
+    /// processes and cgroups of this host. Only meant for live local mode.
+    pub fn enable_actions(&mut self, logger: slog::Logger, cgroup_root: PathBuf) {
+        self.inner
+            .user_data::<ViewState>()
+            .expect("No data stored in Cursive object!")
+            .actions = Some(Rc::new(actions::Actions::new(
+            logger,
+            PathBuf::from("/proc"),
+            cgroup_root,
+        )));
+    }
+
     pub fn cb_sink(&mut self) -> &::cursive::CbSink {
         self.inner.set_fps(4);
         self.inner.cb_sink()
@@ -414,6 +464,15 @@ impl View {
         // Used to handle warning assignment to the correct view
         let init_warnings = get_last_log_to_display();
 
+        let view_state = self
+            .inner
+            .user_data::<ViewState>()
+            .expect("No data stored in Cursive object!");
+        if let ViewMode::Replay(adv) = &view_state.mode {
+            view_state.timeline = timeline::Timeline::new(&mut adv.borrow_mut());
+        }
+        let is_replay = matches!(view_state.mode, ViewMode::Replay(_));
+
         let status_bar = status_bar::new(&mut self.inner);
         let summary_view = summary_view::new(&mut self.inner);
         let cgroup_view = cgroup_view::CgroupView::new(&mut self.inner);
@@ -448,16 +507,18 @@ impl View {
             screens_view.add_screen(BoxedView::boxed(ResizedView::with_full_screen(gpu_view))),
         );
 
+        let mut layout = LinearLayout::vertical()
+            .child(Panel::new(status_bar))
+            .child(Panel::new(summary_view))
+            .child(
+                OnEventView::new(screens_view.with_name("main_view_screens"))
+                    .with_name("dynamic_view"),
+            );
+        if is_replay {
+            layout.add_child(Panel::new(timeline::new(&mut self.inner)));
+        }
         self.inner
-            .add_fullscreen_layer(ResizedView::with_full_screen(
-                LinearLayout::vertical()
-                    .child(Panel::new(status_bar))
-                    .child(Panel::new(summary_view))
-                    .child(
-                        OnEventView::new(screens_view.with_name("main_view_screens"))
-                            .with_name("dynamic_view"),
-                    ),
-            ));
+            .add_fullscreen_layer(ResizedView::with_full_screen(layout));
 
         self.inner
             .focus_name("dynamic_view")
@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+use std::collections::HashSet;
+
 use cursive::utils::markup::StyledString;
 use itertools::Itertools;
 use model::Queriable;
@@ -33,17 +36,34 @@ pub struct ProcessTab {
 
 // Defines how to iterate through the process stats and generate get_rows for ViewBridge
 impl ProcessTab {
-    fn new(view_items: Vec<ProcessViewItem>) -> Self {
+    pub(crate) fn new(view_items: Vec<ProcessViewItem>) -> Self {
         Self { view_items }
     }
 
+    /// Renders the line of a process. `tree_node` is the depth and whether
+    /// the children are collapsed when showing processes as a tree.
     fn get_process_field_line(
         &self,
         model: &SingleProcessModel,
+        tree_node: Option<(usize, bool)>,
         offset: Option<usize>,
     ) -> StyledString {
         let mut line = StyledString::new();
-        line.append(default_tabs::COMM_VIEW_ITEM.render(model));
+        match tree_node {
+            Some((depth, collapsed)) => {
+                let comm_item = if collapsed {
+                    &*default_tabs::COMM_TREE_COLLAPSED_VIEW_ITEM
+                } else {
+                    &*default_tabs::COMM_TREE_VIEW_ITEM
+                };
+                line.append(
+                    comm_item
+                        .config
+                        .render_indented(model.query(&comm_item.field_id), depth),
+                );
+            }
+            None => line.append(default_tabs::COMM_VIEW_ITEM.render(model)),
+        }
         line.append_plain(" ");
 
         for item in std::iter::once(&*default_tabs::CGROUP_VIEW_ITEM)
@@ -81,8 +101,8 @@ impl ProcessTab {
         if let Some(sort_order) = state.sort_order.as_ref() {
             model::sort_queriables(&mut processes, sort_order, state.reverse);
         }
-        processes
-            .iter()
+        let processes: Vec<&SingleProcessModel> = processes
+            .into_iter()
             .filter(|spm| {
                 // If we're in zoomed pids mode, only show processes belonging
                 // to set of pids
@@ -112,7 +132,14 @@ impl ProcessTab {
                     true
                 }
             })
-            .copied()
+            .filter(|spm| state.filter_exprs.iter().all(|expr| expr.eval(spm)))
+            .collect();
+
+        if state.tree && !state.fold {
+            return self.get_tree_rows(state, processes, offset);
+        }
+        processes
+            .into_iter()
             // Abuse batching() to conditionally fold iter
             .batching(|it| {
                 if state.fold {
@@ -127,15 +154,57 @@ impl ProcessTab {
             })
             .map(|spm| {
                 (
-                    self.get_process_field_line(&spm, offset),
+                    self.get_process_field_line(&spm, None, offset),
                     spm.pid.unwrap_or(0),
                 )
             })
             .collect()
     }
+
+    /// Rows of processes as a tree following their ppid. Processes whose
+    /// parent is not shown are roots. Siblings keep the order of processes.
+    fn get_tree_rows(
+        &self,
+        state: &ProcessState,
+        processes: Vec<&SingleProcessModel>,
+        offset: Option<usize>,
+    ) -> Vec<(StyledString, i32)> {
+        let shown: HashSet<i32> = processes.iter().filter_map(|spm| spm.pid).collect();
+        let mut children: HashMap<i32, Vec<&SingleProcessModel>> = HashMap::new();
+        let mut roots = Vec::new();
+        for spm in processes {
+            match spm.ppid {
+                Some(ppid) if shown.contains(&ppid) => children.entry(ppid).or_default().push(spm),
+                _ => roots.push(spm),
+            }
+        }
+
+        let mut rows = Vec::new();
+        let mut stack: Vec<(&SingleProcessModel, usize)> =
+            roots.into_iter().rev().map(|spm| (spm, 0)).collect();
+        while let Some((spm, depth)) = stack.pop() {
+            let pid = spm.pid.unwrap_or(0);
+            let collapsed = state.collapsed_pids.contains(&pid);
+            rows.push((
+                self.get_process_field_line(spm, Some((depth, collapsed)), offset),
+                pid,
+            ));
+            if collapsed {
+                continue;
+            }
+            // Push children in reverse order so the first one will be pop first
+            if let Some(children) = children.get(&pid) {
+                stack.extend(children.iter().rev().map(|child| (*child, depth + 1)));
+            }
+        }
+        rows
+    }
 }
 
 pub mod default_tabs {
+    use base_render::RenderConfigBuilder as Rc;
+    use common::util::get_prefix;
+    use model::ContainerModelFieldId;
     use model::ProcessCpuModelFieldId::NumThreads;
     use model::ProcessCpuModelFieldId::SystemPct;
     use model::ProcessCpuModelFieldId::UsagePct;
@@ -155,9 +224,11 @@ pub mod default_tabs {
     use model::ProcessMemoryModelFieldId::Shmem;
     use model::ProcessMemoryModelFieldId::Swap;
     use model::ProcessMemoryModelFieldId::VmSize;
+    use model::ProcessSubtreeModelFieldId;
     use model::SingleProcessModelFieldId::Cgroup;
     use model::SingleProcessModelFieldId::Cmdline;
     use model::SingleProcessModelFieldId::Comm;
+    use model::SingleProcessModelFieldId::Container;
     use model::SingleProcessModelFieldId::Cpu;
     use model::SingleProcessModelFieldId::Io;
     use model::SingleProcessModelFieldId::Mem;
@@ -165,12 +236,19 @@ pub mod default_tabs {
     use model::SingleProcessModelFieldId::Pid;
     use model::SingleProcessModelFieldId::Ppid;
     use model::SingleProcessModelFieldId::State;
+    use model::SingleProcessModelFieldId::Subtree;
     use model::SingleProcessModelFieldId::UptimeSecs;
     use once_cell::sync::Lazy;
 
     use super::*;
 
     pub static COMM_VIEW_ITEM: Lazy<ProcessViewItem> = Lazy::new(|| ViewItem::from_default(Comm));
+    pub static COMM_TREE_VIEW_ITEM: Lazy<ProcessViewItem> = Lazy::new(|| {
+        ViewItem::from_default(Comm).update(Rc::new().indented_prefix(get_prefix(false)))
+    });
+    pub static COMM_TREE_COLLAPSED_VIEW_ITEM: Lazy<ProcessViewItem> = Lazy::new(|| {
+        ViewItem::from_default(Comm).update(Rc::new().indented_prefix(get_prefix(true)))
+    });
     pub static CGROUP_VIEW_ITEM: Lazy<ProcessViewItem> =
         Lazy::new(|| ViewItem::from_default(Cgroup));
 
@@ -191,6 +269,7 @@ pub mod default_tabs {
             ViewItem::from_default(UptimeSecs),
             ViewItem::from_default(Cpu(NumThreads)),
             ViewItem::from_default(Io(RwbytesPerSec)),
+            ViewItem::from_default(Container(ContainerModelFieldId::Name)),
             ViewItem::from_default(Cmdline),
         ])
     });
@@ -228,4 +307,18 @@ pub mod default_tabs {
             ViewItem::from_default(Io(RwbytesPerSec)),
         ])
     });
+
+    /// Totals of each process and its descendants, most useful when showing
+    /// processes as a tree.
+    pub static PROCESS_TREE_TAB: Lazy<ProcessTab> = Lazy::new(|| {
+        ProcessTab::new(vec![
+            ViewItem::from_default(Pid),
+            ViewItem::from_default(Ppid),
+            ViewItem::from_default(Subtree(ProcessSubtreeModelFieldId::NumProcs)),
+            ViewItem::from_default(Subtree(ProcessSubtreeModelFieldId::CpuUsagePct)),
+            ViewItem::from_default(Subtree(ProcessSubtreeModelFieldId::RssBytes)),
+            ViewItem::from_default(Subtree(ProcessSubtreeModelFieldId::RwbytesPerSec)),
+            ViewItem::from_default(Cmdline),
+        ])
+    });
 }
@@ -16,14 +16,17 @@ use std::cell::Ref;
 use std::cell::RefCell;
 use std::cell::RefMut;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::rc::Rc;
 
+use anyhow::Result;
 use cursive::utils::markup::StyledString;
 use cursive::view::Nameable;
 use cursive::views::NamedView;
 use cursive::views::SelectView;
 use cursive::views::ViewRef;
 use cursive::Cursive;
+use model::filter_expr::FilterExpr;
 use model::ProcessCpuModelFieldId;
 use model::ProcessIoModelFieldId;
 use model::ProcessMemoryModelFieldId;
@@ -31,15 +34,18 @@ use model::ProcessModel;
 use model::Queriable;
 use model::SingleProcessModelFieldId;
 
+use crate::graph_popup::GraphQuery;
 use crate::process_tabs::default_tabs::PROCESS_CPU_TAB;
 use crate::process_tabs::default_tabs::PROCESS_GENERAL_TAB;
 use crate::process_tabs::default_tabs::PROCESS_IO_TAB;
 use crate::process_tabs::default_tabs::PROCESS_MEM_TAB;
+use crate::process_tabs::default_tabs::PROCESS_TREE_TAB;
 use crate::process_tabs::ProcessTab;
 use crate::stats_view::ColumnTitles;
 use crate::stats_view::StateCommon;
 use crate::stats_view::StatsView;
 use crate::stats_view::ViewBridge;
+use crate::viewrc::DefaultFrontView;
 use crate::ViewState;
 
 pub type ViewType = StatsView<ProcessView>;
@@ -47,6 +53,8 @@ pub type ViewType = StatsView<ProcessView>;
 #[derive(Default)]
 pub struct ProcessState {
     pub filter_info: Option<(SingleProcessModelFieldId, String)>,
+    // Filter expressions every row must match, e.g. cpu.usage_pct > 10
+    pub filter_exprs: Vec<FilterExpr<SingleProcessModelFieldId>>,
     pub cgroup_filter: Option<String>,
     pub pids_filter: Option<Vec<i32>>,
     // For zoomed view, we should save current filter to here and reset the
@@ -57,6 +65,9 @@ pub struct ProcessState {
     pub sort_tags: HashMap<String, &'static ProcessTab>,
     pub reverse: bool,
     pub fold: bool,
+    // Show processes as a tree following their ppid. Ignored when folded.
+    pub tree: bool,
+    pub collapsed_pids: HashSet<i32>,
     pub model: Rc<RefCell<ProcessModel>>,
 }
 
@@ -109,6 +120,27 @@ impl StateCommon for ProcessState {
         true
     }
 
+    fn add_filter_expr(&mut self, expr: &str) -> Result<()> {
+        self.filter_exprs.push(expr.parse()?);
+        Ok(())
+    }
+
+    fn set_filter_exprs(&mut self, exprs: &[String]) -> Result<()> {
+        self.filter_exprs = exprs
+            .iter()
+            .map(|expr| expr.parse())
+            .collect::<Result<_>>()?;
+        Ok(())
+    }
+
+    fn get_filter_exprs(&self) -> Vec<String> {
+        self.filter_exprs.iter().map(|e| e.to_string()).collect()
+    }
+
+    fn clear_filter_exprs(&mut self) {
+        self.filter_exprs.clear();
+    }
+
     fn set_sort_tag(&mut self, sort_order: Self::TagType, reverse: &mut bool) -> bool {
         let sort_order = Some(sort_order);
         if self.sort_order == sort_order {
@@ -134,6 +166,19 @@ impl StateCommon for ProcessState {
         }
     }
 
+    fn get_graph_query(&self, tab: &str, idx: usize, key: &i32) -> Option<GraphQuery> {
+        // Folded rows aggregate processes by name, the pid is just one of them
+        if idx < 2 || self.fold {
+            return None;
+        }
+        let pid = *key;
+        Some(GraphQuery::new(
+            self.get_tag_from_tab_idx(tab, idx),
+            &format!("pid {}", pid),
+            move |model| model.process.processes.get(&pid),
+        ))
+    }
+
     fn get_model(&self) -> Ref<Self::ModelType> {
         self.model.borrow()
     }
@@ -148,8 +193,10 @@ impl StateCommon for ProcessState {
         sort_tags.insert("CPU".into(), &*PROCESS_CPU_TAB);
         sort_tags.insert("Mem".into(), &*PROCESS_MEM_TAB);
         sort_tags.insert("I/O".into(), &*PROCESS_IO_TAB);
+        sort_tags.insert("Tree".into(), &*PROCESS_TREE_TAB);
         Self {
             filter_info: None,
+            filter_exprs: Vec::new(),
             cgroup_filter: None,
             pids_filter: None,
             filter_cache_for_zoom: None,
@@ -158,6 +205,8 @@ impl StateCommon for ProcessState {
             sort_tags,
             reverse: false,
             fold: false,
+            tree: false,
+            collapsed_pids: HashSet::new(),
             model,
         }
     }
@@ -176,6 +225,17 @@ impl ProcessState {
         self.fold = !self.fold;
     }
 
+    pub fn toggle_tree(&mut self) {
+        self.tree = !self.tree;
+    }
+
+    /// Collapses or expands the children of pid in the tree
+    pub fn toggle_collapsed(&mut self, pid: i32) {
+        if !self.collapsed_pids.remove(&pid) {
+            self.collapsed_pids.insert(pid);
+        }
+    }
+
     pub fn handle_state_for_entering_zoom(&mut self, current_selection: String) {
         self.cgroup_filter = Some(current_selection);
         std::mem::swap(&mut self.filter_cache_for_zoom, &mut self.filter_info);
@@ -212,8 +272,21 @@ pub struct ProcessView {
 
 impl ProcessView {
     pub fn new(c: &mut Cursive) -> NamedView<ViewType> {
-        let list = SelectView::<i32>::new();
-        let tabs = vec!["General".into(), "CPU".into(), "Mem".into(), "I/O".into()];
+        let mut list = SelectView::<i32>::new();
+        list.set_on_submit(|c, pid: &i32| {
+            let mut view = Self::get_process_view(c);
+            if view.state.borrow().tree {
+                view.state.borrow_mut().toggle_collapsed(*pid);
+                view.refresh(c);
+            }
+        });
+        let mut tabs = vec![
+            "General".into(),
+            "CPU".into(),
+            "Mem".into(),
+            "I/O".into(),
+            "Tree".into(),
+        ];
         let mut tabs_map: HashMap<String, ProcessView> = HashMap::new();
         tabs_map.insert(
             "General".into(),
@@ -239,15 +312,30 @@ impl ProcessView {
                 tab: &*PROCESS_IO_TAB,
             },
         );
+        tabs_map.insert(
+            "Tree".into(),
+            Self {
+                tab: &*PROCESS_TREE_TAB,
+            },
+        );
         let user_data = c
             .user_data::<ViewState>()
             .expect("No data stored in Cursive Object!");
+        let mut process_state = ProcessState::new(user_data.process.clone());
+        // Like the default tabs, custom tabs live as long as the program, so
+        // leaking them gives the same &'static ProcessTab.
+        for (name, items) in user_data.viewrc.tabs_for(DefaultFrontView::Process) {
+            let tab: &'static ProcessTab = Box::leak(Box::new(ProcessTab::new(items)));
+            tabs.push(name.clone());
+            tabs_map.insert(name.clone(), Self { tab });
+            process_state.sort_tags.insert(name, tab);
+        }
         StatsView::new(
             "process",
             tabs,
             tabs_map,
             list,
-            ProcessState::new(user_data.process.clone()),
+            process_state,
             user_data.event_controllers.clone(),
             user_data.cmd_controllers.clone(),
         )
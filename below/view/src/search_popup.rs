@@ -0,0 +1,115 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cursive::event::Key;
+use cursive::theme::BaseColor;
+use cursive::theme::Color;
+use cursive::theme::ColorStyle;
+use cursive::theme::Style;
+use cursive::utils::markup::StyledString;
+use cursive::view::View;
+use cursive::views::Dialog;
+use cursive::views::EditView;
+use cursive::views::OnEventView;
+use cursive::Cursive;
+
+use crate::stats_view::StatsView;
+use crate::stats_view::ViewBridge;
+
+/// Byte ranges of the non-overlapping occurrences of pattern in text. Case
+/// insensitive unless pattern has an uppercase letter, like vim's smartcase.
+pub(crate) fn find_matches(text: &str, pattern: &str) -> Vec<(usize, usize)> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    // ASCII lowercasing keeps byte offsets valid in text.
+    let (text, pattern) = if pattern.chars().any(char::is_uppercase) {
+        (text.to_owned(), pattern.to_owned())
+    } else {
+        (text.to_ascii_lowercase(), pattern.to_ascii_lowercase())
+    };
+    text.match_indices(&pattern)
+        .map(|(start, m)| (start, start + m.len()))
+        .collect()
+}
+
+/// Restyle the occurrences of pattern in row so that they stand out.
+pub(crate) fn highlight_matches(row: StyledString, pattern: &str) -> StyledString {
+    let matches = find_matches(row.source(), pattern);
+    if matches.is_empty() {
+        return row;
+    }
+    let highlight: Style = ColorStyle::new(
+        Color::Dark(BaseColor::Black),
+        Color::Light(BaseColor::Yellow),
+    )
+    .into();
+    let mut res = StyledString::new();
+    let mut offset = 0;
+    for span in row.spans() {
+        let end = offset + span.content.len();
+        let mut pos = offset;
+        let mut append = |from: usize, to: usize, style: Style| {
+            if from < to {
+                res.append_styled(&span.content[from - offset..to - offset], style);
+            }
+        };
+        for &(match_start, match_end) in &matches {
+            let (start, stop) = (match_start.max(pos), match_end.min(end));
+            if start < stop {
+                append(pos, start, *span.attr);
+                append(start, stop, Style::merge(&[*span.attr, highlight]));
+                pos = stop;
+            }
+        }
+        append(pos, end, *span.attr);
+        offset = end;
+    }
+    res
+}
+
+// Set the search pattern and move to the first match starting from the
+// selected row, so that the selection follows what is typed.
+fn set_search<V: 'static + ViewBridge>(c: &mut Cursive, pattern: &str) {
+    let found = {
+        let mut view = StatsView::<V>::get_view(c);
+        view.set_search((!pattern.is_empty()).then(|| pattern.to_owned()));
+        view.select_search_match(c, false, true)
+    };
+    StatsView::<V>::refresh_myself(c);
+    if !found && !pattern.is_empty() {
+        StatsView::<V>::cp_warn(c, &format!("Pattern not found: {}", pattern));
+    }
+}
+
+pub fn new<V: 'static + ViewBridge>(pattern: Option<String>) -> impl View {
+    let editview = EditView::new()
+        .content(pattern.unwrap_or_default())
+        .on_edit(|c, text, _cursor| set_search::<V>(c, text))
+        .on_submit(|c, _text| {
+            c.pop_layer();
+        });
+
+    OnEventView::new(
+        Dialog::new()
+            .title("Search")
+            .padding_lrtb(1, 1, 1, 0)
+            .content(editview)
+            .dismiss_button("Close"),
+    )
+    .on_event(Key::Esc, |c| {
+        c.pop_layer();
+        set_search::<V>(c, "");
+    })
+}
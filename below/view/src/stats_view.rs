@@ -18,6 +18,8 @@ use std::cell::RefMut;
 use std::collections::HashMap;
 use std::rc::Rc;
 
+use anyhow::bail;
+use anyhow::Result;
 use common::logutil::get_last_log_to_display;
 use common::logutil::CPMsgRecord;
 use cursive::event::Event;
@@ -40,6 +42,8 @@ use cursive::Cursive;
 
 use crate::command_palette::CommandPalette;
 use crate::controllers::Controllers;
+use crate::graph_popup::GraphQuery;
+use crate::search_popup;
 use crate::tab_view::TabView;
 
 pub struct ColumnTitles {
@@ -78,6 +82,22 @@ pub trait StateCommon {
         false
     }
 
+    /// Add a filter expression such as `cpu.usage_pct > 10`. Rows must match
+    /// all filter expressions as well as the column filter.
+    fn add_filter_expr(&mut self, _expr: &str) -> Result<()> {
+        bail!("Filter expressions are not supported in this view")
+    }
+    /// Replace the filter expressions with exprs. Nothing is changed if any
+    /// of exprs is invalid.
+    fn set_filter_exprs(&mut self, _exprs: &[String]) -> Result<()> {
+        bail!("Filter expressions are not supported in this view")
+    }
+    /// Active filter expressions in the order they were added.
+    fn get_filter_exprs(&self) -> Vec<String> {
+        Vec::new()
+    }
+    fn clear_filter_exprs(&mut self) {}
+
     /// Set the sorting tag to common state
     /// Return true on success, false if current tab doest support sorting.
     fn set_sort_tag(&mut self, _tag: Self::TagType, _reverse: &mut bool) -> bool {
@@ -90,6 +110,12 @@ pub trait StateCommon {
         false
     }
 
+    /// How to graph the cell at the given tab, column index and row key
+    /// over time. None if the cell cannot be graphed.
+    fn get_graph_query(&self, _tab: &str, _idx: usize, _key: &Self::KeyType) -> Option<GraphQuery> {
+        None
+    }
+
     fn get_model(&self) -> Ref<Self::ModelType>;
     fn get_model_mut(&self) -> RefMut<Self::ModelType>;
     fn new(model: Rc<RefCell<Self::ModelType>>) -> Self;
@@ -179,6 +205,8 @@ pub struct StatsView<V: 'static + ViewBridge> {
     pub state: Rc<RefCell<V::StateType>>,
     pub reverse_sort: bool,
     pub event_controllers: Rc<RefCell<HashMap<Event, Controllers>>>,
+    // Pattern highlighted in rows and jumped to with search_next/search_prev
+    search: Option<String>,
 }
 
 impl<V: 'static + ViewBridge> ViewWrapper for StatsView<V> {
@@ -295,6 +323,7 @@ impl<V: 'static + ViewBridge> StatsView<V> {
             state: Rc::new(RefCell::new(state)),
             reverse_sort: true,
             event_controllers,
+            search: None,
         }
     }
 
@@ -436,7 +465,14 @@ impl<V: 'static + ViewBridge> StatsView<V> {
                 .tab_view_map
                 .get_mut(&cur_tab)
                 .unwrap_or_else(|| panic!("Fail to query data from tab {}", cur_tab));
-            select_view.add_all(tab_detail.get_rows(&self.state.borrow(), Some(horizontal_offset)));
+            let mut rows = tab_detail.get_rows(&self.state.borrow(), Some(horizontal_offset));
+            if let Some(pattern) = &self.search {
+                rows = rows
+                    .into_iter()
+                    .map(|(row, key)| (search_popup::highlight_matches(row, pattern), key))
+                    .collect();
+            }
+            select_view.add_all(rows);
 
             // This will trigger on_select handler, but handler will not be able to
             // find the current StatsView from cursive, presumably because we are
@@ -466,6 +502,55 @@ impl<V: 'static + ViewBridge> StatsView<V> {
         self.get_list_scroll_view().scroll_to_important_area();
     }
 
+    pub fn get_search(&self) -> Option<&String> {
+        self.search.as_ref()
+    }
+
+    pub fn set_search(&mut self, search: Option<String>) {
+        self.get_cmd_palette().set_search(search.clone());
+        self.search = search;
+    }
+
+    /// Select the closest row after the selected one, or before it if
+    /// backward, that matches the search pattern, wrapping around. The
+    /// selected row itself is considered first if from_current. Returns
+    /// false if no row matches.
+    pub fn select_search_match(
+        &mut self,
+        c: &mut Cursive,
+        backward: bool,
+        from_current: bool,
+    ) -> bool {
+        let pattern = match &self.search {
+            Some(pattern) => pattern.clone(),
+            None => return false,
+        };
+        let mut select_view = self.get_detail_view();
+        let len = select_view.len();
+        let cur = select_view.selected_id().unwrap_or(0);
+        let first_step = if from_current { 0 } else { 1 };
+        let found = (first_step..first_step + len)
+            .map(|step| {
+                if backward {
+                    (cur + len - step) % len
+                } else {
+                    (cur + step) % len
+                }
+            })
+            .find(|idx| {
+                select_view.get_item(*idx).map_or(false, |(label, _)| {
+                    !search_popup::find_matches(label, &pattern).is_empty()
+                })
+            });
+        match found {
+            Some(idx) => {
+                select_view.set_selection(idx)(c);
+                true
+            }
+            None => false,
+        }
+    }
+
     // Chaining call. Use for construction to get initial data.
     pub fn feed_data(mut self, c: &mut Cursive) -> Self {
         self.refresh(c);
@@ -499,4 +584,12 @@ impl<V: 'static + ViewBridge> StatsView<V> {
     pub fn cp_filter(c: &mut Cursive, filter_info: Option<(String, String)>) {
         Self::get_view(c).get_cmd_palette().set_filter(filter_info);
     }
+
+    /// Convenience function to show the filter expressions of the state on
+    /// CommandPalette.
+    pub fn cp_filter_exprs(c: &mut Cursive) {
+        let mut view = Self::get_view(c);
+        let exprs = view.state.borrow().get_filter_exprs();
+        view.get_cmd_palette().set_filter_exprs(exprs);
+    }
 }
@@ -19,6 +19,7 @@ use cursive::view::Nameable;
 use cursive::view::View;
 use cursive::views::TextView;
 use cursive::Cursive;
+use model::CollectorHealthModel;
 
 use crate::ViewState;
 
@@ -26,6 +27,25 @@ fn get_spacing() -> &'static str {
     "     "
 }
 
+/// Collection latency of the sample, plus its errors and lost exitstat events
+/// if any, so that one can tell whether below is slowing down the host.
+fn get_collector_health(collector: &CollectorHealthModel) -> StyledString {
+    let red = cursive::theme::Color::Light(cursive::theme::BaseColor::Red);
+    let mut health_str = StyledString::plain(format!(
+        "Collect: {}",
+        collector
+            .collect_latency_usec
+            .map_or_else(|| "?".to_owned(), common::util::convert_duration)
+    ));
+    if let Some(errors) = collector.errors.filter(|errors| *errors > 0) {
+        health_str.append_styled(format!(" Errors: {}", errors), red);
+    }
+    if let Some(lost) = collector.exitstat_lost_events.filter(|lost| *lost > 0) {
+        health_str.append_styled(format!(" Lost events: {}", lost), red);
+    }
+    health_str
+}
+
 fn get_content(c: &mut Cursive) -> impl Into<StyledString> {
     let view_state = &c
         .user_data::<ViewState>()
@@ -62,6 +82,11 @@ fn get_content(c: &mut Cursive) -> impl Into<StyledString> {
     header_str.append_plain(get_spacing());
     header_str.append_plain(view_state.view_mode_str());
 
+    if let Some(collector) = view_state.model.borrow().collector.as_ref() {
+        header_str.append_plain(get_spacing());
+        header_str.append(get_collector_health(collector));
+    }
+
     header_str
 }
 
@@ -98,7 +98,7 @@ impl SystemTab for SystemCpu {
                             line.append_plain(" ");
                             line
                         }),
-                    "".to_owned(),
+                    scm.idx.to_string(),
                 )
             })
             .collect()
@@ -115,21 +115,20 @@ impl SystemTab for SystemMem {
         enum_iterator::all::<MemoryModelFieldId>()
             .map(|field_id| {
                 let mut line = StyledString::new();
-                let item =
-                    ViewItem::from_default(field_id).update(Rc::new().width(FIELD_NAME_WIDTH));
+                let item = ViewItem::from_default(field_id.clone())
+                    .update(Rc::new().width(FIELD_NAME_WIDTH));
                 line.append_plain(item.config.render_title());
                 line.append_plain(" ");
                 line.append(item.update(Rc::new().width(FIELD_WIDTH)).render(&model.mem));
-                line
+                (line, field_id.to_string())
             })
-            .filter(|s| {
+            .filter(|(s, _)| {
                 if let Some((_, filter)) = &state.filter_info {
                     s.source().contains(filter)
                 } else {
                     true
                 }
             })
-            .map(|s| (s.clone(), "".into()))
             .collect()
     }
 }
@@ -144,21 +143,20 @@ impl SystemTab for SystemVm {
         enum_iterator::all::<VmModelFieldId>()
             .map(|field_id| {
                 let mut line = StyledString::new();
-                let item =
-                    ViewItem::from_default(field_id).update(Rc::new().width(FIELD_NAME_WIDTH));
+                let item = ViewItem::from_default(field_id.clone())
+                    .update(Rc::new().width(FIELD_NAME_WIDTH));
                 line.append_plain(item.config.render_title());
                 line.append_plain(" ");
                 line.append(item.update(Rc::new().width(FIELD_WIDTH)).render(&model.vm));
-                line
+                (line, field_id.to_string())
             })
-            .filter(|s| {
+            .filter(|(s, _)| {
                 if let Some((_, filter)) = &state.filter_info {
                     s.source().contains(filter)
                 } else {
                     true
                 }
             })
-            .map(|s| (s.clone(), "".into()))
             .collect()
     }
 }
@@ -217,21 +215,20 @@ impl SystemTab for SystemKsm {
             enum_iterator::all::<KsmModelFieldId>()
                 .map(|field_id| {
                     let mut line = StyledString::new();
-                    let item =
-                        ViewItem::from_default(field_id).update(Rc::new().width(FIELD_NAME_WIDTH));
+                    let item = ViewItem::from_default(field_id.clone())
+                        .update(Rc::new().width(FIELD_NAME_WIDTH));
                     line.append_plain(item.config.render_title());
                     line.append_plain(" ");
                     line.append(item.update(Rc::new().width(FIELD_WIDTH)).render(ksm_model));
-                    line
+                    (line, field_id.to_string())
                 })
-                .filter(|s| {
+                .filter(|(s, _)| {
                     if let Some((_, filter)) = &state.filter_info {
                         s.source().contains(filter)
                     } else {
                         true
                     }
                 })
-                .map(|s| (s.clone(), "".into()))
                 .collect()
         } else {
             Vec::new()
@@ -296,6 +293,48 @@ impl SystemTab for SystemDisk {
     }
 }
 
+/// Renders corresponding Fields From SystemModel.
+type SystemViewItem = ViewItem<model::SystemModelFieldId>;
+
+/// A tab defined in belowrc. Like the Mem tab, each field is a row.
+#[derive(Default, Clone)]
+pub struct SystemCustom {
+    pub view_items: Vec<SystemViewItem>,
+}
+
+impl SystemCustom {
+    pub fn new(view_items: Vec<SystemViewItem>) -> Self {
+        Self { view_items }
+    }
+}
+
+impl SystemTab for SystemCustom {
+    fn get_rows(&self, state: &SystemState, _offset: Option<usize>) -> Vec<(StyledString, String)> {
+        let model = state.get_model();
+
+        self.view_items
+            .iter()
+            .map(|item| {
+                let mut line = StyledString::new();
+                let width = item.config.render_config.width.unwrap_or(FIELD_WIDTH);
+                let item = item.clone().update(Rc::new().width(FIELD_NAME_WIDTH));
+                line.append_plain(item.config.render_title());
+                line.append_plain(" ");
+                let key = item.field_id.to_string();
+                line.append(item.update(Rc::new().width(width)).render(&model));
+                (line, key)
+            })
+            .filter(|(s, _)| {
+                if let Some((_, filter)) = &state.filter_info {
+                    s.source().contains(filter)
+                } else {
+                    true
+                }
+            })
+            .collect()
+    }
+}
+
 /// Renders corresponding Fields From BtrfsModel.
 type BtrfsViewItem = ViewItem<model::BtrfsModelFieldId>;
 
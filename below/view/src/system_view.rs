@@ -18,6 +18,7 @@ use std::cell::RefMut;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::rc::Rc;
+use std::str::FromStr;
 
 use cursive::utils::markup::StyledString;
 use cursive::view::Nameable;
@@ -32,13 +33,16 @@ use model::MemoryModelFieldId;
 use model::SingleCpuModelFieldId;
 use model::SingleDiskModelFieldId;
 use model::SingleSlabModelFieldId;
+use model::SystemModelFieldId;
 use model::VmModelFieldId;
 
+use crate::graph_popup::GraphQuery;
 use crate::stats_view::ColumnTitles;
 use crate::stats_view::StateCommon;
 use crate::stats_view::StatsView;
 use crate::stats_view::ViewBridge;
 use crate::system_tabs::*;
+use crate::viewrc::DefaultFrontView;
 use crate::ViewState;
 
 pub type ViewType = StatsView<SystemView>;
@@ -55,6 +59,8 @@ pub struct SystemState {
     pub sort_order: Option<SystemStateFieldId>,
     pub sort_tags: HashMap<String, default_tabs::SystemTabs>,
     pub reverse: bool,
+    // Names of the tabs defined in belowrc
+    pub custom_tabs: HashSet<String>,
 }
 
 #[derive(PartialEq)]
@@ -66,6 +72,7 @@ pub enum SystemStateFieldId {
     Vm(VmModelFieldId),
     Slab(SingleSlabModelFieldId),
     Ksm(KsmModelFieldId),
+    System(SystemModelFieldId),
 }
 
 impl std::fmt::Display for SystemStateFieldId {
@@ -78,6 +85,7 @@ impl std::fmt::Display for SystemStateFieldId {
             Self::Vm(field) => write!(f, "{}", field),
             Self::Slab(field) => write!(f, "{}", field),
             Self::Ksm(field) => write!(f, "{}", field),
+            Self::System(field) => write!(f, "{}", field),
         }
     }
 }
@@ -130,6 +138,10 @@ impl StateCommon for SystemState {
                     .expect("Tag out of range"),
             ),
             "Ksm" => SystemStateFieldId::Ksm(KsmModelFieldId::FullScans),
+            // Custom tabs have the same 'Field' and 'Value' columns as Mem
+            _ if self.custom_tabs.contains(tab) => {
+                SystemStateFieldId::System(SystemModelFieldId::Hostname)
+            }
             _ => panic!("bug: got unsupported tab {}", tab),
         }
     }
@@ -172,13 +184,77 @@ impl StateCommon for SystemState {
     }
 
     fn set_sort_string(&mut self, selection: &str, reverse: &mut bool) -> bool {
-        use std::str::FromStr;
         match BtrfsModelFieldId::from_str(selection) {
             Ok(field_id) => self.set_sort_tag(SystemStateFieldId::Btrfs(field_id), reverse),
             Err(_) => false,
         }
     }
 
+    fn get_graph_query(&self, tab: &str, idx: usize, key: &String) -> Option<GraphQuery> {
+        let key = key.clone();
+        match tab {
+            "CPU" => {
+                let field_id = enum_iterator::all::<SingleCpuModelFieldId>().nth(idx)?;
+                let cpu = key.parse::<i32>().ok()?;
+                let entity = if cpu == -1 {
+                    "total cpu".to_owned()
+                } else {
+                    format!("cpu {}", cpu)
+                };
+                Some(GraphQuery::new(field_id, &entity, move |model| {
+                    if cpu == -1 {
+                        Some(&model.system.total_cpu)
+                    } else {
+                        model.system.cpus.get(&(cpu as u32))
+                    }
+                }))
+            }
+            // Rows of these tabs are fields of a single model
+            "Mem" => Some(GraphQuery::new(
+                MemoryModelFieldId::from_str(&key).ok()?,
+                "system",
+                |model| Some(&model.system.mem),
+            )),
+            "Vm" => Some(GraphQuery::new(
+                VmModelFieldId::from_str(&key).ok()?,
+                "system",
+                |model| Some(&model.system.vm),
+            )),
+            "Ksm" => Some(GraphQuery::new(
+                KsmModelFieldId::from_str(&key).ok()?,
+                "system",
+                |model| model.system.ksm.as_ref(),
+            )),
+            "Disk" => {
+                let field_id = enum_iterator::all::<SingleDiskModelFieldId>().nth(idx)?;
+                let entity = format!("disk {}", key);
+                Some(GraphQuery::new(field_id, &entity, move |model| {
+                    model.system.disks.values().find(|sdm| {
+                        format!("{}.{}", sdm.major.unwrap_or(0), sdm.minor.unwrap_or(0)) == key
+                    })
+                }))
+            }
+            "Btrfs" => {
+                let field_id = enum_iterator::all::<BtrfsModelFieldId>().nth(idx)?;
+                let entity = format!("subvolume {}", key);
+                Some(GraphQuery::new(field_id, &entity, move |model| {
+                    model
+                        .system
+                        .btrfs
+                        .as_ref()?
+                        .values()
+                        .find(|bmodel| bmodel.name.as_ref() == Some(&key))
+                }))
+            }
+            _ if self.custom_tabs.contains(tab) => Some(GraphQuery::new(
+                SystemModelFieldId::from_str(&key).ok()?,
+                "system",
+                |model| Some(&model.system),
+            )),
+            _ => None,
+        }
+    }
+
     fn get_model(&self) -> Ref<Self::ModelType> {
         self.model.borrow()
     }
@@ -211,6 +287,7 @@ pub enum SystemView {
     Ksm(SystemKsm),
     Disk(SystemDisk),
     Btrfs(SystemBtrfs),
+    Custom(SystemCustom),
 }
 
 impl SystemView {
@@ -233,7 +310,7 @@ impl SystemView {
             }
         });
 
-        let tabs = vec![
+        let mut tabs = vec![
             "CPU".into(),
             "Mem".into(),
             "Vm".into(),
@@ -253,12 +330,18 @@ impl SystemView {
         let user_data = c
             .user_data::<ViewState>()
             .expect("No data stored in Cursive Object!");
+        let mut system_state = SystemState::new(user_data.system.clone());
+        for (name, items) in user_data.viewrc.tabs_for(DefaultFrontView::System) {
+            tabs.push(name.clone());
+            tabs_map.insert(name.clone(), SystemView::Custom(SystemCustom::new(items)));
+            system_state.custom_tabs.insert(name);
+        }
         StatsView::new(
             "system",
             tabs,
             tabs_map,
             list,
-            SystemState::new(user_data.system.clone()),
+            system_state,
             user_data.event_controllers.clone(),
             user_data.cmd_controllers.clone(),
         )
@@ -283,6 +366,7 @@ impl SystemView {
             Self::Ksm(inner) => Box::new(inner.clone()),
             Self::Disk(inner) => Box::new(inner.clone()),
             Self::Btrfs(inner) => Box::new(inner.clone()),
+            Self::Custom(inner) => Box::new(inner.clone()),
         }
     }
 }
@@ -307,9 +391,12 @@ impl ViewBridge for SystemView {
     fn on_select_update_cmd_palette(
         _view: &Self::StateType,
         selected_key: &String,
-        _current_tab: &str,
+        current_tab: &str,
         _selected_column: usize,
     ) -> String {
-        selected_key.clone()
+        match current_tab {
+            "Disk" | "Btrfs" => selected_key.clone(),
+            _ => "".to_owned(),
+        }
     }
 }
@@ -0,0 +1,309 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use chrono::DateTime;
+use chrono::Local;
+use cursive::theme::BaseColor;
+use cursive::theme::Color;
+use cursive::theme::ColorStyle;
+use cursive::utils::markup::StyledString;
+use cursive::view::Nameable;
+use cursive::view::View;
+use cursive::views::TextView;
+use cursive::Cursive;
+use model::Sample;
+use store::Advance;
+
+use crate::ViewMode;
+use crate::ViewState;
+
+/// Interval between samples above which the interval is shown as a gap,
+/// relative to the usual interval.
+const GAP_FACTOR: u32 = 3;
+/// Same threshold as the highlighting of pressure columns.
+const HIGH_PRESSURE_PCT: f64 = 40.0;
+/// Time spent checking samples for high pressure on each refresh, so that
+/// the view stays responsive while a large store is scanned.
+const SCAN_BUDGET: Duration = Duration::from_millis(20);
+
+/// Recorded range of the store, drawn as a bar at the bottom in replay.
+pub struct Timeline {
+    start: SystemTime,
+    end: SystemTime,
+    // Usual interval between samples, None until there are two samples
+    interval: Option<Duration>,
+    // Periods with no sample recorded
+    gaps: Vec<(SystemTime, SystemTime)>,
+    // Samples not checked for high pressure yet, oldest first. Reading every
+    // sample of a store at once would take too long, so they are checked a
+    // few at a time on each refresh.
+    unscanned: VecDeque<SystemTime>,
+    // Samples with high cpu, io or memory pressure on the root cgroup
+    high_pressure: Vec<SystemTime>,
+    // Notes added with the annotate command, by timestamp of their sample
+    annotations: BTreeMap<SystemTime, String>,
+}
+
+impl Timeline {
+    /// Build the timeline of the samples in the store of adv from its
+    /// timestamps. None if the store is empty or cannot list its samples.
+    pub fn new(adv: &mut Advance) -> Option<Self> {
+        Self::from_timestamps(&adv.get_sample_timestamps(None)?)
+    }
+
+    /// Timeline of samples at timestamps, sorted oldest first.
+    pub fn from_timestamps(timestamps: &[SystemTime]) -> Option<Self> {
+        let mut durations: Vec<Duration> = intervals(timestamps).map(|(_, _, d)| d).collect();
+        durations.sort_unstable();
+        let mut timeline = Self {
+            start: *timestamps.first()?,
+            end: *timestamps.last()?,
+            interval: durations.get(durations.len() / 2).copied(),
+            gaps: Vec::new(),
+            unscanned: timestamps.iter().copied().collect(),
+            high_pressure: Vec::new(),
+            annotations: BTreeMap::new(),
+        };
+        timeline.add_gaps(timestamps);
+        Some(timeline)
+    }
+
+    fn add_gaps(&mut self, timestamps: &[SystemTime]) {
+        if let Some(interval) = self.interval {
+            self.gaps.extend(
+                intervals(timestamps)
+                    .filter(|(_, _, d)| *d > interval * GAP_FACTOR)
+                    .map(|(older, newer, _)| (older, newer)),
+            );
+        }
+    }
+
+    /// Extend the timeline with the samples recorded since it was built,
+    /// sorted oldest first.
+    pub fn extend(&mut self, timestamps: &[SystemTime]) {
+        let mut timestamps: Vec<SystemTime> = timestamps
+            .iter()
+            .filter(|timestamp| **timestamp > self.end)
+            .copied()
+            .collect();
+        let last = match timestamps.last() {
+            Some(last) => *last,
+            None => return,
+        };
+        self.unscanned.extend(&timestamps);
+        timestamps.insert(0, self.end);
+        if self.interval.is_none() {
+            self.interval = intervals(&timestamps).map(|(_, _, d)| d).min();
+        }
+        self.add_gaps(&timestamps);
+        self.end = last;
+    }
+
+    /// Check the samples not checked yet for high pressure, oldest first,
+    /// until deadline. is_high tells whether the sample at a timestamp is
+    /// under high pressure.
+    pub fn scan(&mut self, deadline: Instant, mut is_high: impl FnMut(SystemTime) -> bool) {
+        while Instant::now() < deadline {
+            let timestamp = match self.unscanned.pop_front() {
+                Some(timestamp) => timestamp,
+                None => return,
+            };
+            if is_high(timestamp) {
+                self.high_pressure.push(timestamp);
+            }
+        }
+    }
+
+    /// Attach text to the sample at timestamp, or remove its annotation if
+    /// text is empty.
+    pub fn annotate(&mut self, timestamp: SystemTime, text: &str) {
+        if text.is_empty() {
+            self.annotations.remove(&timestamp);
+        } else {
+            self.annotations.insert(timestamp, text.to_owned());
+        }
+    }
+
+    pub fn annotation(&self, timestamp: SystemTime) -> Option<&str> {
+        self.annotations.get(&timestamp).map(String::as_str)
+    }
+
+    fn span(&self) -> Duration {
+        self.end.duration_since(self.start).unwrap_or_default()
+    }
+
+    /// Column of timestamp in a bar of width columns.
+    fn column(&self, timestamp: SystemTime, width: usize) -> Option<usize> {
+        let offset = timestamp.duration_since(self.start).ok()?;
+        if timestamp > self.end || width == 0 {
+            return None;
+        }
+        let span = self.span().as_secs_f64();
+        if span == 0.0 {
+            return Some(0);
+        }
+        Some(((offset.as_secs_f64() / span * width as f64) as usize).min(width - 1))
+    }
+
+    /// Render the bar in width columns: '─' for recorded periods, ' ' for
+    /// gaps, '!' for samples with high pressure, 'A' for annotated samples,
+    /// 'M' for the sample marked with mark_sample and '█' for the current
+    /// position.
+    pub fn render_bar(
+        &self,
+        width: usize,
+        current: SystemTime,
+        marked: Option<SystemTime>,
+    ) -> StyledString {
+        let mut cells = vec!['─'; width];
+        let span = self.span().as_secs_f64();
+        for (col, cell) in cells.iter_mut().enumerate() {
+            // Blank the columns entirely within a gap
+            let from = span * col as f64 / width as f64;
+            let to = span * (col + 1) as f64 / width as f64;
+            let in_gap = self.gaps.iter().any(|(older, newer)| {
+                let older = older.duration_since(self.start).unwrap_or_default();
+                let newer = newer.duration_since(self.start).unwrap_or_default();
+                older.as_secs_f64() < from && to < newer.as_secs_f64()
+            });
+            if in_gap {
+                *cell = ' ';
+            }
+        }
+        for timestamp in &self.high_pressure {
+            if let Some(col) = self.column(*timestamp, width) {
+                cells[col] = '!';
+            }
+        }
+        for timestamp in self.annotations.keys() {
+            if let Some(col) = self.column(*timestamp, width) {
+                cells[col] = 'A';
+            }
+        }
+        if let Some(col) = marked.and_then(|marked| self.column(marked, width)) {
+            cells[col] = 'M';
+        }
+        let current = self.column(current, width);
+        if let Some(col) = current {
+            cells[col] = '█';
+        }
+
+        let mut bar = StyledString::new();
+        for (col, cell) in cells.into_iter().enumerate() {
+            let text = cell.to_string();
+            if Some(col) == current {
+                bar.append_styled(text, Color::Light(BaseColor::Cyan));
+                continue;
+            }
+            match cell {
+                '!' => bar.append_styled(text, Color::Light(BaseColor::Red)),
+                'A' => bar.append_styled(text, Color::Light(BaseColor::Green)),
+                'M' => bar.append_styled(
+                    text,
+                    ColorStyle::new(
+                        Color::Dark(BaseColor::Black),
+                        Color::Light(BaseColor::Yellow),
+                    ),
+                ),
+                _ => bar.append_plain(text),
+            }
+        }
+        bar
+    }
+}
+
+/// Whether the root cgroup of sample has high cpu, io or memory pressure.
+fn is_high_pressure(sample: &Sample) -> bool {
+    sample.cgroup.pressure.as_ref().map_or(false, |p| {
+        [p.cpu.some.avg10, p.io.full.avg10, p.memory.full.avg10]
+            .iter()
+            .any(|pct| pct.map_or(false, |pct| pct > HIGH_PRESSURE_PCT))
+    })
+}
+
+/// Consecutive timestamps and the interval between them.
+fn intervals(
+    timestamps: &[SystemTime],
+) -> impl Iterator<Item = (SystemTime, SystemTime, Duration)> + '_ {
+    timestamps
+        .windows(2)
+        .filter_map(|w| Some((w[0], w[1], w[1].duration_since(w[0]).ok()?)))
+}
+
+fn format_time(timestamp: SystemTime) -> String {
+    DateTime::<Local>::from(timestamp)
+        .format("%m/%d %H:%M")
+        .to_string()
+}
+
+fn get_content(c: &mut Cursive) -> StyledString {
+    // Leave room for the panel borders
+    let width = c.screen_size().x.saturating_sub(2);
+    let view_state = c
+        .user_data::<ViewState>()
+        .expect("No data stored in Cursive object!");
+    let timeline = match &view_state.timeline {
+        Some(timeline) => timeline,
+        None => return StyledString::plain("No timeline available for this store"),
+    };
+    let start = format_time(timeline.start);
+    let end = format_time(timeline.end);
+    let bar_width = width.saturating_sub(start.len() + end.len() + 2);
+    let mut content = StyledString::plain(format!("{} ", start));
+    content.append(
+        timeline.render_bar(
+            bar_width,
+            view_state.timestamp,
+            view_state
+                .marked_model
+                .as_ref()
+                .map(|model| model.timestamp),
+        ),
+    );
+    content.append_plain(format!(" {}", end));
+    if let Some(annotation) = timeline.annotation(view_state.timestamp) {
+        content.append_plain(format!("\nAnnotation: {}", annotation));
+    }
+    content
+}
+
+pub fn refresh(c: &mut Cursive) {
+    let view_state = c
+        .user_data::<ViewState>()
+        .expect("No data stored in Cursive object!");
+    if let (ViewMode::Replay(adv), Some(timeline)) = (&view_state.mode, &mut view_state.timeline) {
+        let mut adv = adv.borrow_mut();
+        // Follow the store as below record keeps writing to it
+        if let Some(timestamps) = adv.get_sample_timestamps(Some(timeline.end)) {
+            timeline.extend(&timestamps);
+        }
+        timeline.scan(Instant::now() + SCAN_BUDGET, |timestamp| {
+            adv.peek_sample(timestamp)
+                .map_or(false, |(_, frame)| is_high_pressure(&frame.sample))
+        });
+    }
+    let content = get_content(c);
+    // Only replay has a timeline
+    c.call_on_name("timeline", |v: &mut TextView| v.set_content(content));
+}
+
+pub fn new(c: &mut Cursive) -> impl View {
+    TextView::new(get_content(c)).with_name("timeline")
+}
@@ -12,10 +12,37 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use base_render::HasRenderConfig;
+use base_render::RenderConfig;
+use base_render::RenderFormat;
+use common::util::ConfigIssue;
+use model::FieldId;
+use model::ModelFieldId;
+use model::SingleCgroupModelFieldId;
+use model::SingleProcessModelFieldId;
+use model::SystemModelFieldId;
 use serde::Deserialize;
+use toml::value::Value;
 
 use super::get_belowrc_filename;
 use super::get_belowrc_view_section_key;
+use crate::render::HasViewStyle;
+use crate::render::ViewItem;
+
+/// Names of the built-in tabs, which custom tabs must not shadow.
+const CGROUP_TABS: &[&str] = &["General", "CPU", "Mem", "I/O", "Pressure", "Properties"];
+const PROCESS_TABS: &[&str] = &["General", "CPU", "Mem", "I/O", "Tree"];
+const SYSTEM_TABS: &[&str] = &["CPU", "Mem", "Vm", "Slab", "Ksm", "Disk", "Btrfs"];
 
 /// Enum of supported front view.
 // We didn't re-use the MainViewState because we don't want to
@@ -40,6 +67,87 @@ pub struct SummaryViewExtraRow {
     pub items: Vec<SummaryViewExtraRowItem>,
 }
 
+#[derive(Default, Deserialize)]
+pub struct CustomTabItem {
+    pub field_id: String,
+    // Column title, defaults to the title of the field.
+    pub alias: Option<String>,
+    pub width: Option<usize>,
+    // Render format, e.g. readable_size or precision:2.
+    pub format: Option<String>,
+}
+
+/// A user defined tab showing the given fields as columns, or as rows of the
+/// system view.
+#[derive(Default, Deserialize)]
+pub struct CustomTab {
+    pub name: String,
+    pub items: Vec<CustomTabItem>,
+}
+
+impl CustomTab {
+    /// Build the ViewItems of this tab. Fails on the first invalid item.
+    pub fn get_view_items<F>(&self) -> Result<Vec<ViewItem<F>>>
+    where
+        F: FieldId + FromStr<Err = anyhow::Error>,
+        F::Queriable: Sized + HasRenderConfig + HasViewStyle,
+    {
+        if self.items.is_empty() {
+            bail!("Tab '{}' has no items", self.name);
+        }
+        self.items
+            .iter()
+            .map(|item| {
+                let field_id = F::from_str(&item.field_id).with_context(|| {
+                    format!(
+                        "Invalid field id '{}' in tab '{}'",
+                        item.field_id, self.name
+                    )
+                })?;
+                let format = match &item.format {
+                    Some(format) => Some(RenderFormat::from_str(format).map_err(|e| {
+                        anyhow!("{} for '{}' in tab '{}'", e, item.field_id, self.name)
+                    })?),
+                    None => None,
+                };
+                Ok(ViewItem::from_default(field_id).update(RenderConfig {
+                    title: item.alias.clone(),
+                    width: item.width,
+                    format,
+                    ..Default::default()
+                }))
+            })
+            .collect()
+    }
+}
+
+/// Drop the invalid tabs of a view, returning why they were dropped.
+fn validate_custom_tabs<F>(tabs: &mut Option<Vec<CustomTab>>, builtin_tabs: &[&str]) -> Vec<String>
+where
+    F: FieldId + FromStr<Err = anyhow::Error>,
+    F::Queriable: Sized + HasRenderConfig + HasViewStyle,
+{
+    let mut errors = Vec::new();
+    let mut names: HashSet<String> = builtin_tabs.iter().map(|name| name.to_string()).collect();
+    if let Some(tabs) = tabs.as_mut() {
+        tabs.retain(|tab| {
+            let res = if !names.insert(tab.name.clone()) {
+                Err(anyhow!("Duplicate tab name '{}'", tab.name))
+            } else {
+                tab.get_view_items::<F>().map(|_| ())
+            };
+            match res {
+                Ok(()) => true,
+                Err(e) => {
+                    errors.push(format!("{:#}", e));
+                    false
+                }
+            }
+        });
+    }
+    errors
+}
+
 /// Runtime configuration on the below view.
 #[derive(Default, Deserialize)]
 pub struct ViewRc {
@@ -53,6 +161,23 @@ pub struct ViewRc {
     pub cgroup_name_width: Option<usize>,
     // Extra rows to add in the summary view.
     pub summary_view_extra_rows: Option<Vec<SummaryViewExtraRow>>,
+    // Named lists of filter expressions, saved and loaded with the
+    // filter_save and filter_load commands.
+    pub filter_presets: Option<HashMap<String, Vec<String>>>,
+    // Extra tabs appended to the cgroup, process and system views.
+    pub cgroup_tabs: Option<Vec<CustomTab>>,
+    pub process_tabs: Option<Vec<CustomTab>>,
+    pub system_tabs: Option<Vec<CustomTab>>,
+}
+
+/// The [view] section of a belowrc along with its keys that are not
+/// attributes of ViewRc
+#[derive(Deserialize)]
+struct CheckedViewRc {
+    #[serde(flatten)]
+    viewrc: ViewRc,
+    #[serde(flatten)]
+    unknown_keys: BTreeMap<String, Value>,
 }
 
 impl ViewRc {
@@ -68,7 +193,10 @@ impl ViewRc {
                     if let Some(viewrc_val) = belowrc_val.get(get_belowrc_view_section_key()) {
                         // Got the [view] section, let's see if we can deserialize it to ViewRc
                         match viewrc_val.to_owned().try_into::<ViewRc>() {
-                            Ok(viewrc) => (viewrc, None),
+                            Ok(mut viewrc) => {
+                                let error = viewrc.validate();
+                                (viewrc, error)
+                            }
                             Err(e) => (
                                 Default::default(),
                                 Some(format!(
@@ -90,4 +218,138 @@ impl ViewRc {
             _ => (Default::default(), None),
         }
     }
+
+    /// Drop the custom tabs that are invalid so the views only get valid
+    /// ones. Returns the reasons they were dropped.
+    pub fn validate(&mut self) -> Option<String> {
+        let errors: Vec<String> = self
+            .validate_tabs()
+            .into_iter()
+            .flat_map(|(tabs, errors)| errors.into_iter().map(move |e| format!("{}: {}", tabs, e)))
+            .collect();
+        if errors.is_empty() {
+            None
+        } else {
+            Some(format!(
+                "Invalid tabs in belowrc::{}: {}",
+                get_belowrc_view_section_key(),
+                errors.join("; ")
+            ))
+        }
+    }
+
+    /// The custom tabs appended to view, with their items. Invalid ones were
+    /// dropped by validate().
+    pub fn tabs_for<F>(&self, view: DefaultFrontView) -> Vec<(String, Vec<ViewItem<F>>)>
+    where
+        F: FieldId + FromStr<Err = anyhow::Error>,
+        F::Queriable: Sized + HasRenderConfig + HasViewStyle,
+    {
+        let tabs = match view {
+            DefaultFrontView::Cgroup => &self.cgroup_tabs,
+            DefaultFrontView::Process => &self.process_tabs,
+            DefaultFrontView::System => &self.system_tabs,
+        };
+        tabs.iter()
+            .flatten()
+            .filter_map(|tab| Some((tab.name.clone(), tab.get_view_items().ok()?)))
+            .collect()
+    }
+
+    /// Drop the invalid custom tabs, returning why they were dropped by key.
+    fn validate_tabs(&mut self) -> [(&'static str, Vec<String>); 3] {
+        [
+            (
+                "cgroup_tabs",
+                validate_custom_tabs::<SingleCgroupModelFieldId>(
+                    &mut self.cgroup_tabs,
+                    CGROUP_TABS,
+                ),
+            ),
+            (
+                "process_tabs",
+                validate_custom_tabs::<SingleProcessModelFieldId>(
+                    &mut self.process_tabs,
+                    PROCESS_TABS,
+                ),
+            ),
+            (
+                "system_tabs",
+                validate_custom_tabs::<SystemModelFieldId>(&mut self.system_tabs, SYSTEM_TABS),
+            ),
+        ]
+    }
+
+    /// Check the [view] section of a belowrc, including what new() and
+    /// validate() silently drop.
+    pub fn check(viewrc_val: &Value) -> Vec<ConfigIssue> {
+        let section = get_belowrc_view_section_key();
+        let checked = match viewrc_val.to_owned().try_into::<CheckedViewRc>() {
+            Ok(checked) => checked,
+            Err(e) => return vec![ConfigIssue::error(section, e.to_string())],
+        };
+        let mut issues: Vec<ConfigIssue> = checked
+            .unknown_keys
+            .into_keys()
+            .map(|key| ConfigIssue::warning(format!("{}.{}", section, key), "Unknown key, ignored"))
+            .collect();
+        let mut viewrc = checked.viewrc;
+        for (tabs, errors) in viewrc.validate_tabs() {
+            issues.extend(
+                errors
+                    .into_iter()
+                    .map(|e| ConfigIssue::error(format!("{}.{}", section, tabs), e)),
+            );
+        }
+        // The summary view skips these
+        for row in viewrc.summary_view_extra_rows.iter().flatten() {
+            for item in &row.items {
+                if let Err(e) = ModelFieldId::from_str(&item.field_id) {
+                    issues.push(ConfigIssue::warning(
+                        format!("{}.summary_view_extra_rows", section),
+                        format!("Invalid field id '{}': {:#}", item.field_id, e),
+                    ));
+                }
+            }
+        }
+        issues
+    }
+
+    /// Save a filter preset to the [view] section of the belowrc at path,
+    /// replacing any preset with the same name. The rest of the file,
+    /// including comments and formatting, is kept as is.
+    pub fn save_filter_preset(path: &Path, name: &str, filters: &[String]) -> Result<()> {
+        let mut belowrc = match std::fs::read_to_string(path) {
+            Ok(belowrc_str) => belowrc_str
+                .parse::<toml_edit::Document>()
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml_edit::Document::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+        let presets = belowrc
+            .entry(get_belowrc_view_section_key())
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .context("view section of belowrc is not a table")?
+            .entry("filter_presets")
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .context("filter_presets of belowrc is not a table")?;
+        presets.insert(
+            name,
+            toml_edit::value(
+                filters
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<toml_edit::Array>(),
+            ),
+        );
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, belowrc.to_string())
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
 }